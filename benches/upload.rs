@@ -0,0 +1,146 @@
+//! Benchmarks of large uploads running concurrently, and of other requests served meanwhile.
+//!
+//! Run them with `make bench` on two commits to compare how a change to the upload handling
+//! affects both, e.g. with `cargo bench --bench upload -- --save-baseline before` and
+//! `--baseline before` afterwards.
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use port_check::free_local_port;
+use reqwest::blocking::{multipart, Client};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Size of each uploaded file
+const UPLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+/// miniserve accepting uploads into a temporary directory, which is killed once dropped
+struct Server {
+    child: Child,
+    url: String,
+    _dir: TempDir,
+}
+
+impl Server {
+    fn start() -> Self {
+        let dir = TempDir::new().expect("Couldn't create a temp dir for benchmarks");
+        let port = free_local_port().expect("Couldn't find a free local port");
+        let child = Command::cargo_bin("miniserve")
+            .expect("Couldn't find the miniserve binary")
+            .arg(dir.path())
+            .arg("-p")
+            .arg(port.to_string())
+            .arg("--upload-files")
+            .arg("--overwrite-files")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Couldn't start miniserve");
+
+        let url = format!("http://localhost:{}", port);
+        while reqwest::blocking::get(&url).is_err() {
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        Server {
+            child,
+            url,
+            _dir: dir,
+        }
+    }
+
+    fn upload(&self, client: &Client, name: &str, content: Vec<u8>) {
+        let part = multipart::Part::bytes(content).file_name(name.to_string());
+        client
+            .post(format!("{}/upload?path=/", self.url).as_str())
+            .multipart(multipart::Form::new().part("file_to_upload", part))
+            .send()
+            .and_then(|res| res.error_for_status())
+            .expect("Upload failed");
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+/// Time to upload several large files at once
+fn concurrent_uploads(c: &mut Criterion) {
+    let server = Arc::new(Server::start());
+    let client = Client::new();
+    let content = vec![0x5a; UPLOAD_SIZE];
+
+    let mut group = c.benchmark_group("concurrent_uploads");
+    group.sample_size(10);
+    for uploads in &[1, 4, 8] {
+        group.throughput(Throughput::Bytes((UPLOAD_SIZE * uploads) as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(uploads),
+            uploads,
+            |b, &uploads| {
+                b.iter(|| {
+                    let uploads = (0..uploads)
+                        .map(|i| {
+                            let (server, client, content) =
+                                (server.clone(), client.clone(), content.clone());
+                            thread::spawn(move || {
+                                server.upload(&client, &format!("{}.bin", i), content)
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    for upload in uploads {
+                        upload.join().unwrap();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Latency of a directory listing while large files are uploaded all the time
+fn listing_during_uploads(c: &mut Criterion) {
+    let server = Arc::new(Server::start());
+    let client = Client::new();
+    let content = vec![0x5a; UPLOAD_SIZE];
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let uploads = (0..4)
+        .map(|i| {
+            let (server, client, content, stop) = (
+                server.clone(),
+                client.clone(),
+                content.clone(),
+                stop.clone(),
+            );
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    server.upload(&client, &format!("{}.bin", i), content.clone());
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    c.bench_function("listing_during_uploads", |b| {
+        b.iter(|| {
+            client
+                .get(server.url.as_str())
+                .send()
+                .and_then(|res| res.error_for_status())
+                .expect("Listing failed")
+        })
+    });
+
+    stop.store(true, Ordering::Relaxed);
+    for upload in uploads {
+        upload.join().unwrap();
+    }
+}
+
+criterion_group!(benches, concurrent_uploads, listing_during_uploads);
+criterion_main!(benches);
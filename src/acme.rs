@@ -0,0 +1,138 @@
+use acme_lib::persist::FilePersist;
+use acme_lib::{create_p384_key, Directory, DirectoryUrl};
+use rustls::sign::CertifiedKey;
+use rustls::{ClientHello, NoClientAuth, ResolvesServerCert, ServerConfig};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::errors::{self, ContextualError};
+use crate::tls;
+
+/// Port Let's Encrypt sends HTTP-01 challenges to
+pub const CHALLENGE_PORT: u16 = 80;
+
+/// Certificates are renewed once they're valid for less than this many days
+const RENEW_DAYS: i64 = 30;
+
+/// Time between checks whether the certificate has to be renewed
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Certificate for `--acme-domain`, obtained and renewed from Let's Encrypt with the HTTP-01
+/// challenge
+#[derive(Clone)]
+pub struct Acme {
+    domain: String,
+    email: String,
+
+    /// Directory keeping the account key and certificates across restarts
+    cache_dir: PathBuf,
+
+    /// Proofs of the pending challenges by their token
+    challenges: Arc<Mutex<HashMap<String, String>>>,
+
+    /// Certificate currently served, if one was obtained yet
+    certificate: Arc<RwLock<Option<CertifiedKey>>>,
+}
+
+/// Hands the current certificate to rustls for every connection, so that renewed
+/// certificates are used without restarting
+struct CertificateResolver(Arc<RwLock<Option<CertifiedKey>>>);
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<CertifiedKey> {
+        self.0.read().unwrap().clone()
+    }
+}
+
+impl Acme {
+    pub fn new(domain: String, email: String, cache_dir: PathBuf) -> Self {
+        Acme {
+            domain,
+            email,
+            cache_dir,
+            challenges: Arc::default(),
+            certificate: Arc::default(),
+        }
+    }
+
+    /// Returns the TLS configuration serving the certificate of the domain
+    pub fn server_config(&self) -> ServerConfig {
+        let mut config = ServerConfig::new(NoClientAuth::new());
+        config.cert_resolver = Arc::new(CertificateResolver(self.certificate.clone()));
+        config
+    }
+
+    /// Serves the certificate cached for the domain, or obtains a new one if there is none or
+    /// it expires soon.
+    ///
+    /// Obtaining a certificate blocks until Let's Encrypt validated the challenge, which is
+    /// answered by the plain HTTP server.
+    fn renew(&self) -> Result<(), ContextualError> {
+        let acme_error =
+            |e: acme_lib::Error| ContextualError::AcmeError(self.domain.clone(), e.to_string());
+
+        std::fs::create_dir_all(&self.cache_dir).map_err(|e| {
+            ContextualError::IoError(format!("Failed to create {}", self.cache_dir.display()), e)
+        })?;
+        let persist = FilePersist::new(&self.cache_dir);
+        let directory =
+            Directory::from_url(persist, DirectoryUrl::LetsEncrypt).map_err(acme_error)?;
+        let account = directory.account(&self.email).map_err(acme_error)?;
+
+        let cached = account.certificate(&self.domain).map_err(acme_error)?;
+        let certificate = match cached {
+            Some(certificate) if certificate.valid_days_left() >= RENEW_DAYS => certificate,
+            _ => {
+                log::info!("Obtaining a certificate for {}", self.domain);
+                let mut order = account.new_order(&self.domain, &[]).map_err(acme_error)?;
+                let csr = loop {
+                    if let Some(csr) = order.confirm_validations() {
+                        break csr;
+                    }
+                    for authorization in order.authorizations().map_err(acme_error)? {
+                        let challenge = authorization.http_challenge();
+                        self.challenges
+                            .lock()
+                            .unwrap()
+                            .insert(challenge.http_token().to_string(), challenge.http_proof());
+                        challenge.validate(5000).map_err(acme_error)?;
+                    }
+                    order.refresh().map_err(acme_error)?;
+                };
+                self.challenges.lock().unwrap().clear();
+
+                let order = csr
+                    .finalize_pkey(create_p384_key(), 5000)
+                    .map_err(acme_error)?;
+                order.download_and_save_cert().map_err(acme_error)?
+            }
+        };
+
+        let certified_key = tls::certified_key(
+            certificate.certificate().as_bytes(),
+            certificate.private_key().as_bytes(),
+        )
+        .map_err(|cause| ContextualError::AcmeError(self.domain.clone(), cause.to_string()))?;
+        *self.certificate.write().unwrap() = Some(certified_key);
+        Ok(())
+    }
+
+    /// Obtains the certificate in the background and renews it whenever it expires soon
+    pub fn spawn_renewal(&self) {
+        let acme = self.clone();
+        thread::spawn(move || loop {
+            if let Err(e) = acme.renew() {
+                errors::log_error_chain(e.to_string());
+            }
+            thread::sleep(CHECK_INTERVAL);
+        });
+    }
+
+    /// Returns the proof of the pending challenge `token`, if any
+    pub fn proof(&self, token: &str) -> Option<String> {
+        self.challenges.lock().unwrap().get(token).cloned()
+    }
+}
@@ -0,0 +1,118 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::http::{header, StatusCode};
+use actix_web::HttpResponse;
+use std::net::IpAddr;
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::renderer;
+
+/// Strips the port, and the brackets of IPv6 addresses, from the value of a `Host` header
+fn host_name(host: &str) -> &str {
+    match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// Returns `true` if `ip` is a loopback, private or link-local address
+fn is_local_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            let first_segment = ip.segments()[0];
+            ip.is_loopback()
+                // Unique local addresses (fc00::/7)
+                || first_segment & 0xfe00 == 0xfc00
+                // Link-local addresses (fe80::/10)
+                || first_segment & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+/// Returns `true` if `host` is one of `allowed_hosts`, `localhost` or a local IP address
+pub fn is_allowed_host(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host_name(host).trim_end_matches('.').to_lowercase();
+
+    host == "localhost"
+        || allowed_hosts.iter().any(|allowed| *allowed == host)
+        || host.parse::<IpAddr>().map_or(false, is_local_ip)
+}
+
+/// Rejects requests for hosts other than the allowed ones, mitigating DNS rebinding
+///
+/// Returns the response to send instead if the request has to be rejected.
+pub fn check_request(req: &ServiceRequest) -> Option<HttpResponse> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    if conf.allowed_hosts.is_empty() {
+        return None;
+    }
+
+    // The raw header is used on purpose: unlike the `Forwarded` headers, it can't be chosen
+    // by scripts of the rebound page
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| req.uri().host())
+        .unwrap_or_default();
+
+    if is_allowed_host(host, &conf.allowed_hosts) {
+        return None;
+    }
+
+    let error = ContextualError::HostNotAllowedError(host.to_string());
+    errors::log_error_chain(error.to_string());
+
+    Some(
+        HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    StatusCode::BAD_REQUEST,
+                    "/",
+                    None,
+                    None,
+                    false,
+                    false,
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            ),
+    )
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    #[rstest(host, expected,
+        case("example.com", true),
+        case("EXAMPLE.com:8080", true),
+        case("example.com.", true),
+        case("files.lan", false),
+        case("localhost:8080", true),
+        case("127.0.0.1:8080", true),
+        case("192.168.1.10", true),
+        case("10.0.0.1", true),
+        case("[::1]:8080", true),
+        case("[fd00::1]", true),
+        case("8.8.8.8", false),
+        case("[2001:db8::1]:8080", false),
+        case("attacker.example", false),
+        case("", false),
+    )]
+    fn allowed_host(host: &str, expected: bool) {
+        assert_eq!(is_allowed_host(host, &["example.com".to_string()]), expected);
+    }
+}
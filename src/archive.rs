@@ -1,15 +1,20 @@
 use actix_web::http::ContentEncoding;
-use libflate::gzip::Encoder;
+use chrono::{DateTime, Datelike, Local, Timelike};
+use crc32fast::Hasher;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
 use serde::Deserialize;
-use std::fs::File;
-use std::io::{Cursor, Read, Write};
-use std::path::Path;
-use std::path::PathBuf;
+use std::convert::TryFrom;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use strum_macros::{Display, EnumIter, EnumString};
 use tar::Builder;
-use zip::{write, ZipWriter};
 
 use crate::errors::ContextualError;
+use crate::ignore_files::IgnoreFiles;
 
 /// Available archive methods
 #[derive(Deserialize, Clone, Copy, EnumIter, EnumString, Display)]
@@ -24,6 +29,38 @@ pub enum ArchiveMethod {
 
     /// Regular zip
     Zip,
+
+    /// 7z archive, as used by 7-Zip
+    #[serde(rename = "7z")]
+    #[strum(serialize = "7z")]
+    SevenZip,
+}
+
+/// Compression level of an archive chosen by the client, from 0 (stored) to 9 (smallest).
+///
+/// `store` is accepted as an alias for 0.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct CompressionLevel(u32);
+
+impl CompressionLevel {
+    pub fn level(self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<String> for CompressionLevel {
+    type Error = String;
+
+    fn try_from(level: String) -> Result<Self, Self::Error> {
+        match level.as_str() {
+            "store" => Ok(CompressionLevel(0)),
+            _ => match level.parse() {
+                Ok(level) if level <= 9 => Ok(CompressionLevel(level)),
+                _ => Err(format!("invalid compression level {}", level)),
+            },
+        }
+    }
 }
 
 impl ArchiveMethod {
@@ -32,6 +69,7 @@ impl ArchiveMethod {
             ArchiveMethod::TarGz => "tar.gz",
             ArchiveMethod::Tar => "tar",
             ArchiveMethod::Zip => "zip",
+            ArchiveMethod::SevenZip => "7z",
         }
         .to_string()
     }
@@ -41,6 +79,7 @@ impl ArchiveMethod {
             ArchiveMethod::TarGz => "application/gzip",
             ArchiveMethod::Tar => "application/tar",
             ArchiveMethod::Zip => "application/zip",
+            ArchiveMethod::SevenZip => "application/x-7z-compressed",
         }
         .to_string()
     }
@@ -50,14 +89,22 @@ impl ArchiveMethod {
             ArchiveMethod::TarGz => ContentEncoding::Gzip,
             ArchiveMethod::Tar => ContentEncoding::Identity,
             ArchiveMethod::Zip => ContentEncoding::Identity,
+            ArchiveMethod::SevenZip => ContentEncoding::Identity,
         }
     }
 
-    pub fn is_enabled(self, tar_enabled: bool, tar_gz_enabled: bool, zip_enabled: bool) -> bool {
+    pub fn is_enabled(
+        self,
+        tar_enabled: bool,
+        tar_gz_enabled: bool,
+        zip_enabled: bool,
+        seven_zip_enabled: bool,
+    ) -> bool {
         match self {
             ArchiveMethod::TarGz => tar_gz_enabled,
             ArchiveMethod::Tar => tar_enabled,
             ArchiveMethod::Zip => zip_enabled,
+            ArchiveMethod::SevenZip => seven_zip_enabled,
         }
     }
 
@@ -66,10 +113,20 @@ impl ArchiveMethod {
     /// Recursively includes all files and subdirectories.
     ///
     /// If `skip_symlinks` is `true`, symlinks fill not be followed and will just be ignored.
+    ///
+    /// If `ignore_files` is set, entries excluded by ignore files are left out.
+    ///
+    /// If `selection` is set, only the entries of `dir` with these names are included.
+    ///
+    /// If `compression` is set, it overrides the default compression of tar.gz archives and
+    /// compresses the files in zip and 7z archives, which are stored uncompressed by default.
     pub fn create_archive<T, W>(
         self,
         dir: T,
         skip_symlinks: bool,
+        ignore_files: Option<&IgnoreFiles>,
+        selection: Option<&[OsString]>,
+        compression: Option<CompressionLevel>,
         out: W,
     ) -> Result<(), ContextualError>
     where
@@ -78,24 +135,66 @@ impl ArchiveMethod {
     {
         let dir = dir.as_ref();
         match self {
-            ArchiveMethod::TarGz => tar_gz(dir, skip_symlinks, out),
-            ArchiveMethod::Tar => tar_dir(dir, skip_symlinks, out),
-            ArchiveMethod::Zip => zip_dir(dir, skip_symlinks, out),
+            ArchiveMethod::TarGz => {
+                let compression = compression
+                    .map_or_else(Compression::default, |level| Compression::new(level.0));
+                tar_gz(
+                    dir,
+                    skip_symlinks,
+                    ignore_files,
+                    selection,
+                    compression,
+                    out,
+                )
+            }
+            ArchiveMethod::Tar => tar_dir(dir, skip_symlinks, ignore_files, selection, out),
+            ArchiveMethod::Zip => {
+                let compression = compression
+                    .filter(|level| level.0 > 0)
+                    .map(|level| Compression::new(level.0));
+                zip_dir(
+                    dir,
+                    skip_symlinks,
+                    ignore_files,
+                    selection,
+                    compression,
+                    out,
+                )
+            }
+            ArchiveMethod::SevenZip => {
+                let compression = compression
+                    .filter(|level| level.0 > 0)
+                    .map(|level| Compression::new(level.0));
+                seven_zip_dir(
+                    dir,
+                    skip_symlinks,
+                    ignore_files,
+                    selection,
+                    compression,
+                    out,
+                )
+            }
         }
     }
 }
 
 /// Write a gzipped tarball of `dir` in `out`.
-fn tar_gz<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+fn tar_gz<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+    compression: Compression,
+    out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
-    let mut out = Encoder::new(out).map_err(|e| ContextualError::IoError("GZIP".to_string(), e))?;
+    let mut out = GzEncoder::new(out, compression);
 
-    tar_dir(dir, skip_symlinks, &mut out)?;
+    tar_dir(dir, skip_symlinks, ignore_files, selection, &mut out)?;
 
     out.finish()
-        .into_result()
         .map_err(|e| ContextualError::IoError("GZIP finish".to_string(), e))?;
 
     Ok(())
@@ -124,7 +223,13 @@ where
 /// ├── f
 /// └── g
 /// ```
-fn tar_dir<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+fn tar_dir<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+    out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
@@ -138,8 +243,15 @@ where
         )
     })?;
 
-    tar(dir, directory.to_string(), skip_symlinks, out)
-        .map_err(|e| ContextualError::ArchiveCreationError("tarball".to_string(), Box::new(e)))
+    tar(
+        dir,
+        directory.to_string(),
+        skip_symlinks,
+        ignore_files,
+        selection,
+        out,
+    )
+    .map_err(|e| ContextualError::ArchiveCreationError("tarball".to_string(), Box::new(e)))
 }
 
 /// Writes a tarball of `dir` in `out`.
@@ -149,6 +261,8 @@ fn tar<W>(
     src_dir: &Path,
     inner_folder: String,
     skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
     out: W,
 ) -> Result<(), ContextualError>
 where
@@ -159,17 +273,26 @@ where
     tar_builder.follow_symlinks(!skip_symlinks);
 
     // Recursively adds the content of src_dir into the archive stream
-    tar_builder
-        .append_dir_all(inner_folder, src_dir)
-        .map_err(|e| {
-            ContextualError::IoError(
-                format!(
-                    "Failed to append the content of {} to the TAR archive",
-                    src_dir.to_str().unwrap_or("file")
-                ),
-                e,
-            )
-        })?;
+    match (ignore_files, selection) {
+        (None, None) => tar_builder.append_dir_all(inner_folder, src_dir),
+        _ => append_dir_filtered(
+            &mut tar_builder,
+            Path::new(&inner_folder),
+            src_dir,
+            skip_symlinks,
+            ignore_files,
+            selection,
+        ),
+    }
+    .map_err(|e| {
+        ContextualError::IoError(
+            format!(
+                "Failed to append the content of {} to the TAR archive",
+                src_dir.to_str().unwrap_or("file")
+            ),
+            e,
+        )
+    })?;
 
     // Finish the archive
     tar_builder.into_inner().map_err(|e| {
@@ -179,6 +302,111 @@ where
     Ok(())
 }
 
+/// Recursively adds the content of `src_dir` to the archive as `path`, like
+/// `Builder::append_dir_all`, but leaves out entries excluded by `ignore_files` and, directly
+/// in `src_dir`, entries not in `selection`.
+fn append_dir_filtered<W>(
+    tar_builder: &mut Builder<W>,
+    path: &Path,
+    src_dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    tar_builder.append_dir(path, src_dir)?;
+
+    for entry in std::fs::read_dir(src_dir)? {
+        let entry = entry?;
+        if !is_selected(&entry.file_name(), selection) {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        let is_dir = if skip_symlinks {
+            entry.file_type()?.is_dir()
+        } else {
+            entry_path.is_dir()
+        };
+
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&entry_path, is_dir) {
+                continue;
+            }
+        }
+
+        let entry_name = path.join(entry.file_name());
+        if is_dir {
+            append_dir_filtered(
+                tar_builder,
+                &entry_name,
+                &entry_path,
+                skip_symlinks,
+                ignore_files
+                    .map(|ignore_files| ignore_files.with_dir(&entry_path))
+                    .as_ref(),
+                None,
+            )?;
+        } else {
+            tar_builder.append_path_with_name(&entry_path, &entry_name)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the combined size of the files an archive of `dir` would contain, before
+/// compression and without the overhead of the archive format.
+///
+/// The arguments are the same as for `ArchiveMethod::create_archive`.
+pub fn content_size(
+    dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+) -> u64 {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_selected(&entry.file_name(), selection))
+        .filter(|entry| {
+            !(skip_symlinks
+                && entry
+                    .file_type()
+                    .map_or(false, |file_type| file_type.is_symlink()))
+        })
+        .filter_map(|entry| Some((entry.path(), entry.path().metadata().ok()?)))
+        .filter(|(path, metadata)| {
+            ignore_files.map_or(true, |ignore_files| {
+                !ignore_files.is_ignored(path, metadata.is_dir())
+            })
+        })
+        .map(|(path, metadata)| {
+            if metadata.is_dir() {
+                let ignore_files = ignore_files.map(|ignore_files| ignore_files.with_dir(&path));
+                content_size(&path, skip_symlinks, ignore_files.as_ref(), None)
+            } else if metadata.is_file() {
+                metadata.len()
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Returns `true` if the entry called `name` is part of `selection`, if any
+fn is_selected(name: &OsStr, selection: Option<&[OsString]>) -> bool {
+    selection.map_or(true, |selection| {
+        selection.iter().any(|selected| selected == name)
+    })
+}
+
 /// Write a zip of `dir` in `out`.
 ///
 /// The target directory will be saved as a top-level directory in the archive.
@@ -202,121 +430,130 @@ where
 /// ├── f
 /// └── g
 /// ```
-fn create_zip_from_directory<W>(
-    out: W,
-    directory: &Path,
+///
+/// Files are compressed with `compression` if it is set, otherwise they are stored as they are.
+fn zip_dir<W>(
+    dir: &Path,
     skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+    compression: Option<Compression>,
+    out: W,
 ) -> Result<(), ContextualError>
 where
-    W: std::io::Write + std::io::Seek,
+    W: std::io::Write,
 {
-    let options = write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-    let mut paths_queue: Vec<PathBuf> = vec![directory.to_path_buf()];
-    let zip_root_folder_name = directory.file_name().ok_or_else(|| {
+    let inner_folder = dir.file_name().ok_or_else(|| {
         ContextualError::InvalidPathError("Directory name terminates in \"..\"".to_string())
     })?;
 
-    let mut zip_writer = ZipWriter::new(out);
-    let mut buffer = Vec::new();
-    while !paths_queue.is_empty() {
-        let next = paths_queue.pop().ok_or_else(|| {
-            ContextualError::ArchiveCreationDetailError("Could not get path from queue".to_string())
-        })?;
-        let current_dir = next.as_path();
-        let directory_entry_iterator = std::fs::read_dir(current_dir)
-            .map_err(|e| ContextualError::IoError("Could not read directory".to_string(), e))?;
-        let zip_directory = Path::new(zip_root_folder_name).join(
-            current_dir.strip_prefix(directory).map_err(|_| {
-                ContextualError::ArchiveCreationDetailError(
-                    "Could not append base directory".to_string(),
-                )
-            })?,
-        );
-
-        for entry in directory_entry_iterator {
-            let entry_path = entry
-                .ok()
-                .ok_or_else(|| {
-                    ContextualError::InvalidPathError(
-                        "Directory name terminates in \"..\"".to_string(),
-                    )
-                })?
-                .path();
-            let entry_metadata = std::fs::metadata(entry_path.clone()).map_err(|e| {
-                ContextualError::IoError("Could not get file metadata".to_string(), e)
-            })?;
-
-            if entry_metadata.file_type().is_symlink() && skip_symlinks {
-                continue;
-            }
-            let current_entry_name = entry_path.file_name().ok_or_else(|| {
-                ContextualError::InvalidPathError("Invalid file or direcotory name".to_string())
-            })?;
-            if entry_metadata.is_file() {
-                let mut f = File::open(&entry_path)
-                    .map_err(|e| ContextualError::IoError("Could not open file".to_string(), e))?;
-                f.read_to_end(&mut buffer).map_err(|e| {
-                    ContextualError::IoError("Could not read from file".to_string(), e)
-                })?;
-                let relative_path = zip_directory.join(current_entry_name).into_os_string();
-                zip_writer
-                    .start_file(relative_path.to_string_lossy(), options)
-                    .map_err(|_| {
-                        ContextualError::ArchiveCreationDetailError(
-                            "Could not add file path to ZIP".to_string(),
-                        )
-                    })?;
-                zip_writer.write(buffer.as_ref()).map_err(|_| {
-                    ContextualError::ArchiveCreationDetailError(
-                        "Could not write file to ZIP".to_string(),
-                    )
-                })?;
-                buffer.clear();
-            } else if entry_metadata.is_dir() {
-                let relative_path = zip_directory.join(current_entry_name).into_os_string();
-                zip_writer
-                    .add_directory(relative_path.to_string_lossy(), options)
-                    .map_err(|_| {
-                        ContextualError::ArchiveCreationDetailError(
-                            "Could not add directory path to ZIP".to_string(),
-                        )
-                    })?;
-                paths_queue.push(entry_path.clone());
-            }
-        }
-    }
-
-    zip_writer.finish().map_err(|_| {
-        ContextualError::ArchiveCreationDetailError(
-            "Could not finish writing ZIP archive".to_string(),
+    let directory = inner_folder.to_str().ok_or_else(|| {
+        ContextualError::InvalidPathError(
+            "Directory name contains invalid UTF-8 characters".to_string(),
         )
     })?;
-    Ok(())
+
+    let mut zip = ZipStream::new(out, compression);
+    append_dir_to_zip(
+        &mut zip,
+        directory,
+        dir,
+        skip_symlinks,
+        ignore_files,
+        selection,
+    )
+    .and_then(|()| zip.finish())
+    .map_err(|e| {
+        ContextualError::ArchiveCreationError(
+            "zip".to_string(),
+            Box::new(ContextualError::IoError(
+                format!(
+                    "Failed to append the content of {} to the ZIP archive",
+                    dir.display()
+                ),
+                e,
+            )),
+        )
+    })
 }
 
-/// Writes a zip of `dir` in `out`.
-///
-/// The content of `src_dir` will be saved in the archive as the  folder named .
-fn zip_data<W>(src_dir: &Path, skip_symlinks: bool, mut out: W) -> Result<(), ContextualError>
+/// Recursively adds the content of `src_dir` to the zip as `path`, leaving out entries
+/// excluded by `ignore_files` and, directly in `src_dir`, entries not in `selection`.
+fn append_dir_to_zip<W>(
+    zip: &mut ZipStream<W>,
+    path: &str,
+    src_dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+) -> io::Result<()>
 where
     W: std::io::Write,
 {
-    let mut data = Vec::new();
-    let memory_file = Cursor::new(&mut data);
-    create_zip_from_directory(memory_file, &src_dir.to_path_buf(), skip_symlinks).map_err(|e| {
-        ContextualError::ArchiveCreationError(
-            "Failed to create the ZIP archive".to_string(),
-            Box::new(e),
-        )
-    })?;
+    zip.add_directory(path, src_dir.metadata()?.modified().ok())?;
+
+    for entry in std::fs::read_dir(src_dir)? {
+        let entry = entry?;
+        if !is_selected(&entry.file_name(), selection) {
+            continue;
+        }
+        if skip_symlinks && entry.file_type()?.is_symlink() {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        // Unreadable entries and broken symlinks are left out
+        let metadata = match entry_path.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&entry_path, metadata.is_dir()) {
+                continue;
+            }
+        }
 
-    out.write_all(data.as_mut_slice())
-        .map_err(|e| ContextualError::IoError("Failed to write the ZIP archive".to_string(), e))?;
+        let entry_name = format!("{}/{}", path, entry.file_name().to_string_lossy());
+        if metadata.is_dir() {
+            append_dir_to_zip(
+                zip,
+                &entry_name,
+                &entry_path,
+                skip_symlinks,
+                ignore_files
+                    .map(|ignore_files| ignore_files.with_dir(&entry_path))
+                    .as_ref(),
+                None,
+            )?;
+        } else if metadata.is_file() {
+            zip.add_file(
+                &entry_name,
+                &mut File::open(&entry_path)?,
+                metadata.len(),
+                metadata.modified().ok(),
+            )?;
+        }
+    }
 
     Ok(())
 }
 
-fn zip_dir<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+/// Write a 7z archive of `dir` in `out`, with the same content as a zip of it.
+///
+/// The start of a 7z archive points to the list of its entries at its end, so the archive is
+/// built in a temporary file and only sent once it is complete. With `--archive-cache-dir`,
+/// the complete archive is also cached, so that unchanged directories are sent right away.
+///
+/// Files are compressed with `compression` if it is set, otherwise they are stored as they are.
+fn seven_zip_dir<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+    compression: Option<Compression>,
+    mut out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
@@ -324,12 +561,698 @@ where
         ContextualError::InvalidPathError("Directory name terminates in \"..\"".to_string())
     })?;
 
-    inner_folder.to_str().ok_or_else(|| {
+    let directory = inner_folder.to_str().ok_or_else(|| {
         ContextualError::InvalidPathError(
             "Directory name contains invalid UTF-8 characters".to_string(),
         )
     })?;
 
-    zip_data(dir, skip_symlinks, out)
-        .map_err(|e| ContextualError::ArchiveCreationError("zip".to_string(), Box::new(e)))
+    let archive_error = |e| {
+        ContextualError::ArchiveCreationError(
+            "7z".to_string(),
+            Box::new(ContextualError::IoError(
+                format!(
+                    "Failed to append the content of {} to the 7z archive",
+                    dir.display()
+                ),
+                e,
+            )),
+        )
+    };
+    let temporary = TemporaryFile::create().map_err(archive_error)?;
+    let mut archive = SevenZipWriter::new(&temporary.file, compression).map_err(archive_error)?;
+    append_dir_to_7z(
+        &mut archive,
+        directory,
+        dir,
+        skip_symlinks,
+        ignore_files,
+        selection,
+    )
+    .and_then(|()| archive.finish())
+    .map_err(archive_error)?;
+
+    let mut archive = &temporary.file;
+    archive
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| io::copy(&mut archive, &mut out))
+        .map_err(|e| ContextualError::IoError("Failed to send the 7z archive".to_string(), e))?;
+    Ok(())
+}
+
+/// Recursively adds the content of `src_dir` to the 7z archive as `path`, leaving out entries
+/// excluded by `ignore_files` and, directly in `src_dir`, entries not in `selection`.
+fn append_dir_to_7z<W>(
+    archive: &mut SevenZipWriter<W>,
+    path: &str,
+    src_dir: &Path,
+    skip_symlinks: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    selection: Option<&[OsString]>,
+) -> io::Result<()>
+where
+    W: Write + Seek,
+{
+    archive.add_directory(path, src_dir.metadata()?.modified().ok());
+
+    for entry in std::fs::read_dir(src_dir)? {
+        let entry = entry?;
+        if !is_selected(&entry.file_name(), selection) {
+            continue;
+        }
+        if skip_symlinks && entry.file_type()?.is_symlink() {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        // Unreadable entries and broken symlinks are left out
+        let metadata = match entry_path.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&entry_path, metadata.is_dir()) {
+                continue;
+            }
+        }
+
+        let entry_name = format!("{}/{}", path, entry.file_name().to_string_lossy());
+        if metadata.is_dir() {
+            append_dir_to_7z(
+                archive,
+                &entry_name,
+                &entry_path,
+                skip_symlinks,
+                ignore_files
+                    .map(|ignore_files| ignore_files.with_dir(&entry_path))
+                    .as_ref(),
+                None,
+            )?;
+        } else if metadata.is_file() {
+            archive.add_file(
+                &entry_name,
+                &mut File::open(&entry_path)?,
+                metadata.modified().ok(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// File in the temporary directory, which is removed again once dropped
+struct TemporaryFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl TemporaryFile {
+    fn create() -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("miniserve-{}.partial", nanoid::nanoid!(10)));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(TemporaryFile { path, file })
+    }
+}
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Entry of a 7z archive, as listed in the header at its end
+struct SevenZipEntry {
+    name: String,
+    is_dir: bool,
+    modified: Option<SystemTime>,
+    /// Length of the content of a file, which is part of the packed stream unless it is 0
+    size: u64,
+    crc: u32,
+}
+
+/// Stream which the content of all files of a 7z archive is written to, one after the other
+enum PackedStream<W: Write> {
+    Stored(W),
+    Deflated(DeflateEncoder<W>),
+}
+
+impl<W: Write> Write for PackedStream<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            PackedStream::Stored(out) => out.write(buf),
+            PackedStream::Deflated(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            PackedStream::Stored(out) => out.flush(),
+            PackedStream::Deflated(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Writes a 7z archive whose files are one stream, stored as it is or deflated as a whole.
+///
+/// The archive starts with a start header pointing to the header listing the entries, which
+/// follows the files. It is filled in last, which requires seeking back.
+struct SevenZipWriter<W: Write> {
+    packed: PackedStream<W>,
+    entries: Vec<SevenZipEntry>,
+}
+
+impl<W: Write + Seek> SevenZipWriter<W> {
+    fn new(mut out: W, compression: Option<Compression>) -> io::Result<Self> {
+        out.write_all(&[0; SEVEN_ZIP_START_HEADER_SIZE])?;
+        let packed = match compression {
+            Some(compression) => PackedStream::Deflated(DeflateEncoder::new(out, compression)),
+            None => PackedStream::Stored(out),
+        };
+        Ok(SevenZipWriter {
+            packed,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Adds a directory called `name`, which must not end on a slash
+    fn add_directory(&mut self, name: &str, modified: Option<SystemTime>) {
+        self.entries.push(SevenZipEntry {
+            name: name.to_string(),
+            is_dir: true,
+            modified,
+            size: 0,
+            crc: 0,
+        });
+    }
+
+    /// Adds a file called `name` with the content of `reader`
+    fn add_file<R: Read>(
+        &mut self,
+        name: &str,
+        reader: &mut R,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        let mut hasher = Hasher::new();
+        let size = copy_hashed(reader, &mut self.packed, &mut hasher)?;
+        self.entries.push(SevenZipEntry {
+            name: name.to_string(),
+            is_dir: false,
+            modified,
+            size,
+            crc: hasher.finalize(),
+        });
+        Ok(())
+    }
+
+    /// Writes the header listing all entries and the start header pointing to it, which
+    /// completes the archive
+    fn finish(self) -> io::Result<()> {
+        let (mut out, method) = match self.packed {
+            PackedStream::Stored(out) => (out, SEVEN_ZIP_COPY),
+            PackedStream::Deflated(encoder) => (encoder.finish()?, SEVEN_ZIP_DEFLATE),
+        };
+        let packed_size = out.seek(SeekFrom::Current(0))? - SEVEN_ZIP_START_HEADER_SIZE as u64;
+        let header = seven_zip_header(&self.entries, method, packed_size);
+        out.write_all(&header)?;
+
+        let mut next_header = Vec::with_capacity(20);
+        next_header.extend_from_slice(&packed_size.to_le_bytes());
+        next_header.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        next_header.extend_from_slice(&crc32fast::hash(&header).to_le_bytes());
+
+        let mut start_header = Vec::with_capacity(SEVEN_ZIP_START_HEADER_SIZE);
+        start_header.extend_from_slice(&SEVEN_ZIP_SIGNATURE);
+        start_header.extend_from_slice(&SEVEN_ZIP_VERSION);
+        start_header.extend_from_slice(&crc32fast::hash(&next_header).to_le_bytes());
+        start_header.extend_from_slice(&next_header);
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&start_header)?;
+        out.flush()
+    }
+}
+
+/// Builds the header of a 7z archive listing `entries`, whose files are the consecutive parts
+/// of a single packed stream of `packed_size` bytes, coded with `method`
+fn seven_zip_header(entries: &[SevenZipEntry], method: &[u8], packed_size: u64) -> Vec<u8> {
+    let files = entries
+        .iter()
+        .filter(|entry| entry.size > 0)
+        .collect::<Vec<_>>();
+    let mut header = vec![SEVEN_ZIP_ID_HEADER];
+
+    if !files.is_empty() {
+        header.push(SEVEN_ZIP_ID_MAIN_STREAMS_INFO);
+
+        // The packed stream follows the start header
+        header.push(SEVEN_ZIP_ID_PACK_INFO);
+        write_7z_number(&mut header, 0);
+        write_7z_number(&mut header, 1);
+        header.push(SEVEN_ZIP_ID_SIZE);
+        write_7z_number(&mut header, packed_size);
+        header.push(SEVEN_ZIP_ID_END);
+
+        // It is decoded by a folder of a single coder with one input and one output
+        header.push(SEVEN_ZIP_ID_UNPACK_INFO);
+        header.push(SEVEN_ZIP_ID_FOLDER);
+        write_7z_number(&mut header, 1);
+        header.push(0);
+        write_7z_number(&mut header, 1);
+        header.push(method.len() as u8);
+        header.extend_from_slice(method);
+        header.push(SEVEN_ZIP_ID_CODERS_UNPACK_SIZE);
+        write_7z_number(&mut header, files.iter().map(|file| file.size).sum());
+        header.push(SEVEN_ZIP_ID_END);
+
+        // The files are its consecutive parts, the size of the last one is implied
+        header.push(SEVEN_ZIP_ID_SUBSTREAMS_INFO);
+        header.push(SEVEN_ZIP_ID_NUM_UNPACK_STREAM);
+        write_7z_number(&mut header, files.len() as u64);
+        if files.len() > 1 {
+            header.push(SEVEN_ZIP_ID_SIZE);
+            for file in &files[..files.len() - 1] {
+                write_7z_number(&mut header, file.size);
+            }
+        }
+        header.push(SEVEN_ZIP_ID_CRC);
+        header.push(1);
+        for file in &files {
+            header.extend_from_slice(&file.crc.to_le_bytes());
+        }
+        header.push(SEVEN_ZIP_ID_END);
+
+        header.push(SEVEN_ZIP_ID_END);
+    }
+
+    header.push(SEVEN_ZIP_ID_FILES_INFO);
+    write_7z_number(&mut header, entries.len() as u64);
+
+    // Directories and empty files have no part in the packed stream
+    if files.len() < entries.len() {
+        let empty_streams = bit_vector(entries.iter().map(|entry| entry.size == 0));
+        write_7z_property(&mut header, SEVEN_ZIP_ID_EMPTY_STREAM, &empty_streams);
+        let empty_files = bit_vector(
+            entries
+                .iter()
+                .filter(|entry| entry.size == 0)
+                .map(|entry| !entry.is_dir),
+        );
+        write_7z_property(&mut header, SEVEN_ZIP_ID_EMPTY_FILE, &empty_files);
+    }
+
+    // Names are in UTF-16, each terminated by a null character
+    let mut names = vec![0];
+    for entry in entries {
+        for unit in entry.name.encode_utf16().chain(std::iter::once(0)) {
+            names.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    write_7z_property(&mut header, SEVEN_ZIP_ID_NAME, &names);
+
+    let file_times = entries
+        .iter()
+        .map(|entry| entry.modified.and_then(file_time))
+        .collect::<Vec<_>>();
+    let mut modified = if file_times.iter().all(Option::is_some) {
+        vec![1]
+    } else {
+        let mut modified = vec![0];
+        modified.extend(bit_vector(file_times.iter().map(Option::is_some)));
+        modified
+    };
+    modified.push(0);
+    for file_time in file_times.into_iter().flatten() {
+        modified.extend_from_slice(&file_time.to_le_bytes());
+    }
+    write_7z_property(&mut header, SEVEN_ZIP_ID_MTIME, &modified);
+
+    // Windows attributes, with the unix mode in the upper half like in zip archives
+    let mut attributes = vec![1, 0];
+    for entry in entries {
+        let attribute: u32 = if entry.is_dir {
+            0o040755 << 16 | SEVEN_ZIP_UNIX_EXTENSION | 0x10
+        } else {
+            0o100644 << 16 | SEVEN_ZIP_UNIX_EXTENSION
+        };
+        attributes.extend_from_slice(&attribute.to_le_bytes());
+    }
+    write_7z_property(&mut header, SEVEN_ZIP_ID_ATTRIBUTES, &attributes);
+
+    header.push(SEVEN_ZIP_ID_END);
+    header.push(SEVEN_ZIP_ID_END);
+    header
+}
+
+/// Appends `value` in the variable length encoding of 7z archives, where the number of
+/// leading one bits of the first byte is the number of bytes following it
+fn write_7z_number(out: &mut Vec<u8>, value: u64) {
+    let mut first = 0u8;
+    let mut mask = 0x80u8;
+    let mut length = 0;
+    while length < 8 {
+        if value < 1 << (7 * (length + 1)) {
+            first |= (value >> (8 * length)) as u8;
+            break;
+        }
+        first |= mask;
+        mask >>= 1;
+        length += 1;
+    }
+    out.push(first);
+    out.extend_from_slice(&value.to_le_bytes()[..length]);
+}
+
+/// Appends a property of the files of a 7z archive, preceded by its id and size
+fn write_7z_property(out: &mut Vec<u8>, id: u8, data: &[u8]) {
+    out.push(id);
+    write_7z_number(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+/// Packs `bits` into bytes, starting with the most significant bit
+fn bit_vector(bits: impl Iterator<Item = bool>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, bit) in bits.enumerate() {
+        if i % 8 == 0 {
+            bytes.push(0);
+        }
+        if bit {
+            *bytes.last_mut().unwrap() |= 0x80 >> (i % 8);
+        }
+    }
+    bytes
+}
+
+/// Converts `time` to the Windows file time used by 7z archives, in 100 ns since 1601.
+///
+/// Dates before 1970 are left out.
+fn file_time(time: SystemTime) -> Option<u64> {
+    let since_epoch = time.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    Some(
+        (since_epoch.as_secs() + 11_644_473_600) * 10_000_000
+            + u64::from(since_epoch.subsec_nanos() / 100),
+    )
+}
+
+const SEVEN_ZIP_SIGNATURE: [u8; 6] = [b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c];
+
+/// Version 0.4 of the format
+const SEVEN_ZIP_VERSION: [u8; 2] = [0, 4];
+
+/// Length of the signature, version and start header at the beginning of 7z archives
+const SEVEN_ZIP_START_HEADER_SIZE: usize = 32;
+
+/// Ids of the coders storing data as it is and deflating it
+const SEVEN_ZIP_COPY: &[u8] = &[0x00];
+const SEVEN_ZIP_DEFLATE: &[u8] = &[0x04, 0x01, 0x08];
+
+/// Attribute flag telling that the upper 16 bits hold the unix mode
+const SEVEN_ZIP_UNIX_EXTENSION: u32 = 0x8000;
+
+const SEVEN_ZIP_ID_END: u8 = 0x00;
+const SEVEN_ZIP_ID_HEADER: u8 = 0x01;
+const SEVEN_ZIP_ID_MAIN_STREAMS_INFO: u8 = 0x04;
+const SEVEN_ZIP_ID_FILES_INFO: u8 = 0x05;
+const SEVEN_ZIP_ID_PACK_INFO: u8 = 0x06;
+const SEVEN_ZIP_ID_UNPACK_INFO: u8 = 0x07;
+const SEVEN_ZIP_ID_SUBSTREAMS_INFO: u8 = 0x08;
+const SEVEN_ZIP_ID_SIZE: u8 = 0x09;
+const SEVEN_ZIP_ID_CRC: u8 = 0x0a;
+const SEVEN_ZIP_ID_FOLDER: u8 = 0x0b;
+const SEVEN_ZIP_ID_CODERS_UNPACK_SIZE: u8 = 0x0c;
+const SEVEN_ZIP_ID_NUM_UNPACK_STREAM: u8 = 0x0d;
+const SEVEN_ZIP_ID_EMPTY_STREAM: u8 = 0x0e;
+const SEVEN_ZIP_ID_EMPTY_FILE: u8 = 0x0f;
+const SEVEN_ZIP_ID_NAME: u8 = 0x11;
+const SEVEN_ZIP_ID_MTIME: u8 = 0x14;
+const SEVEN_ZIP_ID_ATTRIBUTES: u8 = 0x15;
+
+/// Entry of a zip archive, as listed in the central directory at its end
+struct ZipEntry {
+    name: String,
+    is_dir: bool,
+    /// Compression method, 0 for stored and 8 for deflated
+    method: u16,
+    dos_date_time: (u16, u16),
+    crc: u32,
+    size: u32,
+    compressed_size: u32,
+    offset: u32,
+}
+
+/// Writes a zip archive entry by entry without ever seeking back, so that it can be streamed.
+///
+/// Files are stored uncompressed unless a compression is given. Since their checksum and
+/// compressed size are only known once they have been written, they follow their data in a
+/// data descriptor. ZIP64 isn't supported, so files and the whole archive are limited to
+/// 4 GiB and 65535 entries.
+struct ZipStream<W> {
+    out: W,
+
+    /// Number of bytes written so far
+    offset: u64,
+
+    /// Compression of files with deflate, if they aren't stored as they are
+    compression: Option<Compression>,
+
+    entries: Vec<ZipEntry>,
+}
+
+impl<W: std::io::Write> ZipStream<W> {
+    fn new(out: W, compression: Option<Compression>) -> Self {
+        ZipStream {
+            out,
+            offset: 0,
+            compression,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a directory called `name`, which must not end on a slash
+    fn add_directory(&mut self, name: &str, modified: Option<SystemTime>) -> io::Result<()> {
+        let entry = self.start_entry(format!("{}/", name), true, modified)?;
+        self.finish_entry(entry, 0, 0, Hasher::new())
+    }
+
+    /// Adds a file called `name` with the content of `reader`, which is `size` bytes long
+    fn add_file<R: Read>(
+        &mut self,
+        name: &str,
+        reader: &mut R,
+        size: u64,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        if size > u64::from(u32::MAX) {
+            return Err(zip_too_large(name));
+        }
+
+        let entry = self.start_entry(name.to_string(), false, modified)?;
+        let start = self.offset;
+        let mut hasher = Hasher::new();
+        let written = match self.compression {
+            Some(compression) => {
+                let mut encoder = DeflateEncoder::new(&mut *self, compression);
+                let written = copy_hashed(reader, &mut encoder, &mut hasher)?;
+                encoder.finish()?;
+                written
+            }
+            None => copy_hashed(reader, self, &mut hasher)?,
+        };
+        let compressed_size = self.offset - start;
+        // The file may have grown while being read
+        if written > u64::from(u32::MAX) || compressed_size > u64::from(u32::MAX) {
+            return Err(zip_too_large(name));
+        }
+
+        self.finish_entry(entry, written as u32, compressed_size as u32, hasher)
+    }
+
+    /// Writes the local header of an entry, whose checksum and size follow its data
+    fn start_entry(
+        &mut self,
+        name: String,
+        is_dir: bool,
+        modified: Option<SystemTime>,
+    ) -> io::Result<ZipEntry> {
+        let offset = u32::try_from(self.offset).map_err(|_| zip_too_large(&name))?;
+        if self.entries.len() >= usize::from(u16::MAX) || name.len() > usize::from(u16::MAX) {
+            return Err(zip_too_large(&name));
+        }
+        let entry = ZipEntry {
+            dos_date_time: dos_date_time(modified),
+            method: if is_dir || self.compression.is_none() {
+                0
+            } else {
+                8
+            },
+            name,
+            is_dir,
+            crc: 0,
+            size: 0,
+            compressed_size: 0,
+            offset,
+        };
+
+        let mut header = Vec::with_capacity(30 + entry.name.len());
+        header.extend_from_slice(&ZIP_LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        header.extend_from_slice(&entry.method.to_le_bytes());
+        header.extend_from_slice(&entry.dos_date_time.1.to_le_bytes());
+        header.extend_from_slice(&entry.dos_date_time.0.to_le_bytes());
+        // Checksum and sizes are only known after the data, they are in the data descriptor
+        header.extend_from_slice(&[0; 12]);
+        header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(entry.name.as_bytes());
+        self.write_all(&header)?;
+
+        Ok(entry)
+    }
+
+    /// Writes the data descriptor of an entry whose data has been written
+    fn finish_entry(
+        &mut self,
+        mut entry: ZipEntry,
+        size: u32,
+        compressed_size: u32,
+        hasher: Hasher,
+    ) -> io::Result<()> {
+        entry.crc = hasher.finalize();
+        entry.size = size;
+        entry.compressed_size = compressed_size;
+
+        let mut descriptor = Vec::with_capacity(16);
+        descriptor.extend_from_slice(&ZIP_DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+        descriptor.extend_from_slice(&entry.crc.to_le_bytes());
+        descriptor.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        descriptor.extend_from_slice(&entry.size.to_le_bytes());
+        self.write_all(&descriptor)?;
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory listing all entries, which completes the archive
+    fn finish(&mut self) -> io::Result<()> {
+        let directory_offset =
+            u32::try_from(self.offset).map_err(|_| zip_too_large("central directory"))?;
+
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            let (mode, dos_attributes) = if entry.is_dir {
+                (0o040755u32, 0x10)
+            } else {
+                (0o100644u32, 0)
+            };
+            directory.extend_from_slice(&ZIP_CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            // Made by unix, so that the mode in the external attributes is used
+            directory.extend_from_slice(&(3u16 << 8 | ZIP_VERSION).to_le_bytes());
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            directory.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            directory.extend_from_slice(&entry.method.to_le_bytes());
+            directory.extend_from_slice(&entry.dos_date_time.1.to_le_bytes());
+            directory.extend_from_slice(&entry.dos_date_time.0.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Lengths of extra field and comment, disk number and internal attributes
+            directory.extend_from_slice(&[0; 8]);
+            directory.extend_from_slice(&(mode << 16 | dos_attributes).to_le_bytes());
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size =
+            u32::try_from(directory.len()).map_err(|_| zip_too_large("central directory"))?;
+        self.write_all(&directory)?;
+
+        let entry_count = (self.entries.len() as u16).to_le_bytes();
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&ZIP_END_SIGNATURE.to_le_bytes());
+        // Numbers of this disk and the disk with the central directory
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&entry_count);
+        end.extend_from_slice(&entry_count);
+        end.extend_from_slice(&directory_size.to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        // Length of the comment
+        end.extend_from_slice(&0u16.to_le_bytes());
+        self.write_all(&end)?;
+
+        self.out.flush()
+    }
+}
+
+impl<W: std::io::Write> Write for ZipStream<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.offset += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Copies all of `reader` to `out` while computing its checksum, returning its length
+fn copy_hashed<R: Read, O: Write>(
+    reader: &mut R,
+    out: &mut O,
+    hasher: &mut Hasher,
+) -> io::Result<u64> {
+    let mut buffer = vec![0; 64 * 1024];
+    let mut written = 0u64;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        out.write_all(&buffer[..read])?;
+        written += read as u64;
+    }
+    Ok(written)
+}
+
+const ZIP_LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const ZIP_DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const ZIP_CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const ZIP_END_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Version 2.0 of the format, which introduced directories and data descriptors
+const ZIP_VERSION: u16 = 20;
+
+/// Checksum and sizes in a data descriptor (bit 3) and names in UTF-8 (bit 11)
+const ZIP_FLAGS: u16 = 1 << 3 | 1 << 11;
+
+fn zip_too_large(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!("{} exceeds the limits of ZIP archives without ZIP64", name),
+    )
+}
+
+/// Converts `time` to the MS-DOS date and time used by zip archives, in local time.
+///
+/// The format can't represent dates before 1980, these are clamped to its start.
+fn dos_date_time(time: Option<SystemTime>) -> (u16, u16) {
+    let time = match time {
+        Some(time) => DateTime::<Local>::from(time),
+        None => return (0x21, 0),
+    };
+    if time.year() < 1980 {
+        return (0x21, 0);
+    }
+
+    let date = (time.year() as u16 - 1980) << 9 | (time.month() as u16) << 5 | time.day() as u16;
+    let time = (time.hour() as u16) << 11 | (time.minute() as u16) << 5 | time.second() as u16 / 2;
+    (date, time)
 }
@@ -0,0 +1,225 @@
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::archive::{ArchiveMethod, CompressionLevel};
+use crate::errors::ContextualError;
+
+/// Archives of whole directories kept on disk, so that downloading an unchanged directory
+/// again doesn't archive and compress all of it again
+#[derive(Clone)]
+pub struct ArchiveCache {
+    /// Directory holding the cached archives
+    dir: PathBuf,
+
+    /// Combined size in bytes the cached archives may take up
+    max_size: u64,
+
+    /// Patterns left out of all archives, which the cached archives depend on
+    excludes: Vec<String>,
+
+    /// Held while adding an archive and evicting others
+    lock: Arc<Mutex<()>>,
+}
+
+impl ArchiveCache {
+    pub fn new(dir: PathBuf, max_size: u64, excludes: Vec<String>) -> Self {
+        ArchiveCache {
+            dir,
+            max_size,
+            excludes,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Returns the cache entry for an archive of `dir`.
+    ///
+    /// The entry depends on the latest modification date below `dir`, so that archives of
+    /// directories which changed since are never served. Finding it requires walking all of
+    /// `dir`, which is still much cheaper than reading all files.
+    pub fn entry(
+        &self,
+        archive_method: ArchiveMethod,
+        dir: &Path,
+        skip_symlinks: bool,
+        ignore_files: bool,
+        compression: Option<CompressionLevel>,
+    ) -> CacheEntry {
+        let compression = compression.map_or(u8::MAX, |compression| compression.level() as u8);
+        let mut hasher = Sha256::new();
+        hasher.update(dir.to_string_lossy().as_bytes());
+        hasher.update(&[skip_symlinks as u8, ignore_files as u8, compression][..]);
+        for exclude in &self.excludes {
+            hasher.update(exclude.as_bytes());
+            hasher.update(&[0][..]);
+        }
+        if let Some(modified) = latest_modification(dir, skip_symlinks) {
+            let since_epoch = modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            hasher.update(&since_epoch.as_nanos().to_le_bytes()[..]);
+        }
+
+        let name = format!(
+            "{}.{}",
+            hex::encode(hasher.finalize()),
+            archive_method.extension()
+        );
+        CacheEntry {
+            path: self.dir.join(name),
+            cache: self.clone(),
+        }
+    }
+
+    /// Removes the least recently created archives until the cache fits into its size
+    fn evict(&self) -> io::Result<()> {
+        let mut archives = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !is_partial(&entry.path()))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()))
+            })
+            .collect::<Vec<_>>();
+        archives.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut size = archives.iter().map(|(_, size, _)| size).sum::<u64>();
+        for (path, archive_size, _) in archives {
+            if size <= self.max_size {
+                break;
+            }
+            log::info!("Evicting cached archive {}", path.display());
+            fs::remove_file(&path)?;
+            size -= archive_size;
+        }
+        Ok(())
+    }
+}
+
+/// Place of one archive in the cache
+pub struct CacheEntry {
+    path: PathBuf,
+    cache: ArchiveCache,
+}
+
+impl CacheEntry {
+    /// Sends the cached archive to `out`, or if there is none, creates it with `create_archive`
+    /// and adds it to the cache while sending it
+    pub fn send_or_create<W, F>(&self, mut out: W, create_archive: F) -> Result<(), ContextualError>
+    where
+        W: Write,
+        F: FnOnce(&mut dyn Write) -> Result<(), ContextualError>,
+    {
+        if let Ok(mut cached) = File::open(&self.path) {
+            log::info!("Sending cached archive {}", self.path.display());
+            io::copy(&mut cached, &mut out).map_err(|e| {
+                ContextualError::IoError("Failed to send the cached archive".to_string(), e)
+            })?;
+            return Ok(());
+        }
+
+        let mut writer = self.writer(out).map_err(|e| {
+            ContextualError::IoError("Failed to create the cached archive".to_string(), e)
+        })?;
+        create_archive(&mut writer)?;
+        writer
+            .commit()
+            .map_err(|e| ContextualError::IoError("Failed to cache the archive".to_string(), e))
+    }
+
+    /// Returns a writer which passes an archive on to `out` while also writing it to a
+    /// temporary file, which `CacheWriter::commit` moves into the cache
+    fn writer<W: Write>(&self, out: W) -> io::Result<CacheWriter<W>> {
+        fs::create_dir_all(&self.cache.dir)?;
+        let mut partial = self.path.as_os_str().to_owned();
+        partial.push(format!(".{}{}", nanoid::nanoid!(6), PARTIAL_SUFFIX));
+        let partial = PathBuf::from(partial);
+
+        Ok(CacheWriter {
+            out,
+            file: File::create(&partial)?,
+            partial,
+            path: self.path.clone(),
+            cache: self.cache.clone(),
+        })
+    }
+}
+
+/// Suffix of archives which are still being written
+const PARTIAL_SUFFIX: &str = ".partial";
+
+fn is_partial(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(PARTIAL_SUFFIX)
+}
+
+/// Writes an archive both to its destination and to a temporary file in the cache
+struct CacheWriter<W> {
+    out: W,
+    file: File,
+    partial: PathBuf,
+    path: PathBuf,
+    cache: ArchiveCache,
+}
+
+impl<W> CacheWriter<W> {
+    /// Adds the complete archive to the cache, evicting older ones if it gets too large
+    fn commit(self) -> io::Result<()> {
+        self.file.sync_all()?;
+        let _guard = self.cache.lock.lock().unwrap();
+        fs::rename(&self.partial, &self.path)?;
+        self.cache.evict()
+    }
+}
+
+impl<W> Drop for CacheWriter<W> {
+    fn drop(&mut self) {
+        // Archives which weren't completed are never cached
+        let _ = fs::remove_file(&self.partial);
+    }
+}
+
+impl<W: Write> Write for CacheWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.file.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.file.flush()
+    }
+}
+
+/// Returns the latest modification date of `dir` and everything below it.
+///
+/// Adding, removing or renaming entries updates the modification date of their directory, so
+/// this changes with any change to the content of an archive of `dir`.
+fn latest_modification(dir: &Path, skip_symlinks: bool) -> Option<SystemTime> {
+    let mut latest = dir.metadata().and_then(|metadata| metadata.modified()).ok();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return latest,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let is_symlink = entry
+            .file_type()
+            .map_or(false, |file_type| file_type.is_symlink());
+        if is_symlink && skip_symlinks {
+            continue;
+        }
+        let path = entry.path();
+        let modified = match path.metadata() {
+            Ok(metadata) if metadata.is_dir() => latest_modification(&path, skip_symlinks),
+            Ok(metadata) => metadata.modified().ok(),
+            Err(_) => None,
+        };
+        latest = latest.max(modified);
+    }
+
+    latest
+}
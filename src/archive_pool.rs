@@ -0,0 +1,92 @@
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads dedicated to creating archives and streaming them to clients.
+///
+/// Writing to a slow client blocks the thread creating its archive, so a fixed number of
+/// threads keeps many slow downloads from piling up threads. Further archives wait until a
+/// thread is free.
+#[derive(Clone)]
+pub struct ArchivePool {
+    jobs: Arc<Mutex<mpsc::Sender<Job>>>,
+}
+
+impl ArchivePool {
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("archive-{}", i))
+                .spawn(move || loop {
+                    // The lock must be released before running the job
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => {
+                            if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                                log::error!("Archive thread panicked");
+                            }
+                        }
+                        // The pool is gone
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to spawn archive thread");
+        }
+
+        ArchivePool {
+            jobs: Arc::new(Mutex::new(sender)),
+        }
+    }
+
+    /// Runs `job` on the first free thread of the pool
+    pub fn spawn<F: FnOnce() + Send + 'static>(&self, job: F) {
+        let _ = self.jobs.lock().unwrap().send(Box::new(job));
+    }
+}
+
+/// Writer which passes data on to `inner` at no more than `rate` bytes per second on average
+pub struct RateLimited<W> {
+    inner: W,
+    rate: Option<u64>,
+    start: Instant,
+    written: u64,
+}
+
+impl<W> RateLimited<W> {
+    /// Limits writes to `inner` to `rate` bytes per second, or not at all if `rate` is unset
+    pub fn new(inner: W, rate: Option<u64>) -> Self {
+        RateLimited {
+            inner,
+            rate,
+            start: Instant::now(),
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for RateLimited<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+
+        if let Some(rate) = self.rate.filter(|rate| *rate > 0) {
+            let due = Duration::from_secs_f64(self.written as f64 / rate as f64);
+            let elapsed = self.start.elapsed();
+            if due > elapsed {
+                thread::sleep(due - elapsed);
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,19 +1,40 @@
 use bytes::Bytes;
+use bytesize::ByteSize;
+use chrono::format::{Item, StrftimeItems};
 use http::header::{HeaderMap, HeaderName, HeaderValue};
-use std::net::IpAddr;
+use http::StatusCode;
+use ignore::gitignore::Gitignore;
 use std::path::PathBuf;
+use std::time::Duration;
 use structopt::StructOpt;
 
 use crate::auth;
+use crate::cache_control::CacheControlRule;
+use crate::config_file::ConfigFormat;
+use crate::csp;
 use crate::errors::ContextualError;
+use crate::force_download;
+use crate::i18n;
+use crate::interfaces::InterfaceArg;
+use crate::listing;
+use crate::mime_map::MimeMapArg;
+use crate::proxy::TrustedProxy;
+use crate::public_ip::PublicIpSource;
+use crate::random_route;
+use crate::rate_limit;
 use crate::renderer;
+use crate::security_headers::SecurityHeaderArg;
+use crate::shutdown;
 
 #[derive(StructOpt)]
 #[structopt(
     name = "miniserve",
     author,
     about,
-    global_settings = &[structopt::clap::AppSettings::ColoredHelp],
+    global_settings = &[
+        structopt::clap::AppSettings::ColoredHelp,
+        structopt::clap::AppSettings::AllArgsOverrideSelf,
+    ],
 )]
 pub struct CliArgs {
     /// Be verbose, includes emitting access logs
@@ -21,8 +42,11 @@ pub struct CliArgs {
     pub verbose: bool,
 
     /// Which path to serve
+    ///
+    /// If several directories are given, each of them is served under its name, like
+    /// /data and /backups for "miniserve /data /backups".
     #[structopt(name = "PATH", parse(from_os_str))]
-    pub path: Option<PathBuf>,
+    pub path: Vec<PathBuf>,
 
     /// The name of a directory index file to serve, like "index.html"
     ///
@@ -31,18 +55,80 @@ pub struct CliArgs {
     #[structopt(long, parse(from_os_str), name = "index_file")]
     pub index: Option<PathBuf>,
 
+    /// Serve index.html instead of the listing of any directory containing one
+    ///
+    /// Unlike --index, directories without an index.html are still listed.
+    #[structopt(long = "index-per-dir", conflicts_with = "index_file")]
+    pub index_per_dir: bool,
+
     /// Port to use
     #[structopt(short = "p", long = "port", default_value = "8080")]
     pub port: u16,
 
-    /// Interface to listen on
+    /// Unprivileged port to fall back to if binding to a privileged port (<1024) is not permitted
+    #[structopt(long = "fallback-port", parse(try_from_str = parse_unprivileged_port))]
+    pub fallback_port: Option<u16>,
+
+    /// TLS certificate chain to serve HTTPS with, PEM encoded
+    #[structopt(long = "tls-cert", requires = "tls_key", parse(from_os_str))]
+    pub tls_cert: Option<PathBuf>,
+
+    /// TLS private key to serve HTTPS with, PEM encoded (PKCS#8 or RSA)
+    #[structopt(
+        long = "tls-key",
+        name = "tls_key",
+        requires = "tls_cert",
+        parse(from_os_str)
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    /// Serve HTTPS with a self-signed certificate generated at startup, whose fingerprint is
+    /// printed to compare it with the one browsers show
+    #[structopt(
+        long = "tls-self-signed",
+        conflicts_with_all = &["tls_cert", "acme_domain"]
+    )]
+    pub tls_self_signed: bool,
+
+    /// Also listen for plain HTTP on this port and redirect all requests to HTTPS, keeping
+    /// their path and query
+    #[structopt(long = "redirect-http-port", value_name = "port")]
+    pub redirect_http_port: Option<u16>,
+
+    /// Domain to obtain and renew a TLS certificate for from Let's Encrypt, to serve HTTPS with
+    ///
+    /// The challenges are answered on port 80 of the interfaces, which has to be reachable
+    /// from the internet under the domain.
+    #[structopt(
+        long = "acme-domain",
+        name = "acme_domain",
+        requires = "acme_email",
+        conflicts_with = "tls_cert"
+    )]
+    pub acme_domain: Option<String>,
+
+    /// Contact email address of the Let's Encrypt account
+    #[structopt(long = "acme-email", name = "acme_email", requires = "acme_domain")]
+    pub acme_email: Option<String>,
+
+    /// Directory to keep the Let's Encrypt account and certificates in across restarts
+    #[structopt(
+        long = "acme-cache-dir",
+        default_value = ".miniserve-acme",
+        parse(from_os_str)
+    )]
+    pub acme_cache_dir: PathBuf,
+
+    /// Interface to listen on, either an address or the name of a network interface like eth0
+    ///
+    /// Names are resolved to the current addresses of the interface at startup.
     #[structopt(
         short = "i",
         long = "interfaces",
-        parse(try_from_str = parse_interface),
+        parse(try_from_str = InterfaceArg::parse),
         number_of_values = 1,
     )]
-    pub interfaces: Vec<IpAddr>,
+    pub interfaces: Vec<InterfaceArg>,
 
     /// Set authentication. Currently supported formats:
     /// username:password, username:sha256:hash, username:sha512:hash
@@ -55,10 +141,131 @@ pub struct CliArgs {
     )]
     pub auth: Vec<auth::RequiredAuth>,
 
-    /// Generate a random 6-hexdigit route
+    /// MaxMind GeoIP2/GeoLite2 country database used to tag and filter clients by country
+    #[structopt(long = "geoip-db", name = "geoip_db", parse(from_os_str))]
+    pub geoip_db: Option<PathBuf>,
+
+    /// Only allow clients from this country (ISO 3166-1 alpha-2 code, e.g. DE)
+    #[structopt(
+        long = "allow-country",
+        requires = "geoip_db",
+        parse(from_str = parse_country_code),
+        number_of_values = 1,
+    )]
+    pub allow_country: Vec<String>,
+
+    /// Deny clients from this country (ISO 3166-1 alpha-2 code, e.g. DE)
+    #[structopt(
+        long = "deny-country",
+        requires = "geoip_db",
+        parse(from_str = parse_country_code),
+        number_of_values = 1,
+    )]
+    pub deny_country: Vec<String>,
+
+    /// Only answer requests addressed to these hosts, e.g. example.com,files.lan
+    ///
+    /// Requests for localhost and local IP addresses are always allowed.
+    /// This protects miniserve against DNS rebinding attacks.
+    #[structopt(
+        long = "allowed-hosts",
+        use_delimiter = true,
+        parse(from_str = parse_host)
+    )]
+    pub allowed_hosts: Vec<String>,
+
+    /// Allow scripts of these origins to fetch files and use the APIs, e.g.
+    /// https://app.example.com, or * for any origin
+    ///
+    /// Preflight requests of allowed origins are answered without authentication. Only origins
+    /// given explicitly may send credentials, not any origin allowed by *.
+    #[structopt(
+        long = "cors-allow-origin",
+        value_name = "origin",
+        use_delimiter = true,
+        parse(from_str = parse_origin)
+    )]
+    pub cors_allow_origin: Vec<String>,
+
+    /// Methods the origins of --cors-allow-origin may use [default: GET,HEAD,POST]
+    #[structopt(
+        long = "cors-allow-methods",
+        value_name = "methods",
+        use_delimiter = true,
+        requires = "cors_allow_origin",
+        parse(try_from_str = parse_method)
+    )]
+    pub cors_allow_methods: Vec<String>,
+
+    /// Trust the X-Forwarded-For, X-Forwarded-Proto and X-Forwarded-Host headers of requests
+    /// from these reverse proxies, e.g. 127.0.0.1 or 10.0.0.0/8
+    ///
+    /// Logs, country and connection limits then use the real client address, and generated
+    /// links the scheme and host the client used. These headers are removed from requests of
+    /// anyone else.
+    #[structopt(
+        name = "trusted-proxy",
+        long = "trusted-proxy",
+        value_name = "cidr",
+        use_delimiter = true,
+        parse(try_from_str = TrustedProxy::parse)
+    )]
+    pub trusted_proxies: Vec<TrustedProxy>,
+
+    /// Forward the metadata of every request, but not its body, to this URL
+    ///
+    /// The metadata is POSTed as JSON in the background, which helps debugging the behavior of
+    /// clients behind proxies. Credentials and cookies are redacted.
+    #[structopt(
+        long = "mirror-requests",
+        value_name = "url",
+        parse(try_from_str = parse_mirror_url)
+    )]
+    pub mirror_requests: Option<String>,
+
+    /// Encrypt file downloads with this pre-shared key
+    ///
+    /// Files are encrypted in the age format using the key as passphrase and can be decrypted
+    /// with `age -d`. Directory listings are not encrypted.
+    ///
+    /// The key is visible to other users of the machine in the process list when given on the
+    /// command line. Set it in the MINISERVE_PSK environment variable or the config file instead.
+    #[structopt(
+        long = "psk",
+        value_name = "secret",
+        env = "MINISERVE_PSK",
+        hide_env_values = true
+    )]
+    pub psk: Option<String>,
+
+    /// Generate a random route, of 6 hex digits unless given otherwise
     #[structopt(long = "random-route")]
     pub random_route: bool,
 
+    /// Number of characters of the random route
+    #[structopt(
+        long = "random-route-length",
+        value_name = "length",
+        default_value = "6",
+        parse(try_from_str = random_route::parse_length)
+    )]
+    pub random_route_length: usize,
+
+    /// Characters the random route is made of, e.g. 0123456789abcdefghijklmnopqrstuvwxyz
+    #[structopt(
+        long = "random-route-alphabet",
+        value_name = "chars",
+        default_value = "0123456789abcdef",
+        parse(try_from_str = random_route::parse_alphabet)
+    )]
+    pub random_route_alphabet: String,
+
+    /// Keep the random route in this file, to use the same one across restarts
+    ///
+    /// The route is generated and saved if the file doesn't exist yet. Implies --random-route.
+    #[structopt(long = "random-route-file", value_name = "file", parse(from_os_str))]
+    pub random_route_file: Option<PathBuf>,
+
     /// Do not follow symbolic links
     #[structopt(short = "P", long = "no-symlinks")]
     pub no_symlinks: bool,
@@ -67,6 +274,23 @@ pub struct CliArgs {
     #[structopt(short = "H", long = "hidden")]
     pub hidden: bool,
 
+    /// Hide entries matched by .gitignore and .miniserveignore files
+    ///
+    /// Matching entries are left out of listings and archives and can't be downloaded.
+    #[structopt(long = "ignore-files")]
+    pub ignore_files: bool,
+
+    /// How to handle requests for directories without a trailing slash
+    ///
+    /// "redirect" redirects to the URL with a trailing slash, "accept" serves the listing under
+    /// both URLs and "strict" responds with 404 Not Found.
+    #[structopt(
+        long = "trailing-slash",
+        default_value = "redirect",
+        possible_values = &["redirect", "accept", "strict"],
+    )]
+    pub trailing_slash: listing::TrailingSlash,
+
     /// Default color scheme
     #[structopt(
         short = "c",
@@ -88,13 +312,50 @@ pub struct CliArgs {
     pub color_scheme_dark: String,
 
     /// Enable QR code display
+    ///
+    /// Besides the codes of the listing, a code of each address other devices can reach
+    /// miniserve at is printed on startup.
     #[structopt(short = "q", long = "qrcode")]
     pub qrcode: bool,
 
+    /// Open the served URL in the default browser once the server is listening
+    #[structopt(long = "open")]
+    pub open: bool,
+
+    /// Link a page showing the metadata of each file from the listing
+    #[structopt(long = "file-info")]
+    pub file_info: bool,
+
+    /// Show PDFs in the browser instead of downloading them, and link a preview page for each
+    #[structopt(long = "preview")]
+    pub preview: bool,
+
+    /// Offer downloading files compressed on the fly with gzip or zstd
+    #[structopt(long = "compressed-downloads")]
+    pub compressed_downloads: bool,
+
+    /// Serve files like app.js compressed from their siblings app.js.br or app.js.gz, if
+    /// there are any and the client accepts their encoding
+    #[structopt(long = "serve-precompressed")]
+    pub serve_precompressed: bool,
+
     /// Enable file uploading
-    #[structopt(short = "u", long = "upload-files")]
+    #[structopt(name = "upload-files", short = "u", long = "upload-files")]
     pub file_upload: bool,
 
+    /// Allow renaming files and directories from the listing
+    #[structopt(long = "allow-rename")]
+    pub allow_rename: bool,
+
+    /// Allow moving and copying selected entries to other directories from the listing
+    #[structopt(long = "allow-move")]
+    pub allow_move: bool,
+
+    /// Allow extracting .zip, .tar.gz and .tgz archives into a new directory next to them
+    /// from the listing
+    #[structopt(long = "allow-extract")]
+    pub allow_extract: bool,
+
     /// Enable overriding existing files during file upload
     #[structopt(short = "o", long = "overwrite-files")]
     pub overwrite_files: bool,
@@ -109,35 +370,517 @@ pub struct CliArgs {
 
     /// Enable zip archive generation
     ///
-    /// WARNING: Zipping large directories can result in out-of-memory exception
-    /// because zip generation is done in memory and cannot be sent on the fly
+    /// Files are stored uncompressed unless a level is chosen with `?compression=1` to `9`, and
+    /// files or archives larger than 4 GiB are not supported.
     #[structopt(short = "z", long = "enable-zip")]
     pub enable_zip: bool,
 
+    /// Enable 7z archive generation
+    ///
+    /// Files are stored uncompressed unless a level is chosen with `?compression=1` to `9`, in
+    /// which case they are deflated. Archives are built in the temporary directory before they
+    /// are sent.
+    #[structopt(long = "enable-7z")]
+    pub enable_7z: bool,
+
     /// List directories first
     #[structopt(short = "D", long = "dirs-first")]
     pub dirs_first: bool,
 
+    /// Default sorting method for directory listings
+    ///
+    /// Visitors can still pick another one, which is then remembered in a cookie.
+    #[structopt(
+        long = "default-sort",
+        default_value = "name",
+        possible_values = &["name", "size", "date", "extension"],
+    )]
+    pub default_sort: listing::SortingMethod,
+
+    /// Split directory listings into pages of this many entries
+    ///
+    /// Further entries can be loaded on demand, which keeps huge directories responsive.
+    #[structopt(long = "page-size", parse(try_from_str = parse_page_size))]
+    pub page_size: Option<usize>,
+
+    /// Allow expanding directories in place to browse nested folders as a tree
+    #[structopt(long = "tree-view")]
+    pub tree_view: bool,
+
+    /// Log directory reads and archive creations taking longer than this many milliseconds
+    ///
+    /// The path and duration of slow operations are logged, which helps finding slow mounts.
+    #[structopt(long = "slow-op-threshold", value_name = "ms")]
+    pub slow_op_threshold: Option<u64>,
+
+    /// Show the recursive size of directories
+    ///
+    /// Sizes are computed in the background and cached, so they may appear after a reload.
+    #[structopt(long = "show-dir-sizes")]
+    pub show_dir_sizes: bool,
+
+    /// Show the mode bits, owner and group of each entry (unix only)
+    #[structopt(long = "show-permissions")]
+    pub show_permissions: bool,
+
+    /// Show a column with the SHA-256 checksums of files, computed on demand
+    #[structopt(long = "show-checksums")]
+    pub show_checksums: bool,
+
+    /// Create at most this many archives at once, further ones wait for one of them to finish
+    #[structopt(long = "archive-threads", value_name = "threads", default_value = "4")]
+    pub archive_threads: usize,
+
+    /// Limit the speed at which each archive is sent
+    #[structopt(long = "archive-rate-limit", value_name = "KiB/s")]
+    pub archive_rate_limit: Option<u64>,
+
+    /// Answer with 503 Service Unavailable while this many requests are being handled
+    ///
+    /// Requests count until their response is sent completely, downloads as long as they take.
+    #[structopt(long = "max-connections", parse(try_from_str = parse_connection_limit))]
+    pub max_connections: Option<usize>,
+
+    /// Answer with 429 Too Many Requests while this many requests of the client are being
+    /// handled
+    #[structopt(long = "max-connections-per-ip", parse(try_from_str = parse_connection_limit))]
+    pub max_connections_per_ip: Option<usize>,
+
+    /// Limit the speed at which each file is sent, e.g. 500K or 2MiB
+    #[structopt(
+        long = "rate-limit",
+        value_name = "bytes/s",
+        parse(try_from_str = rate_limit::parse_rate)
+    )]
+    pub rate_limit: Option<u64>,
+
+    /// Limit the speed at which all files together are sent, e.g. 10MiB
+    #[structopt(
+        long = "rate-limit-total",
+        value_name = "bytes/s",
+        parse(try_from_str = rate_limit::parse_rate)
+    )]
+    pub rate_limit_total: Option<u64>,
+
+    /// Shut down after this long, e.g. 90s, 10m or 1h30m
+    #[structopt(
+        long = "shutdown-after",
+        value_name = "duration",
+        parse(try_from_str = shutdown::parse_duration)
+    )]
+    pub shutdown_after: Option<Duration>,
+
+    /// Shut down once this many files have been downloaded completely
+    #[structopt(
+        long = "shutdown-after-downloads",
+        value_name = "count",
+        parse(try_from_str = shutdown::parse_download_count)
+    )]
+    pub shutdown_after_downloads: Option<usize>,
+
+    /// Seconds a client may take to send the head of its request, 0 to wait forever
+    #[structopt(long = "request-timeout", value_name = "seconds", default_value = "5")]
+    pub request_timeout: u64,
+
+    /// Seconds an idle connection is kept open for further requests, 0 to close connections
+    /// after each request
+    #[structopt(long = "keep-alive", value_name = "seconds", default_value = "5")]
+    pub keep_alive: usize,
+
+    /// Size of the buffer collecting uploaded data before it is written to disk, e.g. 4MiB
+    ///
+    /// Larger buffers mean fewer writes, which suits slow disks or network filesystems.
+    #[structopt(
+        long = "client-body-buffer-size",
+        value_name = "size",
+        default_value = "256KiB",
+        parse(try_from_str = parse_buffer_size)
+    )]
+    pub client_body_buffer_size: usize,
+
+    /// Refuse to create archives whose files are larger than this in total
+    #[structopt(long = "max-archive-size", value_name = "MiB")]
+    pub max_archive_size: Option<u64>,
+
+    /// Leave entries matching this pattern out of archives, e.g. `.git`, `node_modules` or `*.iso`
+    ///
+    /// Patterns use the syntax of .gitignore files, relative to the archived directory. This
+    /// option can be given multiple times.
+    #[structopt(long = "archive-exclude", value_name = "glob", number_of_values = 1)]
+    pub archive_exclude: Vec<String>,
+
+    /// Never offer directories matching this pattern as archives, nor anything below them
+    ///
+    /// Patterns use the syntax of .gitignore files, relative to the served directory. A
+    /// `.no-archive` file in a directory has the same effect. Such directories are also left
+    /// out of archives of their parents. This option can be given multiple times.
+    #[structopt(long = "no-archive-path", value_name = "glob", number_of_values = 1)]
+    pub no_archive_path: Vec<String>,
+
+    /// Keep archives of whole directories in this directory, to serve them again as long as
+    /// nothing in the archived directory changes
+    #[structopt(long = "archive-cache-dir", value_name = "dir", parse(from_os_str))]
+    pub archive_cache_dir: Option<PathBuf>,
+
+    /// Combined size of the cached archives, beyond which the oldest ones are removed
+    #[structopt(
+        long = "archive-cache-size",
+        value_name = "MiB",
+        default_value = "1024"
+    )]
+    pub archive_cache_size: u64,
+
+    /// Keep up to this many rendered listings in memory
+    ///
+    /// A cached listing is rendered again once its directory changes, or after 10 seconds so
+    /// that sizes and dates of changed files are picked up.
+    #[structopt(long = "listing-cache", value_name = "entries")]
+    pub listing_cache: Option<usize>,
+
+    /// Staging directory which a POST to /__miniserve/publish swaps into the served directory
+    ///
+    /// It has to be on the same filesystem as the served directory. The previous version is
+    /// kept next to the served directory with a `.previous` suffix until the next publish.
+    #[structopt(
+        long = "publish-from",
+        name = "publish_from",
+        value_name = "dir",
+        parse(from_os_str),
+        requires = "publish_token"
+    )]
+    pub publish_from: Option<PathBuf>,
+
+    /// Token which requests to /__miniserve/publish have to present, as in
+    /// `Authorization: Bearer <token>`
+    ///
+    /// It is required in addition to --auth. Set it in the MINISERVE_PUBLISH_TOKEN environment
+    /// variable or the config file to keep it out of the process list.
+    #[structopt(
+        long = "publish-token",
+        name = "publish_token",
+        value_name = "token",
+        env = "MINISERVE_PUBLISH_TOKEN",
+        hide_env_values = true,
+        requires = "publish_from"
+    )]
+    pub publish_token: Option<String>,
+
+    /// Show the number of files, their total size and the last update time of the served
+    /// directory at /__miniserve/stats, even to visitors without credentials
+    #[structopt(long = "public-stats")]
+    pub public_stats: bool,
+
+    /// Show the files of the served directory with the same content and the space removing
+    /// them would free at /__miniserve/duplicates
+    ///
+    /// The served directory is scanned at most once a minute, the page shows the last result
+    /// in between.
+    #[structopt(long = "show-duplicates")]
+    pub show_duplicates: bool,
+
+    /// Directory with custom Tera templates for the listing and error pages
+    ///
+    /// `listing.html` replaces the directory listing and `error.html` the error page.
+    #[structopt(long = "template", value_name = "dir", parse(from_os_str))]
+    pub template: Option<PathBuf>,
+
+    /// Improve compatibility with HTTP/1.0 clients
+    ///
+    /// Responses to HTTP/1.0 requests never use chunked transfer encoding. Instead, bodies of
+    /// unknown length are delimited by closing the connection.
+    #[structopt(long = "legacy-http")]
+    pub legacy_http: bool,
+
+    /// Language of the interface
+    ///
+    /// By default, the language is chosen according to the Accept-Language header of each client.
+    #[structopt(long = "lang", possible_values = &["en", "de", "fr"])]
+    pub lang: Option<i18n::Language>,
+
     /// Shown instead of host in page title and heading
     #[structopt(short = "t", long = "title")]
     pub title: Option<String>,
 
+    /// Text shown above the listing, e.g. who to contact about the share
+    ///
+    /// The text is Markdown, which may contain HTML.
+    #[structopt(long = "header-text", value_name = "text")]
+    pub header_text: Option<String>,
+
     /// Set custom header for responses
     #[structopt(long = "header", parse(try_from_str = parse_header), number_of_values = 1)]
     pub header: Vec<HeaderMap>,
 
+    /// Set the Cache-Control header of served files, either for all of them (e.g. no-cache) or
+    /// for the ones matching a glob (e.g. '*.css=max-age=31536000,immutable')
+    ///
+    /// Values for globs take precedence over the one for all files, the last matching glob applies.
+    #[structopt(
+        long = "cache-control",
+        parse(try_from_str = CacheControlRule::parse),
+        number_of_values = 1
+    )]
+    pub cache_control: Vec<CacheControlRule>,
+
+    /// Serve files as attachments, so that browsers download them instead of showing them
+    ///
+    /// Without globs, this applies to all files, otherwise only to the ones matching one of the
+    /// globs, e.g. --force-download='*.html,*.svg'. This keeps uploaded HTML or SVG files from
+    /// running scripts.
+    #[structopt(
+        long = "force-download",
+        value_name = "globs",
+        min_values = 0,
+        require_equals = true,
+        use_delimiter = true,
+        parse(try_from_str = force_download::parse_glob)
+    )]
+    pub force_download: Option<Vec<Gitignore>>,
+
+    /// Send HSTS, X-Content-Type-Options, Referrer-Policy and X-Frame-Options headers for
+    /// internet-facing deployments
+    ///
+    /// Each header can be given another value, or left out with off, e.g.
+    /// --security-headers='hsts=max-age=63072000,referrer-policy=off'. The headers are named
+    /// hsts, content-type-options, referrer-policy and frame-options. HSTS is only sent over
+    /// HTTPS.
+    #[structopt(
+        long = "security-headers",
+        value_name = "header=value",
+        min_values = 0,
+        require_equals = true,
+        use_delimiter = true,
+        parse(try_from_str = SecurityHeaderArg::parse)
+    )]
+    pub security_headers: Option<Vec<SecurityHeaderArg>>,
+
+    /// Content-Security-Policy of the generated pages, in which {nonce} is replaced by the nonce
+    /// of their inline scripts
+    ///
+    /// By default, only the inline scripts of miniserve and resources of its own origin are
+    /// allowed. Served files are sent without policy.
+    #[structopt(
+        long = "csp",
+        value_name = "policy",
+        default_value = csp::DEFAULT_POLICY,
+        parse(try_from_str = csp::parse_policy)
+    )]
+    pub csp: String,
+
+    /// Serve files with an extension as a MIME type, e.g. wasm=application/wasm, or the ones of a
+    /// file with one such mapping per line
+    ///
+    /// These take precedence over the MIME types guessed from the extension.
+    #[structopt(
+        long = "mime-map",
+        value_name = "ext=type|file",
+        number_of_values = 1,
+        parse(try_from_str = MimeMapArg::parse)
+    )]
+    pub mime_map: Vec<MimeMapArg>,
+
+    /// Show the content of this HTML file instead of the built-in page for missing files
+    #[structopt(long = "error-page-404", value_name = "file", parse(from_os_str))]
+    pub error_page_404: Option<PathBuf>,
+
+    /// Show the content of an HTML file instead of the built-in page for an error status, e.g.
+    /// 403=forbidden.html
+    #[structopt(
+        long = "error-page",
+        value_name = "status=file",
+        number_of_values = 1,
+        parse(try_from_str = parse_error_page)
+    )]
+    pub error_page: Vec<(StatusCode, PathBuf)>,
+
     /// Hide version footer
     #[structopt(short = "F", long = "hide-version-footer")]
     pub hide_version_footer: bool,
 
+    /// Format of modification dates, as strftime string (e.g. %Y-%m-%dT%H:%M:%S%:z for ISO 8601)
+    #[structopt(long = "time-format", parse(try_from_str = parse_time_format))]
+    pub time_format: Option<String>,
+
+    /// Show modification dates in UTC (default)
+    #[structopt(long = "utc", conflicts_with = "local")]
+    pub utc: bool,
+
+    /// Show modification dates in the local timezone of the server
+    #[structopt(long = "local")]
+    pub local: bool,
+
+    /// Suggest similarly named entries on the page shown for missing files
+    #[structopt(long = "suggest-on-404")]
+    pub suggest_on_404: bool,
+
+    /// Print the URL miniserve is likely reachable at from the internet, given port forwarding
+    ///
+    /// The public IP address is asked from a STUN server, stun:stun.l.google.com:19302 unless
+    /// another one is given like stun:<host>[:<port>], or from an http(s) URL responding with
+    /// the address as plain text.
+    #[structopt(
+        long = "show-public-url",
+        value_name = "source",
+        min_values = 0,
+        require_equals = true,
+        parse(try_from_str = PublicIpSource::parse)
+    )]
+    pub show_public_url: Option<Option<PublicIpSource>>,
+
+    /// Check the configuration and exit instead of starting the server
+    ///
+    /// Besides the options, this checks that the paths to serve, the index file and the TLS files
+    /// exist, and that the addresses to listen on can be bound. Problems make miniserve exit with
+    /// an error.
+    #[structopt(long = "check")]
+    pub check: bool,
+
+    /// Load options from a TOML config file
+    ///
+    /// Keys are the long names of the options, like `port = 8080` or `auth = ["joe:123"]`, and
+    /// `path` gives the directories to serve. Options on the command line take precedence,
+    /// the ones which can be given several times add to the values of the file.
+    ///
+    /// On SIGHUP, `auth`, `color-scheme` and `color-scheme-dark` are read from the file again,
+    /// without dropping any connections.
+    #[structopt(long = "config", value_name = "file", parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Print the options in effect after merging the command line and the config file, including
+    /// defaults, and exit
+    #[structopt(
+        long = "print-config",
+        value_name = "format",
+        possible_values = &["toml", "json"]
+    )]
+    pub print_config: Option<ConfigFormat>,
+
     /// Generate completion file for a shell
     #[structopt(long = "print-completions", value_name = "shell", possible_values = &structopt::clap::Shell::variants())]
     pub print_completions: Option<structopt::clap::Shell>,
 }
 
-/// Checks wether an interface is valid, i.e. it can be parsed into an IP address
-fn parse_interface(src: &str) -> Result<IpAddr, std::net::AddrParseError> {
-    src.parse::<IpAddr>()
+/// Checks wether a port is unprivileged, i.e. it can be bound without special permissions
+fn parse_unprivileged_port(src: &str) -> Result<u16, ContextualError> {
+    let port = src
+        .parse::<u16>()
+        .map_err(|e| ContextualError::ParseError("port".to_string(), e.to_string()))?;
+
+    if port < 1024 {
+        return Err(ContextualError::ParseError(
+            "port".to_string(),
+            "fallback port must not be a privileged port (<1024)".to_string(),
+        ));
+    }
+
+    Ok(port)
+}
+
+/// Checks wether a page size is valid, i.e. a positive number
+fn parse_page_size(src: &str) -> Result<usize, ContextualError> {
+    match src.parse::<usize>() {
+        Ok(0) => Err(ContextualError::ParseError(
+            "page size".to_string(),
+            "page size must be greater than 0".to_string(),
+        )),
+        Ok(page_size) => Ok(page_size),
+        Err(e) => Err(ContextualError::ParseError(
+            "page size".to_string(),
+            e.to_string(),
+        )),
+    }
+}
+
+/// Checks wether a connection limit is valid, i.e. a positive number
+fn parse_connection_limit(src: &str) -> Result<usize, ContextualError> {
+    match src.parse::<usize>() {
+        Ok(0) => Err(ContextualError::ParseError(
+            "connection limit".to_string(),
+            "connection limit must be greater than 0".to_string(),
+        )),
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(ContextualError::ParseError(
+            "connection limit".to_string(),
+            e.to_string(),
+        )),
+    }
+}
+
+/// Parses a buffer size like 256KiB or 4MB, which has to be positive
+fn parse_buffer_size(src: &str) -> Result<usize, ContextualError> {
+    match src.parse::<ByteSize>() {
+        Ok(ByteSize(0)) => Err(ContextualError::ParseError(
+            "buffer size".to_string(),
+            "buffer size must be greater than 0".to_string(),
+        )),
+        Ok(ByteSize(size)) => Ok(size as usize),
+        Err(e) => Err(ContextualError::ParseError("buffer size".to_string(), e)),
+    }
+}
+
+/// Checks wether a strftime format only contains valid specifiers
+fn parse_time_format(src: &str) -> Result<String, ContextualError> {
+    if StrftimeItems::new(src).any(|item| item == Item::Error) {
+        return Err(ContextualError::ParseError(
+            "time format".to_string(),
+            format!("{} is not a valid strftime format", src),
+        ));
+    }
+    Ok(src.to_string())
+}
+
+/// Checks wether the URL of a mirror collector is a valid HTTP URL
+fn parse_mirror_url(src: &str) -> Result<String, ContextualError> {
+    let uri = src
+        .parse::<http::Uri>()
+        .map_err(|e| ContextualError::ParseError("mirror URL".to_string(), e.to_string()))?;
+    match uri.scheme_str() {
+        Some("http") | Some("https") if uri.host().is_some() => Ok(src.to_string()),
+        _ => Err(ContextualError::ParseError(
+            "mirror URL".to_string(),
+            format!("{} is not an HTTP URL", src),
+        )),
+    }
+}
+
+/// Checks wether an error page is given for an error status, like 403=forbidden.html
+fn parse_error_page(src: &str) -> Result<(StatusCode, PathBuf), ContextualError> {
+    let parse_error = |cause: String| ContextualError::ParseError("error page".to_string(), cause);
+
+    let (status, path) = src
+        .split_once('=')
+        .ok_or_else(|| parse_error(format!("{} is not of the form status=file", src)))?;
+    let status = status
+        .trim()
+        .parse::<u16>()
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .filter(|status| status.is_client_error() || status.is_server_error())
+        .ok_or_else(|| parse_error(format!("{} is not an error status", status)))?;
+    Ok((status, PathBuf::from(path)))
+}
+
+/// Normalizes a host name so that it can be compared to the `Host` header of requests
+fn parse_host(src: &str) -> String {
+    src.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Normalizes an origin so that it can be compared to the `Origin` header of requests
+fn parse_origin(src: &str) -> String {
+    src.trim().trim_end_matches('/').to_string()
+}
+
+/// Checks wether a method is a valid HTTP method and normalizes it to upper case
+fn parse_method(src: &str) -> Result<String, ContextualError> {
+    let method = src.trim().to_uppercase();
+    http::Method::from_bytes(method.as_bytes())
+        .map_err(|e| ContextualError::ParseError("method".to_string(), e.to_string()))?;
+    Ok(method)
+}
+
+/// Normalizes a country code so that it can be compared to the GeoIP database's ISO codes
+fn parse_country_code(src: &str) -> String {
+    src.trim().to_uppercase()
 }
 
 /// Parse authentication requirement
@@ -267,4 +1010,16 @@ mod tests {
         let err = parse_auth(auth_string).unwrap_err();
         assert_eq!(format!("{}", err), err_msg.to_owned());
     }
+
+    #[rstest(
+        port_string, expected,
+        case("8080", Some(8080)),
+        case("1024", Some(1024)),
+        case("1023", None),
+        case("80", None),
+        case("foo", None),
+    )]
+    fn parse_unprivileged_port_values(port_string: &str, expected: Option<u16>) {
+        assert_eq!(parse_unprivileged_port(port_string).ok(), expected);
+    }
 }
@@ -5,6 +5,7 @@ use actix_web_httpauth::extractors::basic::BasicAuth;
 use sha2::{Digest, Sha256, Sha512};
 
 use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
 use crate::renderer;
 
 #[derive(Clone, Debug)]
@@ -74,9 +75,13 @@ pub fn get_hash<T: Digest>(text: &str) -> Vec<u8> {
 
 pub async fn handle_auth(req: ServiceRequest, cred: BasicAuth) -> Result<ServiceRequest> {
     let (req, pl) = req.into_parts();
-    let required_auth = &req.app_data::<crate::MiniserveConfig>().unwrap().auth;
+    let settings = req
+        .app_data::<crate::MiniserveConfig>()
+        .unwrap()
+        .reloadable
+        .get();
 
-    if match_auth(cred.into(), required_auth) {
+    if match_auth(cred.into(), &settings.auth) {
         Ok(ServiceRequest::from_parts(req, pl).unwrap_or_else(|_| unreachable!()))
     } else {
         Err(HttpResponse::Unauthorized()
@@ -124,9 +129,11 @@ fn build_unauthorized_response(
         false,
         &state.favicon_route,
         &state.css_route,
-        &state.default_color_scheme,
-        &state.default_color_scheme_dark,
+        &state.reloadable.get().default_color_scheme,
+        &state.reloadable.get().default_color_scheme_dark,
         state.hide_version_footer,
+        state.templates.as_ref(),
+        Language::negotiate(req.headers(), state.lang),
     )
     .into_string()
 }
@@ -0,0 +1,88 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, HeaderValue, StatusCode};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+use crate::errors::ContextualError;
+use crate::path_policy;
+
+/// A `--cache-control` value, applying to all files or only to the ones matching `glob`
+#[derive(Clone, Debug)]
+pub struct CacheControlRule {
+    glob: Option<Gitignore>,
+    value: HeaderValue,
+}
+
+impl CacheControlRule {
+    /// Parses either a plain value like `no-cache` or a value for matching files like
+    /// `*.css=max-age=31536000,immutable`.
+    ///
+    /// Values contain `=` themselves, so the part before the first `=` is only taken as a glob
+    /// if it contains one of `*?[./`, which no directive name does.
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        let parse_error =
+            |cause: String| ContextualError::ParseError("cache control".to_string(), cause);
+
+        let (glob, value) = match src.split_once('=') {
+            Some((glob, value)) if glob.contains(&['*', '?', '[', '.', '/'][..]) => {
+                let mut builder = GitignoreBuilder::new("");
+                builder
+                    .add_line(None, glob.trim())
+                    .map_err(|e| parse_error(e.to_string()))?;
+                let glob = builder.build().map_err(|e| parse_error(e.to_string()))?;
+                (Some(glob), value)
+            }
+            _ => (None, src),
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(parse_error(format!("{} has no value", src)));
+        }
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| parse_error(format!("{} is not a valid header value", value)))?;
+        Ok(CacheControlRule { glob, value })
+    }
+
+    fn matches(&self, relative_path: &Path) -> bool {
+        self.glob.as_ref().map_or(false, |glob| {
+            glob.matched_path_or_any_parents(relative_path, false)
+                .is_ignore()
+        })
+    }
+}
+
+/// Returns the value of `rules` for the file at `relative_path` from the served directory.
+///
+/// The last matching glob applies, otherwise the last value for all files.
+fn value_for(rules: &[CacheControlRule], relative_path: &Path) -> Option<HeaderValue> {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(relative_path))
+        .or_else(|| rules.iter().rev().find(|rule| rule.glob.is_none()))
+        .map(|rule| rule.value.clone())
+}
+
+/// Returns the `Cache-Control` value for the response to `req`, if it requests a file which
+/// one of the `--cache-control` rules applies to
+pub fn for_request(req: &ServiceRequest) -> Option<HeaderValue> {
+    let conf = req.app_data::<crate::MiniserveConfig>()?;
+    if conf.cache_control.is_empty() {
+        return None;
+    }
+
+    let relative_path = path_policy::requested_file(req)?;
+    value_for(&conf.cache_control, &relative_path)
+}
+
+/// Sets the `Cache-Control` header `value` on `res`, if it serves the file
+pub fn apply<B>(mut res: ServiceResponse<B>, value: Option<HeaderValue>) -> ServiceResponse<B> {
+    let serves_file = matches!(
+        res.status(),
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+    );
+    if let Some(value) = value.filter(|_| serves_file) {
+        res.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+    res
+}
@@ -0,0 +1,86 @@
+use actix_web::web;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::errors::ContextualError;
+use crate::slow_ops;
+
+/// Checksum of one file, valid as long as the file keeps its modification date and size
+struct CachedChecksum {
+    modified: Option<SystemTime>,
+    size: u64,
+    checksum: String,
+}
+
+/// SHA-256 checksums of files, computed on demand and cached
+#[derive(Clone, Default)]
+pub struct ChecksumCache {
+    checksums: Arc<Mutex<HashMap<PathBuf, CachedChecksum>>>,
+}
+
+impl ChecksumCache {
+    /// Returns the cached checksum of the file at `path`, unless it changed since
+    pub fn get(&self, path: &Path, metadata: &Metadata) -> Option<String> {
+        let checksums = self.checksums.lock().unwrap();
+        checksums
+            .get(path)
+            .filter(|cached| {
+                cached.modified == metadata.modified().ok() && cached.size == metadata.len()
+            })
+            .map(|cached| cached.checksum.clone())
+    }
+
+    /// Returns the hex-encoded SHA-256 checksum of the file at `path`, computing it on the
+    /// blocking thread pool if it isn't cached
+    pub async fn compute(
+        &self,
+        path: PathBuf,
+        slow_op_threshold: Option<Duration>,
+    ) -> Result<String, ContextualError> {
+        let metadata = path.metadata().map_err(|e| {
+            ContextualError::IoError(format!("Failed to read metadata of {}", path.display()), e)
+        })?;
+        if let Some(checksum) = self.get(&path, &metadata) {
+            return Ok(checksum);
+        }
+
+        let checksum = {
+            let path = path.clone();
+            web::block(move || {
+                slow_ops::timed(slow_op_threshold, "computing checksum of", &path, || {
+                    sha256_checksum(&path).map_err(|e| {
+                        ContextualError::IoError(
+                            format!("Failed to compute checksum of {}", path.display()),
+                            e,
+                        )
+                    })
+                })
+            })
+            .await?
+        };
+
+        // Cached for the file as it was before reading, so that changes in the meantime
+        // are noticed on the next request
+        self.checksums.lock().unwrap().insert(
+            path,
+            CachedChecksum {
+                modified: metadata.modified().ok(),
+                size: metadata.len(),
+                checksum: checksum.clone(),
+            },
+        );
+        Ok(checksum)
+    }
+}
+
+/// Computes the hex-encoded SHA-256 checksum of the file at `path`
+pub fn sha256_checksum(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,68 @@
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::http::header::{self, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the latest modification date of `dir` and its entries, which a listing of `dir`
+/// can't be older than
+pub fn listing_modified(dir: &Path) -> Option<SystemTime> {
+    let modified = dir.metadata().and_then(|metadata| metadata.modified()).ok();
+    let entries = fs::read_dir(dir).ok()?;
+    entries
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .fold(modified, |latest, modified| latest.max(Some(modified)))
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns whether the client already has the current version of a response with the tag
+/// `etag`, last modified at `last_modified`.
+///
+/// Like for files, `If-Modified-Since` is only considered if there is no `If-None-Match`.
+fn is_fresh(req: &HttpRequest, etag: &EntityTag, last_modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        return match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        };
+    }
+    match (req.get_header::<IfModifiedSince>(), last_modified) {
+        (Some(IfModifiedSince(since)), Some(last_modified)) => {
+            seconds(last_modified) <= seconds(SystemTime::from(since))
+        }
+        _ => false,
+    }
+}
+
+/// Finishes the response `res` with the rendered page `body`, or with 304 Not Modified if the
+/// client already has it.
+///
+/// The strong ETag is derived from the content of `body`, so that it changes with anything
+/// shown on the page.
+pub fn respond(
+    req: &HttpRequest,
+    mut res: HttpResponseBuilder,
+    body: String,
+    last_modified: Option<SystemTime>,
+) -> HttpResponse {
+    let hash = hex::encode(Sha256::digest(body.as_bytes()));
+    let etag = EntityTag::strong(hash[..32].to_string());
+
+    res.set(header::ETag(etag.clone()));
+    if let Some(last_modified) = last_modified {
+        res.set(header::LastModified(HttpDate::from(last_modified)));
+    }
+    if is_fresh(req, &etag, last_modified) {
+        res.status(StatusCode::NOT_MODIFIED).finish()
+    } else {
+        res.content_type("text/html; charset=utf-8").body(body)
+    }
+}
@@ -0,0 +1,186 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use structopt::clap;
+use structopt::StructOpt;
+use strum_macros::EnumString;
+
+use crate::args::CliArgs;
+use crate::errors::ContextualError;
+
+/// Turns a scalar value of the config file into an argument value
+fn to_arg_value(key: &str, value: &toml::Value) -> Result<String, ContextualError> {
+    match value {
+        toml::Value::String(value) => Ok(value.clone()),
+        toml::Value::Integer(value) => Ok(value.to_string()),
+        toml::Value::Float(value) => Ok(value.to_string()),
+        toml::Value::Boolean(value) => Ok(value.to_string()),
+        toml::Value::Datetime(value) => Ok(value.to_string()),
+        toml::Value::Array(_) | toml::Value::Table(_) => Err(ContextualError::ConfigFileError(
+            format!("{} can't contain arrays or tables", key),
+        )),
+    }
+}
+
+/// Turns the options of the config file into command line arguments.
+///
+/// Keys are the long names of the options. `true` enables a flag and `false` leaves it out,
+/// arrays give an option several times. `path` gives the directories to serve.
+fn to_args(config: toml::value::Table, with_path: bool) -> Result<Vec<OsString>, ContextualError> {
+    let mut args = vec![];
+    let mut paths = vec![];
+    for (key, value) in config {
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        match (key.as_str(), values.as_slice()) {
+            ("config", _) => {
+                return Err(ContextualError::ConfigFileError(
+                    "config files can't include other config files".to_string(),
+                ))
+            }
+            ("path", _) => {
+                for value in &values {
+                    paths.push(OsString::from(to_arg_value(&key, value)?));
+                }
+            }
+            (_, [toml::Value::Boolean(true)]) => args.push(OsString::from(format!("--{}", key))),
+            (_, [toml::Value::Boolean(false)]) => (),
+            _ => {
+                for value in &values {
+                    let value = to_arg_value(&key, value)?;
+                    args.push(OsString::from(format!("--{}={}", key, value)));
+                }
+            }
+        }
+    }
+    if with_path {
+        args.extend(paths);
+    }
+    Ok(args)
+}
+
+/// Returns the command line arguments `cli_args` with the options of the config file at
+/// `path` put before the ones of the command line, which thus take precedence.
+///
+/// `path` of the file is left out if the command line gives directories to serve itself.
+pub fn merge_args(
+    path: &Path,
+    cli_args: Vec<OsString>,
+    cli_has_path: bool,
+) -> Result<Vec<OsString>, ContextualError> {
+    let config = fs::read_to_string(path).map_err(|e| {
+        ContextualError::IoError(format!("Failed to read config file {}", path.display()), e)
+    })?;
+    let config = config
+        .parse::<toml::Value>()
+        .map_err(|e| ContextualError::ConfigFileError(e.to_string()))?;
+    let config = match config {
+        toml::Value::Table(config) => config,
+        _ => {
+            return Err(ContextualError::ConfigFileError(
+                "the config file has to be a table of options".to_string(),
+            ))
+        }
+    };
+
+    let mut cli_args = cli_args.into_iter();
+    let mut args = cli_args.next().into_iter().collect::<Vec<_>>();
+    args.extend(to_args(config, !cli_has_path)?);
+    args.extend(cli_args);
+    Ok(args)
+}
+
+/// Returns the command line arguments `cli_args`, along with the options of the config file if
+/// `--config` is given
+pub fn load_args(cli_args: Vec<OsString>) -> Result<Vec<OsString>, clap::Error> {
+    let args = CliArgs::from_iter_safe(&cli_args)?;
+    match &args.config {
+        Some(config_file) => merge_args(config_file, cli_args, !args.path.is_empty())
+            .map_err(|e| clap::Error::with_description(&e.to_string(), clap::ErrorKind::Io)),
+        None => Ok(cli_args),
+    }
+}
+
+/// Parses the command line arguments `cli_args`, along with the options of the config file if
+/// `--config` is given
+pub fn parse_args(cli_args: Vec<OsString>) -> Result<CliArgs, clap::Error> {
+    CliArgs::from_iter_safe(load_args(cli_args)?)
+}
+
+/// Format of `--print-config`
+#[derive(Clone, Copy, Debug, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+/// Turns an argument value back into a config file value, numbers only if they are written
+/// the same way as a number, e.g. not `0123` of `--random-route-alphabet`
+fn to_config_value(value: &OsString) -> toml::Value {
+    let value = value.to_string_lossy();
+    match value.parse::<i64>() {
+        Ok(number) if number.to_string() == value => toml::Value::Integer(number),
+        _ => toml::Value::String(value.into_owned()),
+    }
+}
+
+/// Returns the options in effect for the arguments `args`, including the defaults of the ones
+/// which aren't given, in the format of the config file
+fn effective_options(args: Vec<OsString>) -> Result<toml::value::Table, clap::Error> {
+    let matches = CliArgs::clap().get_matches_from_safe(args)?;
+
+    // Options are named like their long name, which is also their key in the config file.
+    // clap 2 doesn't list the options of an app, but the matched ones including defaults.
+    let mut options = toml::value::Table::new();
+    for (name, arg) in &matches.args {
+        let key = match *name {
+            "PATH" => "path",
+            "config" | "print-config" | "print-completions" => continue,
+            name => name,
+        };
+        let value = match arg.vals.as_slice() {
+            [] => toml::Value::Boolean(true),
+            [value] if arg.occurs <= 1 => to_config_value(value),
+            values => toml::Value::Array(values.iter().map(to_config_value).collect()),
+        };
+        options.insert(key.to_string(), value);
+    }
+    Ok(options)
+}
+
+/// Options whose values are credentials, which `--print-config` doesn't show
+const SECRET_OPTIONS: &[&str] = &["auth", "psk", "publish-token"];
+
+/// Replaces the values of the secret-bearing options in `options`, keeping how many were given
+fn redact_secrets(options: &mut toml::value::Table) {
+    let redacted = || toml::Value::String("<redacted>".to_string());
+    for (key, value) in options.iter_mut() {
+        if !SECRET_OPTIONS.contains(&key.replace('_', "-").as_str()) {
+            continue;
+        }
+        *value = match value {
+            toml::Value::Array(values) => {
+                toml::Value::Array(values.iter().map(|_| redacted()).collect())
+            }
+            _ => redacted(),
+        };
+    }
+}
+
+/// Prints the options in effect for the arguments `args` in `format`, as `--print-config` does
+pub fn print_config(args: Vec<OsString>, format: ConfigFormat) -> Result<(), ContextualError> {
+    let mut options =
+        effective_options(args).map_err(|e| ContextualError::ConfigFileError(e.message))?;
+    redact_secrets(&mut options);
+    let config = match format {
+        ConfigFormat::Toml => toml::to_string(&options)
+            .map_err(|e| ContextualError::ConfigFileError(e.to_string()))?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&options)
+            .map_err(|e| ContextualError::ConfigFileError(e.to_string()))?,
+    };
+    println!("{}", config.trim_end());
+    Ok(())
+}
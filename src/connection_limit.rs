@@ -0,0 +1,168 @@
+use actix_web::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::{Error, HttpResponse};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::proxy;
+use crate::renderer;
+
+/// Seconds after which clients turned away may try again
+const RETRY_AFTER: &str = "1";
+
+#[derive(Default)]
+struct Active {
+    total: usize,
+    by_ip: HashMap<IpAddr, usize>,
+}
+
+/// Limits of `--max-connections` and `--max-connections-per-ip`, along with the requests
+/// being handled, shared by all workers.
+///
+/// A request counts until its response is sent completely, so downloads count for as long
+/// as they take.
+#[derive(Clone)]
+pub struct ConnectionLimits {
+    max_total: Option<usize>,
+    max_per_ip: Option<usize>,
+    active: Arc<Mutex<Active>>,
+}
+
+/// Counts a request as active until it is dropped along with its response
+pub struct ConnectionGuard {
+    limits: ConnectionLimits,
+    ip: Option<IpAddr>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut active = self.limits.active.lock().unwrap();
+        active.total -= 1;
+        if let Some(ip) = self.ip {
+            if let Some(count) = active.by_ip.get_mut(&ip) {
+                *count -= 1;
+                if *count == 0 {
+                    active.by_ip.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+impl ConnectionLimits {
+    /// Returns the limits, if any of them is set
+    pub fn new(max_total: Option<usize>, max_per_ip: Option<usize>) -> Option<Self> {
+        if max_total.is_none() && max_per_ip.is_none() {
+            return None;
+        }
+        Some(ConnectionLimits {
+            max_total,
+            max_per_ip,
+            active: Arc::default(),
+        })
+    }
+
+    /// Counts a request from `ip` as active, or returns the error to reject it with if that
+    /// would exceed one of the limits
+    fn acquire(&self, ip: Option<IpAddr>) -> Result<ConnectionGuard, ContextualError> {
+        let mut active = self.active.lock().unwrap();
+        if self.max_total.map_or(false, |max| active.total >= max) {
+            return Err(ContextualError::ServerBusyError);
+        }
+        if let Some(ip) = ip {
+            let count = active.by_ip.get(&ip).copied().unwrap_or(0);
+            if self.max_per_ip.map_or(false, |max| count >= max) {
+                return Err(ContextualError::TooManyConnectionsError(ip.to_string()));
+            }
+            active.by_ip.insert(ip, count + 1);
+        }
+        active.total += 1;
+
+        Ok(ConnectionGuard {
+            limits: self.clone(),
+            ip,
+        })
+    }
+}
+
+/// Body keeping its request counted as active until it is sent
+struct GuardedBody {
+    body: ResponseBody<Body>,
+    _guard: ConnectionGuard,
+}
+
+impl MessageBody for GuardedBody {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        Pin::new(&mut self.get_mut().body).poll_next(cx)
+    }
+}
+
+/// Counts the request as active if the limits allow it.
+///
+/// Returns the response to send instead if the request has to be rejected, with 503 Service
+/// Unavailable if there are too many requests overall and 429 Too Many Requests if there are
+/// too many from the client.
+pub fn check_request(req: &ServiceRequest) -> Result<Option<ConnectionGuard>, HttpResponse> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let limits = match &conf.connection_limits {
+        Some(limits) => limits,
+        None => return Ok(None),
+    };
+
+    let error = match limits.acquire(proxy::client_ip(req)) {
+        Ok(guard) => return Ok(Some(guard)),
+        Err(error) => error,
+    };
+    errors::log_error_chain(error.to_string());
+
+    let status = match error {
+        ContextualError::ServerBusyError => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::TOO_MANY_REQUESTS,
+    };
+    Err(HttpResponse::build(status)
+        .header(header::RETRY_AFTER, RETRY_AFTER)
+        .content_type("text/html; charset=utf-8")
+        .body(
+            renderer::render_error(
+                &error.to_string(),
+                status,
+                "/",
+                None,
+                None,
+                false,
+                false,
+                &conf.favicon_route,
+                &conf.css_route,
+                &conf.reloadable.get().default_color_scheme,
+                &conf.reloadable.get().default_color_scheme_dark,
+                conf.hide_version_footer,
+                conf.templates.as_ref(),
+                Language::negotiate(req.headers(), conf.lang),
+            )
+            .into_string(),
+        ))
+}
+
+/// Keeps the request counted as active until `res` is sent completely
+pub fn hold(res: ServiceResponse, guard: Option<ConnectionGuard>) -> ServiceResponse {
+    match guard {
+        Some(guard) => res.map_body(|_, body| {
+            ResponseBody::Body(Body::from_message(GuardedBody {
+                body,
+                _guard: guard,
+            }))
+        }),
+        None => res,
+    }
+}
@@ -0,0 +1,122 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, HeaderValue, Method};
+use actix_web::HttpResponse;
+
+/// Methods other origins may use unless `--cors-allow-methods` is given
+pub const DEFAULT_METHODS: [&str; 3] = ["GET", "HEAD", "POST"];
+
+/// Response headers scripts of other origins may read besides the safelisted ones
+const EXPOSED_HEADERS: &str = "content-disposition, etag";
+
+/// How long browsers may cache the answer to a preflight request, in seconds
+const MAX_AGE: &str = "86400";
+
+/// Returns the value of `Access-Control-Allow-Origin` for the request, if it comes from an
+/// allowed origin.
+///
+/// Any origin is answered with `*`, so that browsers never send credentials along. Only
+/// origins which are given explicitly are echoed, and may use credentials.
+pub fn allowed_origin(req: &ServiceRequest) -> Option<HeaderValue> {
+    let conf = req.app_data::<crate::MiniserveConfig>()?;
+    if conf.cors_allowed_origins.is_empty() {
+        return None;
+    }
+    let origin = req.headers().get(header::ORIGIN)?;
+    let origin_str = origin.to_str().ok()?.trim_end_matches('/');
+
+    if conf
+        .cors_allowed_origins
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(origin_str))
+    {
+        Some(origin.clone())
+    } else if conf
+        .cors_allowed_origins
+        .iter()
+        .any(|allowed| allowed == "*")
+    {
+        Some(HeaderValue::from_static("*"))
+    } else {
+        None
+    }
+}
+
+/// Answers the preflight request browsers send before cross-origin requests which aren't
+/// simple, like uploads or requests with credentials.
+///
+/// Returns the response to send instead of passing the request on, if it is a preflight.
+/// Preflights never carry credentials, so this has to happen before authentication.
+pub fn preflight(req: &ServiceRequest) -> Option<HttpResponse> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    if conf.cors_allowed_origins.is_empty() || req.method() != Method::OPTIONS {
+        return None;
+    }
+    let requested_method = req
+        .headers()
+        .get(header::ACCESS_CONTROL_REQUEST_METHOD)?
+        .to_str()
+        .ok()?;
+    req.headers().get(header::ORIGIN)?;
+
+    let is_allowed_method = conf
+        .cors_allowed_methods
+        .iter()
+        .any(|method| method.eq_ignore_ascii_case(requested_method));
+    let origin = match allowed_origin(req) {
+        Some(origin) if is_allowed_method => origin,
+        _ => return Some(HttpResponse::Forbidden().finish()),
+    };
+
+    let credentials = allows_credentials(conf, &origin);
+    let mut res = HttpResponse::NoContent();
+    res.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+        .header(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            conf.cors_allowed_methods.join(", "),
+        )
+        .header(header::ACCESS_CONTROL_MAX_AGE, MAX_AGE)
+        .header(header::VARY, "origin");
+    if let Some(requested_headers) = req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+        res.header(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            requested_headers.clone(),
+        );
+    }
+    if credentials {
+        res.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+    }
+    Some(res.finish())
+}
+
+/// Returns whether scripts of the allowed `origin` may send credentials, which is only the case
+/// if authentication is required and the origin was given explicitly rather than by `*`
+fn allows_credentials(conf: &crate::MiniserveConfig, origin: &HeaderValue) -> bool {
+    *origin != "*" && !conf.reloadable.get().auth.is_empty()
+}
+
+/// Lets scripts of the allowed `origin` read `res`, if the request came from one
+pub fn apply<B>(mut res: ServiceResponse<B>, origin: Option<HeaderValue>) -> ServiceResponse<B> {
+    let origin = match origin {
+        Some(origin) => origin,
+        None => return res,
+    };
+    let credentials = res
+        .request()
+        .app_data::<crate::MiniserveConfig>()
+        .map_or(false, |conf| allows_credentials(conf, &origin));
+
+    let headers = res.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    headers.insert(
+        header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        HeaderValue::from_static(EXPOSED_HEADERS),
+    );
+    if credentials {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+    headers.append(header::VARY, HeaderValue::from_static("origin"));
+    res
+}
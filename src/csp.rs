@@ -0,0 +1,67 @@
+use actix_web::body::{Body, ResponseBody};
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{self, HeaderValue};
+
+use crate::errors::ContextualError;
+
+/// Content-Security-Policy of generated pages, unless `--csp` is given.
+///
+/// Only the inline scripts of the renderer run, as they carry the nonce of the page. Anything
+/// else, like scripts in `HEADER.html` of an uploaded directory, is blocked.
+pub const DEFAULT_POLICY: &str = "default-src 'self'; script-src 'nonce-{nonce}'; \
+    style-src 'self'; img-src 'self' data:; object-src 'none'; base-uri 'none'; \
+    form-action 'self'; frame-ancestors 'self'";
+
+/// Placeholder of the policy replaced by the nonce of the page
+const NONCE_VARIABLE: &str = "{nonce}";
+
+/// Parses the policy of `--csp`, which has to be a valid header value
+pub fn parse_policy(src: &str) -> Result<String, ContextualError> {
+    HeaderValue::from_str(src)
+        .map(|_| src.trim().to_string())
+        .map_err(|_| {
+            ContextualError::ParseError(
+                "Content-Security-Policy".to_string(),
+                format!("{} is not a valid header value", src),
+            )
+        })
+}
+
+/// Sends the Content-Security-Policy with the page generated by `res`.
+///
+/// Pages are rendered with `nonce_placeholder` in place of their nonce, so that they can be
+/// cached. It is replaced by a fresh nonce in each response, and never leaves the server, so
+/// it can't be used by anything injected into a page. Files are sent as they are, since their
+/// bodies are streamed rather than generated.
+pub fn apply(res: ServiceResponse) -> ServiceResponse {
+    let (policy, nonce_placeholder) = match res.request().app_data::<crate::MiniserveConfig>() {
+        Some(conf) => (conf.csp.clone(), conf.nonce_placeholder.clone()),
+        None => return res,
+    };
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| content_type.starts_with("text/html"));
+    let page = match res.response().body() {
+        ResponseBody::Body(Body::Bytes(page)) if is_html => page,
+        _ => return res,
+    };
+    let page = match std::str::from_utf8(page) {
+        Ok(page) => page,
+        Err(_) => return res,
+    };
+
+    let nonce = nanoid::nanoid!();
+    let page = page.replace(&nonce_placeholder, &nonce);
+    let policy = policy.replace(NONCE_VARIABLE, &nonce);
+
+    let mut res = res.map_body(|_, _| ResponseBody::Body(Body::from(page)));
+    if !res.headers().contains_key(header::CONTENT_SECURITY_POLICY) {
+        if let Ok(policy) = HeaderValue::from_str(&policy) {
+            res.headers_mut()
+                .insert(header::CONTENT_SECURITY_POLICY, policy);
+        }
+    }
+    res
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::ignore_files::IgnoreFiles;
+
+/// Time after which a cached size is recomputed in the background
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Maximum number of directories whose sizes are kept
+const MAX_ENTRIES: usize = 10_000;
+
+/// Cached size of one directory
+#[derive(Default)]
+struct CachedSize {
+    /// Last computed size in bytes, if any
+    size: Option<u64>,
+
+    /// When `size` was computed
+    computed_at: Option<Instant>,
+
+    /// Whether a computation is currently queued or running
+    computing: bool,
+}
+
+impl CachedSize {
+    fn is_outdated(&self) -> bool {
+        self.computed_at
+            .map_or(true, |computed_at| computed_at.elapsed() > MAX_AGE)
+    }
+}
+
+type Sizes = Arc<Mutex<HashMap<PathBuf, CachedSize>>>;
+
+/// Directory whose size is to be computed, along with what the listing shows of it
+struct Job {
+    dir: PathBuf,
+    root: PathBuf,
+    show_hidden: bool,
+    ignore_files: bool,
+}
+
+/// Recursive directory sizes, computed one after the other in a background thread and cached
+#[derive(Clone)]
+pub struct DirSizeCache {
+    sizes: Sizes,
+    jobs: Arc<Mutex<mpsc::Sender<Job>>>,
+}
+
+impl Default for DirSizeCache {
+    fn default() -> Self {
+        let sizes = Sizes::default();
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let worker_sizes = sizes.clone();
+        thread::Builder::new()
+            .name("dir-sizes".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    compute(&worker_sizes, job);
+                }
+            })
+            .expect("Failed to spawn directory size thread");
+
+        DirSizeCache {
+            sizes,
+            jobs: Arc::new(Mutex::new(sender)),
+        }
+    }
+}
+
+impl DirSizeCache {
+    /// Returns the last known size of `dir`, if any.
+    ///
+    /// Unknown or outdated sizes are (re)computed in the background so that listings are
+    /// never held up by large trees; they show up on a later request. Only the entries which
+    /// the listing shows with `conf` are counted.
+    pub fn get(&self, dir: &Path, conf: &crate::MiniserveConfig) -> Option<u64> {
+        let mut sizes = self.sizes.lock().unwrap();
+        if sizes.len() >= MAX_ENTRIES && !sizes.contains_key(dir) {
+            evict(&mut sizes);
+        }
+        let cached = sizes.entry(dir.to_path_buf()).or_default();
+
+        if cached.is_outdated() && !cached.computing {
+            cached.computing = true;
+            let _ = self.jobs.lock().unwrap().send(Job {
+                dir: dir.to_path_buf(),
+                root: conf.path.clone(),
+                show_hidden: conf.show_hidden,
+                ignore_files: conf.ignore_files,
+            });
+        }
+
+        cached.size
+    }
+
+    /// Forgets all sizes, e.g. after the served directory has been replaced
+    pub fn clear(&self) {
+        self.sizes.lock().unwrap().clear();
+    }
+}
+
+/// Makes room in the full `sizes`, by dropping outdated sizes or, if there are none, all
+/// sizes which aren't being computed
+fn evict(sizes: &mut HashMap<PathBuf, CachedSize>) {
+    sizes.retain(|_, cached| cached.computing || !cached.is_outdated());
+    if sizes.len() >= MAX_ENTRIES {
+        sizes.retain(|_, cached| cached.computing);
+    }
+}
+
+/// Computes the size of the directory of `job` and stores it in `sizes`, unless a walk of one
+/// of its parents already did
+fn compute(sizes: &Sizes, job: Job) {
+    let is_done = sizes
+        .lock()
+        .unwrap()
+        .get(&job.dir)
+        .map_or(true, |cached| !cached.computing);
+    if is_done {
+        return;
+    }
+
+    let ignore_files = if job.ignore_files {
+        Some(IgnoreFiles::for_dir(&job.root, &job.dir))
+    } else {
+        None
+    };
+    recursive_size(sizes, &job.dir, job.show_hidden, ignore_files.as_ref());
+}
+
+/// Sums up the sizes of all files below `dir` which the listings show, and stores the sizes of
+/// `dir` and of the directories below it which are in `sizes`, so that they aren't walked again.
+///
+/// Symlinks are not followed so that loops can't keep the computation running forever,
+/// and unreadable entries are skipped.
+fn recursive_size(
+    sizes: &Sizes,
+    dir: &Path,
+    show_hidden: bool,
+    ignore_files: Option<&IgnoreFiles>,
+) -> u64 {
+    let mut size = 0;
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            let path = entry.path();
+            if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if let Some(ignore_files) = ignore_files {
+                if ignore_files.is_ignored(&path, metadata.is_dir()) {
+                    continue;
+                }
+            }
+
+            if metadata.is_dir() {
+                let ignore_files = ignore_files.map(|ignore_files| ignore_files.with_dir(&path));
+                size += recursive_size(sizes, &path, show_hidden, ignore_files.as_ref());
+            } else if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
+    }
+
+    if let Some(cached) = sizes.lock().unwrap().get_mut(dir) {
+        cached.size = Some(size);
+        cached.computed_at = Some(Instant::now());
+        cached.computing = false;
+    }
+    size
+}
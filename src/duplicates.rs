@@ -0,0 +1,196 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures::lock::Mutex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::checksum;
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::ignore_files::IgnoreFiles;
+use crate::renderer;
+use crate::slow_ops;
+
+/// Time for which found duplicates are shown before the served directory is scanned again
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Files with the same content
+pub struct DuplicateGroup {
+    /// Size in byte of each of the files
+    pub size: u64,
+
+    /// Paths of the files relative to the served directory, sorted
+    pub paths: Vec<String>,
+}
+
+impl DuplicateGroup {
+    /// Size in byte which removing all but one of the files would free
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Collects the sizes of the files below `dir` which the listings show.
+///
+/// Like for the stats of the served directory, symlinks are not followed and unreadable
+/// entries are skipped.
+fn collect_files(
+    dir: &Path,
+    show_hidden: bool,
+    ignore_files: Option<&IgnoreFiles>,
+    files: &mut HashMap<u64, Vec<PathBuf>>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&path, metadata.is_dir()) {
+                continue;
+            }
+        }
+
+        if metadata.is_dir() {
+            let ignore_files = ignore_files.map(|ignore_files| ignore_files.with_dir(&path));
+            collect_files(&path, show_hidden, ignore_files.as_ref(), files);
+        } else if metadata.is_file() && metadata.len() > 0 {
+            files.entry(metadata.len()).or_default().push(path);
+        }
+    }
+}
+
+/// Finds the files below `root` with the same content, the groups freeing the most space
+/// first.
+///
+/// Only files of the same size can have the same content, so only those are hashed.
+fn find_duplicates(
+    root: &Path,
+    show_hidden: bool,
+    ignore_files: Option<&IgnoreFiles>,
+) -> Vec<DuplicateGroup> {
+    let mut files = HashMap::new();
+    collect_files(root, show_hidden, ignore_files, &mut files);
+
+    let mut groups = Vec::new();
+    for (size, paths) in files.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        let mut by_checksum = HashMap::<String, Vec<String>>::new();
+        for path in paths {
+            let checksum = match checksum::sha256_checksum(&path) {
+                Ok(checksum) => checksum,
+                Err(_) => continue,
+            };
+            let relative_path = path.strip_prefix(root).unwrap_or(&path);
+            by_checksum
+                .entry(checksum)
+                .or_default()
+                .push(relative_path.to_string_lossy().to_string());
+        }
+        groups.extend(
+            by_checksum
+                .into_values()
+                .filter(|paths| paths.len() > 1)
+                .map(|mut paths| {
+                    paths.sort();
+                    DuplicateGroup { size, paths }
+                }),
+        );
+    }
+
+    groups.sort_by(|a, b| {
+        b.reclaimable()
+            .cmp(&a.reclaimable())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+/// Duplicates found in each served directory, cached for `MAX_AGE`
+#[derive(Clone, Default)]
+pub struct DuplicatesCache {
+    /// Held during scans, so that concurrent requests wait for one scan instead of each
+    /// starting their own
+    groups: Arc<Mutex<HashMap<PathBuf, (Instant, Arc<Vec<DuplicateGroup>>)>>>,
+}
+
+impl DuplicatesCache {
+    /// Returns the duplicates in the served directory, scanning it on the blocking thread pool
+    /// if the cached ones are outdated
+    async fn get(
+        &self,
+        conf: &crate::MiniserveConfig,
+    ) -> Result<Arc<Vec<DuplicateGroup>>, ContextualError> {
+        let root = conf.path.canonicalize().map_err(|e| {
+            ContextualError::IoError(format!("Failed to resolve {}", conf.path.display()), e)
+        })?;
+
+        let mut cached_groups = self.groups.lock().await;
+        if let Some((computed_at, groups)) = cached_groups.get(&root) {
+            if computed_at.elapsed() <= MAX_AGE {
+                return Ok(groups.clone());
+            }
+        }
+
+        let show_hidden = conf.show_hidden;
+        let ignore_files = if conf.ignore_files {
+            Some(IgnoreFiles::for_dir(&root, &root))
+        } else {
+            None
+        };
+        let slow_op_threshold = conf.slow_op_threshold;
+        let scanned_root = root.clone();
+
+        let groups = web::block(move || {
+            Ok::<_, ContextualError>(slow_ops::timed(
+                slow_op_threshold,
+                "finding duplicates in",
+                &scanned_root,
+                || find_duplicates(&scanned_root, show_hidden, ignore_files.as_ref()),
+            ))
+        })
+        .await?;
+
+        let groups = Arc::new(groups);
+        cached_groups.insert(root, (Instant::now(), groups.clone()));
+        Ok(groups)
+    }
+
+    /// Forgets the duplicates, e.g. after the served directory has been replaced.
+    ///
+    /// Scans which are running are waited for, so that their outdated results are dropped too.
+    pub fn clear(&self) {
+        let groups = self.groups.clone();
+        actix_web::rt::spawn(async move { groups.lock().await.clear() });
+    }
+}
+
+/// Lists the files of the served directory which have the same content
+pub async fn duplicates(req: HttpRequest) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let lang = Language::negotiate(req.headers(), conf.lang);
+    let cache = match &conf.duplicates {
+        Some(cache) => cache,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    match cache.get(conf).await {
+        Ok(groups) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(renderer::duplicates_page(&groups, conf, lang).into_string()),
+        Err(e) => {
+            errors::log_error_chain(e.to_string());
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -1,3 +1,10 @@
+use actix_web::dev::ServiceResponse;
+use actix_web::error::BlockingError;
+use actix_web::http::{header, StatusCode};
+use actix_web::HttpResponse;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,6 +21,14 @@ pub enum ContextualError {
     #[error("File already exists, and the overwrite_files option has not been set")]
     DuplicateFileError,
 
+    /// Might occur during file upload, when another upload is writing to the same file
+    #[error("{0} is currently being written by another upload")]
+    UploadConflictError(String),
+
+    /// Might occur when renaming an entry to the name of another one
+    #[error("An entry named {0} already exists")]
+    EntryExistsError(String),
+
     /// Any error related to an invalid path (failed to retrieve entry name, unexpected entry type, etc)
     #[error("Invalid path\ncaused by: {0}")]
     InvalidPathError(String),
@@ -46,6 +61,10 @@ pub enum ContextualError {
     #[error("An error occured while creating the {0}\ncaused by: {1}")]
     ArchiveCreationError(String, Box<ContextualError>),
 
+    /// Might occur when the content of an archive exceeds the --max-archive-size option
+    #[error("The archive would contain {0}, more than the maximum of {1}")]
+    ArchiveTooLargeError(String, String),
+
     /// More specific archive creation failure reason
     #[error("{0}")]
     ArchiveCreationDetailError(String),
@@ -71,13 +90,132 @@ pub enum ContextualError {
 Please set an explicit serve path like: `miniserve /my/path`")]
     NoExplicitPathAndNoTerminal,
 
+    /// Might occur with --check if the file of --index doesn't exist
+    #[error("The file '{0}' provided for option --index could not be found")]
+    MissingIndexFileError(String),
+
+    /// Might occur if miniserve is not permitted to bind to a privileged port (<1024)
+    #[error("Insufficient permissions to bind to privileged port {0}\n{1}")]
+    PrivilegedPortError(u16, String),
+
+    /// Might occur if the GeoIP database can't be opened
+    #[error("Failed to open GeoIP database {0}\ncaused by: {1}")]
+    GeoIpDatabaseError(String, String),
+
+    /// Might occur if the TLS certificate or key can't be used
+    #[error("Failed to set up TLS with {0}\ncaused by: {1}")]
+    TlsConfigError(String, String),
+
+    /// Might occur if plain HTTP should be redirected to HTTPS, but HTTPS isn't served
+    #[error("The --redirect-http-port option requires serving HTTPS with --tls-cert, --tls-self-signed or --acme-domain")]
+    RedirectWithoutTlsError,
+
+    /// Might occur if no certificate can be obtained from Let's Encrypt
+    #[error("Failed to obtain a certificate for {0}\ncaused by: {1}")]
+    AcmeError(String, String),
+
+    /// Might occur if the custom templates can't be loaded
+    #[error("Failed to load templates from {0}\ncaused by: {1}")]
+    TemplateError(String, String),
+
+    /// Might occur when a client is not allowed to access miniserve from its country
+    #[error("Access from {0} is not allowed")]
+    CountryNotAllowedError(String),
+
+    /// Might occur when a request is addressed to a host that is not allowed
+    #[error("Host '{0}' is not allowed")]
+    HostNotAllowedError(String),
+
+    /// Might occur when more requests than allowed by --max-connections are being handled
+    #[error("The server is handling too many requests, try again later")]
+    ServerBusyError,
+
+    /// Might occur when a client makes more requests than allowed by --max-connections-per-ip
+    #[error("Too many concurrent requests from {0}")]
+    TooManyConnectionsError(String),
+
+    /// Might occur when serving several directories and the name of one can't be used in URLs
+    #[error("{0} can't be served along with other directories, since its name contains characters other than letters, digits, '-', '.', '_' and '~'")]
+    InvalidMountError(String),
+
+    /// Might occur when the config file of --config isn't valid TOML or has unsupported values
+    #[error("Invalid config file\ncaused by: {0}")]
+    ConfigFileError(String),
+
+    /// Might occur when an interface given by name to --interfaces can't be bound
+    #[error("The network interface {0} doesn't exist or has no address")]
+    InterfaceNotFoundError(String),
+
+    /// Might occur when serving several directories with the same name
+    #[error("Several of the directories to serve are named {0}")]
+    DuplicateMountError(String),
+
+    /// Might occur if the public IP address can't be discovered for --show-public-url
+    #[error("Failed to discover the public IP address\ncaused by: {0}")]
+    PublicIpError(String),
+
     /// In case miniserve was invoked with --no-symlinks but the serve path is a symlink
     #[error("The -P|--no-symlinks option was provided but the serve path '{0}' is a symlink")]
     NoSymlinksOptionWithSymlinkServePath(String),
 }
 
+impl From<BlockingError<ContextualError>> for ContextualError {
+    fn from(e: BlockingError<ContextualError>) -> Self {
+        match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => ContextualError::IoError(
+                "Blocking operation was canceled".to_string(),
+                std::io::Error::from(std::io::ErrorKind::Interrupted),
+            ),
+        }
+    }
+}
+
 pub fn log_error_chain(description: String) {
     for cause in description.lines() {
         log::error!("{}", cause);
     }
 }
+
+/// Reads the custom error pages of `--error-page` and `--error-page-404` at startup
+pub fn load_error_pages(
+    files: &[(StatusCode, PathBuf)],
+) -> Result<HashMap<StatusCode, String>, ContextualError> {
+    files
+        .iter()
+        .map(|(status, path)| {
+            let page = fs::read_to_string(path).map_err(|e| {
+                ContextualError::IoError(format!("Failed to read error page {}", path.display()), e)
+            })?;
+            Ok((*status, page))
+        })
+        .collect()
+}
+
+/// Replaces the body of an error response by the custom page for its status, if there is one.
+///
+/// The status and headers, like `WWW-Authenticate`, are kept. The Content-Security-Policy is
+/// dropped, as it was made for the replaced page.
+pub fn custom_error_page(res: ServiceResponse) -> ServiceResponse {
+    let page = res
+        .request()
+        .app_data::<crate::MiniserveConfig>()
+        .and_then(|conf| conf.error_pages.get(&res.status()))
+        .cloned();
+    let page = match page {
+        Some(page) => page,
+        None => return res,
+    };
+
+    let mut custom = HttpResponse::build(res.status());
+    for (name, value) in res.headers() {
+        if name != header::CONTENT_TYPE
+            && name != header::CONTENT_LENGTH
+            && name != header::CONTENT_SECURITY_POLICY
+        {
+            custom.header(name.clone(), value.clone());
+        }
+    }
+    let custom = custom.content_type("text/html; charset=utf-8").body(page);
+    res.into_response(custom)
+}
@@ -0,0 +1,268 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::web::{self, Bytes};
+use actix_web::{HttpRequest, HttpResponse};
+use flate2::read::GzDecoder;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::ignore_files::IgnoreFiles;
+use crate::listing;
+use crate::path_policy;
+use crate::renderer;
+
+/// Formats of archives which can be extracted on the server
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtractFormat {
+    Zip,
+    TarGz,
+}
+
+impl ExtractFormat {
+    /// Suffixes of the file names of archives, matched case insensitively
+    const SUFFIXES: [(&'static str, ExtractFormat); 3] = [
+        (".zip", ExtractFormat::Zip),
+        (".tar.gz", ExtractFormat::TarGz),
+        (".tgz", ExtractFormat::TarGz),
+    ];
+
+    /// Splits the file name of an archive into the name of the directory it's extracted to
+    /// and its format, or returns `None` if it's not an archive which can be extracted
+    pub fn from_name(name: &str) -> Option<(&str, ExtractFormat)> {
+        Self::SUFFIXES.iter().find_map(|(suffix, format)| {
+            let stem_len = name.len().checked_sub(suffix.len())?;
+            let is_match = name.is_char_boundary(stem_len)
+                && name[stem_len..].eq_ignore_ascii_case(suffix)
+                && stem_len > 0;
+            if is_match {
+                Some((&name[..stem_len], *format))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Extracts all files and directories of the archive at `archive` into `dir`.
+    ///
+    /// Members which would end up outside of `dir` make the whole archive fail. Symlinks,
+    /// hard links and special files are skipped, so nothing extracted can point elsewhere.
+    /// Members for which `is_excluded` returns `true`, given their path relative to `dir` and
+    /// whether they are directories, are skipped as well.
+    fn extract(
+        self,
+        archive: &Path,
+        dir: &Path,
+        is_excluded: impl Fn(&Path, bool) -> bool,
+    ) -> Result<(), ContextualError> {
+        let io_error = |e: io::Error| {
+            ContextualError::IoError(format!("Failed to extract {}", archive.display()), e)
+        };
+        let file = File::open(archive).map_err(io_error)?;
+
+        match self {
+            ExtractFormat::Zip => {
+                let mut zip = zip::ZipArchive::new(file).map_err(|e| {
+                    ContextualError::ParseError(archive.display().to_string(), e.to_string())
+                })?;
+                for i in 0..zip.len() {
+                    let mut member = zip.by_index(i).map_err(|e| {
+                        ContextualError::ParseError(archive.display().to_string(), e.to_string())
+                    })?;
+                    let relative_path = member_path(Path::new(member.name()))?;
+                    if is_excluded(&relative_path, member.is_dir()) {
+                        continue;
+                    }
+                    let path = dir.join(relative_path);
+                    let is_symlink = member
+                        .unix_mode()
+                        .map_or(false, |mode| mode & 0o170000 == 0o120000);
+                    if member.is_dir() {
+                        fs::create_dir_all(&path).map_err(io_error)?;
+                    } else if !is_symlink {
+                        create_parent(&path).map_err(io_error)?;
+                        let mut out = File::create(&path).map_err(io_error)?;
+                        io::copy(&mut member, &mut out).map_err(io_error)?;
+                    }
+                }
+            }
+            ExtractFormat::TarGz => {
+                let mut tar = tar::Archive::new(GzDecoder::new(file));
+                for member in tar.entries().map_err(io_error)? {
+                    let mut member = member.map_err(io_error)?;
+                    let relative_path = member_path(&member.path().map_err(io_error)?)?;
+                    let entry_type = member.header().entry_type();
+                    if is_excluded(&relative_path, entry_type.is_dir()) {
+                        continue;
+                    }
+                    let path = dir.join(relative_path);
+                    if entry_type.is_dir() {
+                        fs::create_dir_all(&path).map_err(io_error)?;
+                    } else if entry_type.is_file() {
+                        create_parent(&path).map_err(io_error)?;
+                        member.unpack(&path).map_err(io_error)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns where the archive member `member` is extracted to, relative to the directory the
+/// archive is extracted into
+fn member_path(member: &Path) -> Result<PathBuf, ContextualError> {
+    match path_policy::enclosed_path(member) {
+        Some(path) => Ok(path),
+        None => Err(ContextualError::InvalidPathError(format!(
+            "archive member {} would be extracted outside of its directory",
+            member.display()
+        ))),
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+/// Extracts the archive `name` of `dir` into a new directory `target_name` next to it.
+///
+/// The archive is extracted into a hidden temporary directory first, which is only renamed to
+/// `target_name` once all of it was extracted, so that failed extractions leave nothing behind.
+/// Members which wouldn't be listed in `target_name` are left out.
+fn extract_entry(
+    dir: &Path,
+    name: &OsStr,
+    target_name: &str,
+    format: ExtractFormat,
+    show_hidden: bool,
+    ignore_files: Option<IgnoreFiles>,
+) -> Result<(), ContextualError> {
+    let target = dir.join(target_name);
+    if target.symlink_metadata().is_ok() {
+        return Err(ContextualError::EntryExistsError(target_name.to_string()));
+    }
+
+    let partial = dir.join(format!(
+        ".{}.{}.extracting",
+        target_name,
+        nanoid::nanoid!(6)
+    ));
+    fs::create_dir(&partial).map_err(|e| {
+        ContextualError::IoError(format!("Failed to create {}", partial.display()), e)
+    })?;
+    let is_excluded = |member: &Path, is_dir: bool| {
+        (!show_hidden && path_policy::is_hidden(member))
+            || (!is_dir
+                && member
+                    .file_name()
+                    .map_or(false, listing::Annotations::is_reserved_name))
+            || ignore_files.as_ref().map_or(false, |ignore_files| {
+                ignore_files.is_ignored(&target.join(member), is_dir)
+            })
+    };
+    let result = format
+        .extract(&dir.join(name), &partial, is_excluded)
+        .and_then(|()| {
+            fs::rename(&partial, &target).map_err(|e| {
+                ContextualError::IoError(format!("Failed to create {}", target.display()), e)
+            })
+        });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&partial);
+    }
+    result
+}
+
+/// Extracts an archive of a directory into a new directory next to it, named like the archive
+/// without its extension
+///
+/// Like for uploads, the directory is given by the `path` query parameter. The form data
+/// contains the `name` of the archive, which must be a file the listing would show.
+pub async fn extract(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let return_path = req
+        .headers()
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok())
+        .unwrap_or("/")
+        .to_string();
+    let error_response = |error: ContextualError, status: StatusCode| {
+        errors::log_error_chain(error.to_string());
+        HttpResponse::build(status)
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    status,
+                    &return_path,
+                    None,
+                    None,
+                    true,
+                    conf.random_route.is_none(),
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            )
+    };
+    let invalid_request = |description: String| {
+        error_response(
+            ContextualError::InvalidHttpRequestError(description),
+            StatusCode::BAD_REQUEST,
+        )
+    };
+
+    let name = match form_urlencoded::parse(&body).find(|(key, _)| key == "name") {
+        Some((_, name)) => OsString::from(name.into_owned()),
+        None => return invalid_request("Missing 'name' field".to_string()),
+    };
+
+    // The directory has to be inside the served directory, like the target of uploads
+    let query_params = listing::extract_query_parameters(&req);
+    let dir_path = query_params.path.unwrap_or_default();
+    let dir = match path_policy::resolve_request_path(&conf.path, &dir_path) {
+        Ok(dir) if dir.is_dir() => dir,
+        _ => return invalid_request("Invalid value for 'path' parameter".to_string()),
+    };
+
+    let is_file = listing::is_listed_entry(&dir, &name, conf) && dir.join(&name).is_file();
+    let name_str = name.to_string_lossy().to_string();
+    let (target_name, format) = match ExtractFormat::from_name(&name_str) {
+        Some((target_name, format)) if is_file => (target_name.to_string(), format),
+        _ => return invalid_request(format!("'{}' is not an archive", name_str)),
+    };
+
+    let show_hidden = conf.show_hidden;
+    let ignore_files = if conf.ignore_files {
+        Some(IgnoreFiles::for_dir(&conf.path, &dir))
+    } else {
+        None
+    };
+
+    log::info!("Extracting {} in {}", name_str, dir.display());
+    let result = web::block(move || {
+        extract_entry(&dir, &name, &target_name, format, show_hidden, ignore_files)
+    })
+    .await;
+    match result.map_err(ContextualError::from) {
+        Ok(()) => HttpResponse::SeeOther()
+            .header(header::LOCATION, return_path.as_str())
+            .finish(),
+        Err(e @ ContextualError::EntryExistsError(_)) => error_response(e, StatusCode::CONFLICT),
+        Err(e @ ContextualError::InvalidPathError(_))
+        | Err(e @ ContextualError::ParseError(..)) => error_response(e, StatusCode::BAD_REQUEST),
+        Err(e) => error_response(e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
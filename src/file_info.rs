@@ -0,0 +1,356 @@
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{self, ContentDisposition, DispositionType, IntoHeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::web::Query;
+use actix_web::{HttpRequest, HttpResponse};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use strum_macros::{Display, EnumIter};
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::ignore_files::IgnoreFiles;
+use crate::path_policy;
+use crate::permissions::{OwnerNames, Permissions};
+use crate::renderer;
+use crate::slow_ops;
+
+/// MIME type of the files which can be previewed
+const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// Query parameters of the file info route
+#[derive(Deserialize)]
+pub struct FileInfoParameters {
+    /// Percent-encoded path of the file, as linked in the listing
+    path: String,
+
+    /// Whether to compute the checksum of the file, which requires reading all of it
+    #[serde(default)]
+    checksum: bool,
+}
+
+/// Query parameters of the compressed download route
+#[derive(Deserialize)]
+pub struct CompressedDownloadParameters {
+    /// Percent-encoded path of the file, as linked in the listing
+    path: String,
+
+    /// Format to compress the file in
+    format: FileCompression,
+}
+
+/// Formats single files can be compressed in while they are downloaded
+#[derive(Deserialize, Clone, Copy, EnumIter, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FileCompression {
+    /// gzip
+    Gz,
+
+    /// Zstandard
+    Zst,
+}
+
+impl FileCompression {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileCompression::Gz => "gz",
+            FileCompression::Zst => "zst",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            FileCompression::Gz => "application/gzip",
+            FileCompression::Zst => "application/zstd",
+        }
+    }
+
+    /// Writes all of `reader` compressed in this format to `out`
+    fn compress<R: Read, W: Write>(self, reader: &mut R, out: W) -> io::Result<()> {
+        match self {
+            FileCompression::Gz => {
+                let mut encoder = GzEncoder::new(out, Compression::default());
+                io::copy(reader, &mut encoder)?;
+                encoder.finish()?;
+            }
+            FileCompression::Zst => {
+                let mut encoder = zstd::stream::write::Encoder::new(out, 0)?;
+                io::copy(reader, &mut encoder)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Metadata of a single file shown on its info page
+pub struct FileInfo {
+    /// Name of the file
+    pub name: String,
+
+    /// URL of the file
+    pub link: String,
+
+    /// URL of the directory containing the file
+    pub parent_link: String,
+
+    /// Size in byte of the file
+    pub size: u64,
+
+    /// Last modification date
+    pub last_modification_date: Option<SystemTime>,
+
+    /// MIME type guessed from the extension, as sent when downloading the file
+    pub mime_type: String,
+
+    /// Mode bits, owner and group, only available on unix
+    pub permissions: Option<Permissions>,
+
+    /// Hex-encoded SHA-256 checksum, only computed on demand
+    pub checksum: Option<String>,
+}
+
+impl FileInfo {
+    fn new(name: String, link: String, parent_link: String, metadata: &Metadata) -> Self {
+        let extension = Path::new(&name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        FileInfo {
+            mime_type: actix_files::file_extension_to_mime(&extension).to_string(),
+            name,
+            link,
+            parent_link,
+            size: metadata.len(),
+            last_modification_date: metadata.modified().ok(),
+            permissions: OwnerNames::load().permissions(metadata),
+            checksum: None,
+        }
+    }
+}
+
+/// Responds with a page showing the metadata of a single file
+pub async fn file_info(req: HttpRequest, query: Query<FileInfoParameters>) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let lang = Language::negotiate(req.headers(), conf.lang);
+
+    let (path, mut info) = match resolve_file(&req, &query.path) {
+        Some(file) => file,
+        None => return invalid_path_response(conf, lang),
+    };
+
+    if query.checksum {
+        info.checksum = match conf.checksums.compute(path, conf.slow_op_threshold).await {
+            Ok(checksum) => Some(checksum),
+            Err(e) => {
+                errors::log_error_chain(e.to_string());
+                None
+            }
+        };
+    } else {
+        info.checksum = path
+            .metadata()
+            .ok()
+            .and_then(|metadata| conf.checksums.get(&path, &metadata));
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(renderer::file_info_page(&info, conf, lang).into_string())
+}
+
+/// Responds with the hex-encoded SHA-256 checksum of a single file as plain text
+pub async fn file_checksum(req: HttpRequest, query: Query<FileInfoParameters>) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let path = match resolve_file(&req, &query.path) {
+        Some((path, _)) => path,
+        None => return invalid_path_response(conf, Language::negotiate(req.headers(), conf.lang)),
+    };
+
+    match conf.checksums.compute(path, conf.slow_op_threshold).await {
+        Ok(checksum) => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body(checksum),
+        Err(e) => {
+            errors::log_error_chain(e.to_string());
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Responds with a page embedding the browser's viewer for a single PDF file
+pub async fn file_preview(req: HttpRequest, query: Query<FileInfoParameters>) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let lang = Language::negotiate(req.headers(), conf.lang);
+
+    match resolve_file(&req, &query.path) {
+        Some((_, info)) if info.mime_type == PDF_MIME_TYPE => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(renderer::preview_page(&info, conf, lang).into_string()),
+        _ => invalid_path_response(conf, lang),
+    }
+}
+
+/// Streams a single file compressed on the fly, so that large text files like logs transfer
+/// faster over slow links without being compressed on the server beforehand
+pub async fn compressed_download(
+    req: HttpRequest,
+    query: Query<CompressedDownloadParameters>,
+) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let (path, info) = match resolve_file(&req, &query.path) {
+        Some(file) => file,
+        None => return invalid_path_response(conf, Language::negotiate(req.headers(), conf.lang)),
+    };
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            let err = ContextualError::IoError(format!("Failed to open {}", path.display()), e);
+            errors::log_error_chain(err.to_string());
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let format = query.format;
+    let slow_op_threshold = conf.slow_op_threshold;
+    log::info!(
+        "Compressing {path} ({extension})...",
+        path = path.display(),
+        extension = format.extension()
+    );
+
+    // Like archives, the file is compressed on the archive pool and streamed through a pipe
+    let (tx, rx) = futures::channel::mpsc::channel::<Result<actix_web::web::Bytes, ()>>(10);
+    let pipe = crate::pipe::Pipe::new(tx);
+    conf.archive_pool.spawn(move || {
+        let result = slow_ops::timed(slow_op_threshold, "compressing", &path, || {
+            format.compress(&mut file, pipe)
+        });
+        if let Err(err) = result {
+            log::error!("Error during compression of {}: {:?}", path.display(), err);
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .header("Content-Transfer-Encoding", "binary")
+        .header(
+            "Content-Disposition",
+            format!(
+                "attachment; filename={:?}",
+                format!("{}.{}", info.name, format.extension())
+            ),
+        )
+        .body(actix_web::body::BodyStream::new(rx))
+}
+
+/// Marks PDF downloads as inline, so that browsers show them instead of saving them.
+///
+/// Only the disposition type changes, the file name stays as suggested by the files service.
+pub fn inline_pdf<B>(mut res: ServiceResponse<B>) -> ServiceResponse<B> {
+    let is_pdf = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| {
+            content_type.starts_with(PDF_MIME_TYPE)
+        });
+    if !is_pdf {
+        return res;
+    }
+
+    let disposition = res
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|cd| ContentDisposition::from_raw(cd).ok());
+    if let Some(mut disposition) = disposition {
+        disposition.disposition = DispositionType::Inline;
+        if let Ok(disposition) = IntoHeaderValue::try_into(disposition) {
+            res.headers_mut()
+                .insert(header::CONTENT_DISPOSITION, disposition);
+        }
+    }
+    res
+}
+
+/// Responds to requests for files which can't be looked up
+fn invalid_path_response(conf: &crate::MiniserveConfig, lang: Language) -> HttpResponse {
+    let error =
+        ContextualError::InvalidHttpRequestError("Invalid value for 'path' parameter".to_string());
+    errors::log_error_chain(error.to_string());
+    HttpResponse::NotFound()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            renderer::render_error(
+                &error.to_string(),
+                StatusCode::NOT_FOUND,
+                "/",
+                None,
+                None,
+                false,
+                conf.random_route.is_none(),
+                &conf.favicon_route,
+                &conf.css_route,
+                &conf.reloadable.get().default_color_scheme,
+                &conf.reloadable.get().default_color_scheme_dark,
+                conf.hide_version_footer,
+                conf.templates.as_ref(),
+                lang,
+            )
+            .into_string(),
+        )
+}
+
+/// Resolves the link of a file as found in the listing to the file it refers to, as long as
+/// that file would be listed
+fn resolve_file(req: &HttpRequest, link: &str) -> Option<(PathBuf, FileInfo)> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let route = match conf.random_route {
+        Some(ref random_route) => format!("/{}", random_route),
+        None => String::new(),
+    };
+
+    let encoded_path = link.strip_prefix(&route)?;
+    let (parent_link, encoded_name) = encoded_path.rsplit_once('/')?;
+    let request_path = percent_decode_str(encoded_path).decode_utf8().ok()?;
+    let request_path = Path::new(&*request_path);
+    let name = request_path.file_name()?.to_string_lossy().to_string();
+    if encoded_name.is_empty() || (path_policy::is_hidden(request_path) && !conf.show_hidden) {
+        return None;
+    }
+
+    // Symlinks and ignore files apply to the entry in its directory, not to what it points to
+    let dir = path_policy::resolve_request_path(&conf.path, request_path.parent()?).ok()?;
+    let entry_path = dir.join(&name);
+    let is_symlink = entry_path
+        .symlink_metadata()
+        .map_or(false, |metadata| metadata.file_type().is_symlink());
+    if is_symlink && conf.no_symlinks {
+        return None;
+    }
+    if conf.ignore_files && IgnoreFiles::for_dir(&conf.path, &dir).is_ignored(&entry_path, false) {
+        return None;
+    }
+
+    let path = path_policy::resolve_request_path(&conf.path, request_path).ok()?;
+    let metadata = path.metadata().ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let info = FileInfo::new(
+        name,
+        link.to_string(),
+        format!("{}{}/", route, parent_link),
+        &metadata,
+    );
+    Some((path, info))
+}
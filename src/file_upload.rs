@@ -1,58 +1,130 @@
 use actix_web::{
     http::{header, StatusCode},
-    HttpRequest, HttpResponse,
+    web, HttpRequest, HttpResponse,
 };
-use futures::{future, Future, FutureExt, Stream, TryStreamExt};
+use fs2::FileExt;
+use futures::{future, Future, FutureExt, StreamExt, TryStreamExt};
+use serde::Serialize;
 use std::{
-    io::Write,
-    path::{Component, PathBuf},
+    ffi::OsStr,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
     pin::Pin,
 };
 
 use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
 use crate::listing::{self, SortingMethod, SortingOrder};
+use crate::path_policy;
 use crate::renderer;
+use crate::templates::Templates;
+
+/// Opens the file an upload is saved to, locked exclusively and empty.
+///
+/// The file is locked while it's written, so that concurrent uploads to the same file can't
+/// interleave. Instead, all but the first one fail.
+fn open_locked(file_path: &Path, overwrite_files: bool) -> Result<File, ContextualError> {
+    // The file is only truncated once we hold the lock
+    let file = OpenOptions::new()
+        .write(true)
+        .create(overwrite_files)
+        .create_new(!overwrite_files)
+        .open(file_path)
+        .map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => ContextualError::DuplicateFileError,
+            _ => ContextualError::IoError(format!("Failed to create {}", file_path.display()), e),
+        })?;
+
+    file.try_lock_exclusive().map_err(|e| {
+        if e.kind() == fs2::lock_contended_error().kind() {
+            ContextualError::UploadConflictError(file_path.display().to_string())
+        } else {
+            ContextualError::IoError(format!("Failed to lock {}", file_path.display()), e)
+        }
+    })?;
+    file.set_len(0).map_err(|e| {
+        ContextualError::IoError(format!("Failed to truncate {}", file_path.display()), e)
+    })?;
+
+    Ok(file)
+}
 
 /// Create future to save file.
+///
+/// All file operations run on the blocking thread pool, so that slow disks don't stall other
+/// requests handled by the same worker. The next chunk is only read from the request once the
+/// previous one has been written, which keeps clients from uploading faster than the disk can
+/// take it.
+///
+/// Chunks are collected in a buffer of `buffer_size` bytes before they are written.
 fn save_file(
-    field: actix_multipart::Field,
+    mut field: actix_multipart::Field,
     file_path: PathBuf,
     overwrite_files: bool,
+    buffer_size: usize,
 ) -> Pin<Box<dyn Future<Output = Result<i64, ContextualError>>>> {
-    if !overwrite_files && file_path.exists() {
-        return Box::pin(future::err(ContextualError::DuplicateFileError));
-    }
+    Box::pin(async move {
+        let file = web::block(move || open_locked(&file_path, overwrite_files)).await?;
 
-    let mut file = match std::fs::File::create(&file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            return Box::pin(future::err(ContextualError::IoError(
-                format!("Failed to create {}", file_path.display()),
-                e,
-            )));
+        let mut writer = BufWriter::with_capacity(buffer_size, file);
+        let mut written = 0i64;
+        while let Some(bytes) = field.next().await {
+            let bytes = bytes.map_err(ContextualError::MultipartError)?;
+            written += bytes.len() as i64;
+            writer = web::block(move || {
+                writer
+                    .write_all(&bytes)
+                    .map(|_| writer)
+                    .map_err(|e| ContextualError::IoError("Failed to write to file".to_string(), e))
+            })
+            .await?;
         }
-    };
-    Box::pin(
-        field
-            .map_err(ContextualError::MultipartError)
-            .try_fold(0i64, move |acc, bytes| {
-                let rt = file
-                    .write_all(bytes.as_ref())
-                    .map(|_| acc + bytes.len() as i64)
-                    .map_err(|e| {
-                        ContextualError::IoError("Failed to write to file".to_string(), e)
-                    });
-                future::ready(rt)
-            }),
-    )
+
+        web::block(move || {
+            writer
+                .flush()
+                .map_err(|e| ContextualError::IoError("Failed to write to file".to_string(), e))
+        })
+        .await?;
+
+        Ok(written)
+    })
+}
+
+/// Outcome of uploading one file of a multipart request
+struct UploadResult {
+    /// Name of the file as sent by the client, empty if it is missing
+    name: String,
+
+    /// Number of bytes written, or why the file could not be uploaded
+    result: Result<i64, ContextualError>,
+}
+
+/// Outcome of all files as reported in JSON
+#[derive(Serialize)]
+struct UploadReport<'a> {
+    files: Vec<FileReport<'a>>,
+}
+
+/// Outcome of one file as reported in JSON
+#[derive(Serialize)]
+struct FileReport<'a> {
+    name: &'a str,
+    uploaded: bool,
+    size: Option<i64>,
+    error: Option<String>,
 }
 
 /// Create new future to handle file as multipart data.
+///
+/// Failures only concern this file, the remaining files of the request are still uploaded.
 fn handle_multipart(
     field: actix_multipart::Field,
     mut file_path: PathBuf,
     overwrite_files: bool,
-) -> Pin<Box<dyn Stream<Item = Result<i64, ContextualError>>>> {
+    buffer_size: usize,
+) -> Pin<Box<dyn Future<Output = UploadResult>>> {
     let filename = field
         .headers()
         .get(header::CONTENT_DISPOSITION)
@@ -66,8 +138,23 @@ fn handle_multipart(
                 .ok_or(ContextualError::ParseError)
                 .map(String::from)
         });
-    let err = |e: ContextualError| Box::pin(future::err(e).into_stream());
+    let name = filename.clone().unwrap_or_default();
+    let err = |e: ContextualError| {
+        Box::pin(future::ready(UploadResult {
+            name: name.clone(),
+            result: Err(e),
+        }))
+    };
     match filename {
+        Ok(f)
+            if !path_policy::is_entry_name(Path::new(&f))
+                || listing::Annotations::is_reserved_name(OsStr::new(&f)) =>
+        {
+            err(ContextualError::InvalidPathError(format!(
+                "'{}' is not a valid file name",
+                f
+            )))
+        }
         Ok(f) => {
             match std::fs::metadata(&file_path) {
                 Ok(metadata) => {
@@ -89,7 +176,10 @@ fn handle_multipart(
                 }
             }
             file_path = file_path.join(f);
-            Box::pin(save_file(field, file_path, overwrite_files).into_stream())
+            Box::pin(
+                save_file(field, file_path, overwrite_files, buffer_size)
+                    .map(move |result| UploadResult { name, result }),
+            )
         }
         Err(e) => err(e(
             "HTTP header".to_string(),
@@ -110,11 +200,13 @@ pub fn upload_file(
     uses_random_route: bool,
     favicon_route: String,
     css_route: String,
-    default_color_scheme: &str,
-    default_color_scheme_dark: &str,
     hide_version_footer: bool,
 ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, actix_web::Error>>>> {
     let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let settings = conf.reloadable.get();
+    let default_color_scheme = settings.default_color_scheme.as_str();
+    let default_color_scheme_dark = settings.default_color_scheme_dark.as_str();
+    let lang = Language::negotiate(req.headers(), conf.lang);
     let return_path = if let Some(header) = req.headers().get(header::REFERER) {
         header.to_str().unwrap_or("/").to_owned()
     } else {
@@ -123,10 +215,7 @@ pub fn upload_file(
 
     let query_params = listing::extract_query_parameters(&req);
     let upload_path = match query_params.path.clone() {
-        Some(path) => match path.strip_prefix(Component::RootDir) {
-            Ok(stripped_path) => stripped_path.to_owned(),
-            Err(_) => path.clone(),
-        },
+        Some(path) => path,
         None => {
             let err = ContextualError::InvalidHttpRequestError(
                 "Missing query parameter 'path'".to_string(),
@@ -143,43 +232,23 @@ pub fn upload_file(
                 default_color_scheme,
                 default_color_scheme_dark,
                 hide_version_footer,
-            ));
-        }
-    };
-
-    let app_root_dir = match conf.path.canonicalize() {
-        Ok(dir) => dir,
-        Err(e) => {
-            let err = ContextualError::IoError(
-                "Failed to resolve path served by miniserve".to_string(),
-                e,
-            );
-            return Box::pin(create_error_response(
-                &err.to_string(),
-                StatusCode::INTERNAL_SERVER_ERROR,
-                &return_path,
-                query_params.sort,
-                query_params.order,
-                uses_random_route,
-                &favicon_route,
-                &css_route,
-                default_color_scheme,
-                default_color_scheme_dark,
-                hide_version_footer,
+                conf.templates.as_ref(),
+                lang,
             ));
         }
     };
 
     // If the target path is under the app root directory, save the file.
-    let target_dir = match &app_root_dir.join(upload_path).canonicalize() {
-        Ok(path) if path.starts_with(&app_root_dir) => path.clone(),
-        _ => {
-            let err = ContextualError::InvalidHttpRequestError(
-                "Invalid value for 'path' parameter".to_string(),
-            );
+    let target_dir = match path_policy::resolve_request_path(&conf.path, &upload_path) {
+        Ok(path) => path,
+        Err(err) => {
+            let status = match err {
+                ContextualError::IoError(..) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
             return Box::pin(create_error_response(
                 &err.to_string(),
-                StatusCode::BAD_REQUEST,
+                status,
                 &return_path,
                 query_params.sort,
                 query_params.order,
@@ -189,28 +258,63 @@ pub fn upload_file(
                 default_color_scheme,
                 default_color_scheme_dark,
                 hide_version_footer,
+                conf.templates.as_ref(),
+                lang,
             ));
         }
     };
     let overwrite_files = conf.overwrite_files;
-    let default_color_scheme = conf.default_color_scheme.clone();
-    let default_color_scheme_dark = conf.default_color_scheme_dark.clone();
+    let buffer_size = conf.client_body_buffer_size;
+    let default_color_scheme = settings.default_color_scheme.clone();
+    let default_color_scheme_dark = settings.default_color_scheme_dark.clone();
+    let templates = conf.templates.clone();
+    let wants_json = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map_or(false, |accept| accept.contains("application/json"));
 
     Box::pin(
         actix_multipart::Multipart::new(req.headers(), payload)
             .map_err(ContextualError::MultipartError)
-            .map_ok(move |item| handle_multipart(item, target_dir.clone(), overwrite_files))
-            .try_flatten()
+            .and_then(move |field| {
+                handle_multipart(field, target_dir.clone(), overwrite_files, buffer_size).map(Ok)
+            })
             .try_collect::<Vec<_>>()
-            .then(move |e| match e {
-                Ok(_) => future::ok(
-                    HttpResponse::SeeOther()
-                        .header(header::LOCATION, return_path)
-                        .finish(),
-                ),
+            .then(move |results| match results {
+                Ok(results) => {
+                    // The first failure decides the status, later ones are only reported
+                    let status = results
+                        .iter()
+                        .find_map(|file| file.result.as_ref().err())
+                        .map(error_status);
+                    match status {
+                        _ if wants_json => future::ok(json_report(&results, status)),
+                        None => future::ok(
+                            HttpResponse::SeeOther()
+                                .header(header::LOCATION, return_path)
+                                .finish(),
+                        ),
+                        Some(status) => create_error_response(
+                            &summary(&results),
+                            status,
+                            &return_path,
+                            query_params.sort,
+                            query_params.order,
+                            uses_random_route,
+                            &favicon_route,
+                            &css_route,
+                            &default_color_scheme,
+                            &default_color_scheme_dark,
+                            hide_version_footer,
+                            templates.as_ref(),
+                            lang,
+                        ),
+                    }
+                }
                 Err(e) => create_error_response(
                     &e.to_string(),
-                    StatusCode::INTERNAL_SERVER_ERROR,
+                    error_status(&e),
                     &return_path,
                     query_params.sort,
                     query_params.order,
@@ -220,11 +324,138 @@ pub fn upload_file(
                     &default_color_scheme,
                     &default_color_scheme_dark,
                     hide_version_footer,
+                    templates.as_ref(),
+                    lang,
                 ),
             }),
     )
 }
 
+/// Creates an empty file, like uploading an empty file but without choosing one first.
+///
+/// The directory is given by the `path` query parameter like for uploads, the form data
+/// contains the `name` of the file, which must not exist yet.
+pub async fn create_file(
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let lang = Language::negotiate(req.headers(), conf.lang);
+    let return_path = if let Some(header) = req.headers().get(header::REFERER) {
+        header.to_str().unwrap_or("/").to_owned()
+    } else {
+        "/".to_string()
+    };
+    let query_params = listing::extract_query_parameters(&req);
+    let error_response = |err: ContextualError, status: StatusCode| {
+        create_error_response(
+            &err.to_string(),
+            status,
+            &return_path,
+            query_params.sort,
+            query_params.order,
+            conf.random_route.is_some(),
+            &conf.favicon_route,
+            &conf.css_route,
+            &conf.reloadable.get().default_color_scheme,
+            &conf.reloadable.get().default_color_scheme_dark,
+            conf.hide_version_footer,
+            conf.templates.as_ref(),
+            lang,
+        )
+    };
+
+    let name = form_urlencoded::parse(&body)
+        .find(|(key, _)| key == "name")
+        .map(|(_, name)| name.into_owned())
+        .unwrap_or_default();
+    // The file must be listed once created
+    let is_valid_name = path_policy::is_entry_name(Path::new(&name))
+        && (conf.show_hidden || !name.starts_with('.'))
+        && !listing::Annotations::is_reserved_name(OsStr::new(&name));
+    if !is_valid_name {
+        return error_response(
+            ContextualError::InvalidPathError(format!("'{}' is not a valid file name", name)),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    // The directory has to be inside the served directory, like the target of uploads
+    let dir_path = query_params.path.clone().unwrap_or_default();
+    let file_path = match path_policy::resolve_request_path(&conf.path, &dir_path) {
+        Ok(dir) if dir.is_dir() => dir.join(&name),
+        _ => {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(
+                    "Invalid value for 'path' parameter".to_string(),
+                ),
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+    };
+
+    log::info!("Creating empty file {}", file_path.display());
+    match web::block(move || open_locked(&file_path, false).map(|_| ())).await {
+        Ok(()) => Ok(HttpResponse::SeeOther()
+            .header(header::LOCATION, return_path.as_str())
+            .finish()),
+        Err(err) => {
+            let err = ContextualError::from(err);
+            let status = error_status(&err);
+            error_response(err, status).await
+        }
+    }
+}
+
+/// Status of a response to an upload which failed with `error`
+fn error_status(error: &ContextualError) -> StatusCode {
+    match error {
+        ContextualError::UploadConflictError(_) | ContextualError::DuplicateFileError => {
+            StatusCode::CONFLICT
+        }
+        ContextualError::InvalidPathError(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Describes the outcome of each file, one per line, for the error page
+fn summary(results: &[UploadResult]) -> String {
+    let failed = results.iter().filter(|file| file.result.is_err()).count();
+    let mut summary = format!(
+        "{} of {} files could not be uploaded",
+        failed,
+        results.len()
+    );
+    for file in results {
+        let outcome = match &file.result {
+            Ok(_) => "uploaded".to_string(),
+            Err(e) => e.to_string().replace('\n', ", "),
+        };
+        summary.push_str(&format!("\n{}: {}", file.name, outcome));
+    }
+    summary
+}
+
+/// Responds with the outcome of each file as JSON
+fn json_report(results: &[UploadResult], status: Option<StatusCode>) -> HttpResponse {
+    let files = results
+        .iter()
+        .map(|file| FileReport {
+            name: &file.name,
+            uploaded: file.result.is_ok(),
+            size: file.result.as_ref().ok().copied(),
+            error: file.result.as_ref().err().map(ToString::to_string),
+        })
+        .collect::<Vec<_>>();
+    for error in files.iter().filter_map(|file| file.error.as_ref()) {
+        errors::log_error_chain(error.to_string());
+    }
+
+    HttpResponse::build(status.unwrap_or(StatusCode::OK)).json(UploadReport { files })
+}
+
 /// Convenience method for creating response errors, if file upload fails.
 #[allow(clippy::too_many_arguments)]
 fn create_error_response(
@@ -239,10 +470,12 @@ fn create_error_response(
     default_color_scheme: &str,
     default_color_scheme_dark: &str,
     hide_version_footer: bool,
+    templates: Option<&Templates>,
+    lang: Language,
 ) -> future::Ready<Result<HttpResponse, actix_web::Error>> {
     errors::log_error_chain(description.to_string());
     future::ok(
-        HttpResponse::BadRequest()
+        HttpResponse::build(error_code)
             .content_type("text/html; charset=utf-8")
             .body(
                 renderer::render_error(
@@ -258,6 +491,8 @@ fn create_error_response(
                     default_color_scheme,
                     default_color_scheme_dark,
                     hide_version_footer,
+                    templates,
+                    lang,
                 )
                 .into_string(),
             ),
@@ -0,0 +1,76 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, ContentDisposition, DispositionType, IntoHeaderValue};
+use actix_web::http::{HeaderValue, StatusCode};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::errors::ContextualError;
+use crate::path_policy;
+
+/// Parses a glob of `--force-download`, like `*.html`
+pub fn parse_glob(src: &str) -> Result<Gitignore, ContextualError> {
+    let parse_error = |cause: String| ContextualError::ParseError("glob".to_string(), cause);
+
+    let mut builder = GitignoreBuilder::new("");
+    builder
+        .add_line(None, src.trim())
+        .map_err(|e| parse_error(e.to_string()))?;
+    builder.build().map_err(|e| parse_error(e.to_string()))
+}
+
+/// Returns whether the file requested by `req` has to be downloaded rather than shown, i.e.
+/// `--force-download` is given without globs or with one matching the file
+pub fn is_forced(req: &ServiceRequest) -> bool {
+    let globs = match req
+        .app_data::<crate::MiniserveConfig>()
+        .and_then(|conf| conf.force_download.as_ref())
+    {
+        Some(globs) => globs,
+        None => return false,
+    };
+
+    match path_policy::requested_file(req) {
+        Some(relative_path) => {
+            globs.is_empty()
+                || globs.iter().any(|glob| {
+                    glob.matched_path_or_any_parents(&relative_path, false)
+                        .is_ignore()
+                })
+        }
+        None => false,
+    }
+}
+
+/// Marks the file served by `res` as an attachment, so that browsers save it instead of
+/// rendering it, and keeps them from guessing another type.
+///
+/// This keeps HTML or SVG files uploaded by others from running scripts on the origin of
+/// miniserve.
+pub fn apply<B>(mut res: ServiceResponse<B>, forced: bool) -> ServiceResponse<B> {
+    if !forced || !matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT) {
+        return res;
+    }
+
+    // Only the disposition type changes, the file name stays as suggested by the files service
+    let disposition = res
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|cd| ContentDisposition::from_raw(cd).ok())
+        .unwrap_or(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![],
+        });
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        ..disposition
+    };
+
+    let headers = res.headers_mut();
+    if let Ok(disposition) = IntoHeaderValue::try_into(disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    res
+}
@@ -0,0 +1,150 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use maxminddb::geoip2;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::proxy;
+use crate::renderer;
+
+/// Country-based access policy backed by a MaxMind GeoIP database
+#[derive(Clone)]
+pub struct GeoIpFilter {
+    reader: Arc<maxminddb::Reader<Vec<u8>>>,
+    allowed_countries: Vec<String>,
+    denied_countries: Vec<String>,
+}
+
+impl GeoIpFilter {
+    /// Opens the database at `db`, checking clients against the given country codes
+    pub fn open(
+        db: &Path,
+        allowed_countries: Vec<String>,
+        denied_countries: Vec<String>,
+    ) -> Result<Self, ContextualError> {
+        let reader = maxminddb::Reader::open_readfile(db).map_err(|e| {
+            ContextualError::GeoIpDatabaseError(db.display().to_string(), e.to_string())
+        })?;
+
+        Ok(GeoIpFilter {
+            reader: Arc::new(reader),
+            allowed_countries,
+            denied_countries,
+        })
+    }
+
+    /// Returns the ISO code of the country `ip` is located in, if it is known
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        self.reader
+            .lookup::<geoip2::Country>(ip)
+            .ok()?
+            .country?
+            .iso_code
+            .map(str::to_uppercase)
+    }
+
+    /// Returns `true` if clients from `country` may access miniserve
+    pub fn is_allowed(&self, country: Option<&str>) -> bool {
+        is_allowed_country(country, &self.allowed_countries, &self.denied_countries)
+    }
+}
+
+/// Returns `true` if `country` is in `allowed_countries`, or that list is empty, and not in
+/// `denied_countries`
+///
+/// If an allow list is set, clients whose country is unknown are denied.
+pub fn is_allowed_country(
+    country: Option<&str>,
+    allowed_countries: &[String],
+    denied_countries: &[String],
+) -> bool {
+    match country {
+        Some(country) => {
+            (allowed_countries.is_empty() || allowed_countries.iter().any(|c| c == country))
+                && !denied_countries.iter().any(|c| c == country)
+        }
+        None => allowed_countries.is_empty(),
+    }
+}
+
+/// Tags the request with the client's country and enforces the country policy
+///
+/// Returns the response to send instead if the request has to be rejected.
+pub fn check_request(req: &ServiceRequest) -> Option<HttpResponse> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let filter = conf.geoip.as_ref()?;
+    let ip = proxy::client_ip(req)?;
+    let country = filter.country(ip);
+
+    log::debug!(
+        "{} [{}] {}",
+        ip,
+        country.as_deref().unwrap_or("--"),
+        req.path()
+    );
+
+    if filter.is_allowed(country.as_deref()) {
+        return None;
+    }
+
+    let error = ContextualError::CountryNotAllowedError(country.unwrap_or_else(|| ip.to_string()));
+    errors::log_error_chain(error.to_string());
+
+    Some(
+        HttpResponse::Forbidden()
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    StatusCode::FORBIDDEN,
+                    "/",
+                    None,
+                    None,
+                    false,
+                    false,
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            ),
+    )
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    fn countries(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|code| code.to_string()).collect()
+    }
+
+    #[rstest(country, allowed, denied, expected,
+        case(Some("DE"), &[], &[], true),
+        case(None, &[], &[], true),
+        case(Some("DE"), &["DE", "AT"], &[], true),
+        case(Some("FR"), &["DE", "AT"], &[], false),
+        case(None, &["DE"], &[], false),
+        case(Some("DE"), &[], &["RU"], true),
+        case(Some("RU"), &[], &["RU"], false),
+        case(None, &[], &["RU"], true),
+        case(Some("DE"), &["DE"], &["DE"], false),
+    )]
+    fn allowed_country(country: Option<&str>, allowed: &[&str], denied: &[&str], expected: bool) {
+        assert_eq!(
+            is_allowed_country(country, &countries(allowed), &countries(denied)),
+            expected
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use actix_web::http::header::{HeaderMap, ACCEPT_LANGUAGE};
+use strum_macros::{Display, EnumString};
+
+/// Languages the interface is available in
+#[derive(Clone, Copy, Debug, PartialEq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Language {
+    /// English
+    En,
+
+    /// German
+    De,
+
+    /// French
+    Fr,
+}
+
+impl Language {
+    /// Picks the language of a response.
+    ///
+    /// `forced` wins if set, otherwise the supported language the client prefers according to
+    /// its `Accept-Language` header is used, falling back to English.
+    pub fn negotiate(headers: &HeaderMap, forced: Option<Language>) -> Self {
+        forced
+            .or_else(|| {
+                headers
+                    .get(ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(preferred_language)
+            })
+            .unwrap_or(Language::En)
+    }
+
+    /// Returns the translated strings of the interface
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::En => &EN,
+            Language::De => &DE,
+            Language::Fr => &FR,
+        }
+    }
+}
+
+/// Returns the supported language with the highest quality in an `Accept-Language` header
+fn preferred_language(accept_language: &str) -> Option<Language> {
+    let mut best: Option<(Language, f32)> = None;
+
+    for item in accept_language.split(',') {
+        let mut parts = item.split(';').map(str::trim);
+        let tag = parts.next().unwrap_or_default().to_lowercase();
+        let quality = match parts.find_map(|param| param.strip_prefix("q=")) {
+            Some(quality) => quality.parse::<f32>().unwrap_or(0.0),
+            None => 1.0,
+        };
+        let language = match tag.split('-').next().unwrap_or_default().parse() {
+            Ok(language) => language,
+            Err(_) => continue,
+        };
+
+        if quality > 0.0 && best.map_or(true, |(_, best_quality)| quality > best_quality) {
+            best = Some((language, quality));
+        }
+    }
+
+    best.map(|(language, _)| language)
+}
+
+/// Strings of the interface in one language
+///
+/// Strings containing `{}` are patterns whose placeholders are filled in with `fill`.
+pub struct Strings {
+    pub drop_to_upload: &'static str,
+    pub select_to_upload: &'static str,
+    pub upload_file: &'static str,
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub size: &'static str,
+    pub last_modification: &'static str,
+    pub sort_ascending: &'static str,
+    pub sort_descending: &'static str,
+    pub parent_directory: &'static str,
+    pub expand_directory: &'static str,
+    pub copy_link: &'static str,
+    pub filter_entries: &'static str,
+    pub gallery_view: &'static str,
+    pub table_view: &'static str,
+    pub at: &'static str,
+    pub previous_page: &'static str,
+    pub next_page: &'static str,
+    pub page_of: &'static str,
+    pub load_more: &'static str,
+    pub file: &'static str,
+    pub files: &'static str,
+    pub directory: &'static str,
+    pub directories: &'static str,
+    pub total: &'static str,
+    pub download_archive: &'static str,
+    pub download_selected: &'static str,
+    pub archive_format: &'static str,
+    pub select_entry: &'static str,
+    pub qr_code: &'static str,
+    pub qr_code_title: &'static str,
+    pub file_qr_code_title: &'static str,
+    pub file_info: &'static str,
+    pub mime_type: &'static str,
+    pub permissions: &'static str,
+    pub owner: &'static str,
+    pub checksum: &'static str,
+    pub compute_checksum: &'static str,
+    pub open_raw: &'static str,
+    pub download: &'static str,
+    pub preview: &'static str,
+    pub rename: &'static str,
+    pub extract: &'static str,
+    pub file_name: &'static str,
+    pub create_file: &'static str,
+    pub destination: &'static str,
+    pub move_selected: &'static str,
+    pub copy_selected: &'static str,
+    pub share_stats: &'static str,
+    pub file_count: &'static str,
+    pub total_size: &'static str,
+    pub last_update: &'static str,
+    pub duplicates: &'static str,
+    pub duplicate_groups: &'static str,
+    pub duplicate_group: &'static str,
+    pub reclaimable_size: &'static str,
+    pub mounts: &'static str,
+    pub change_theme: &'static str,
+    pub switch_theme: &'static str,
+    pub go_back: &'static str,
+    pub did_you_mean: &'static str,
+}
+
+/// Replaces the placeholders of `pattern` with `values`, in order
+pub fn fill(pattern: &str, values: &[&str]) -> String {
+    values.iter().fold(pattern.to_string(), |filled, value| {
+        filled.replacen("{}", value, 1)
+    })
+}
+
+static EN: Strings = Strings {
+    drop_to_upload: "Drop your file here to upload it",
+    select_to_upload: "Select a file to upload or drag it anywhere into the window",
+    upload_file: "Upload file",
+    name: "Name",
+    extension: "Extension",
+    size: "Size",
+    last_modification: "Last modification",
+    sort_ascending: "Sort by {} in ascending order",
+    sort_descending: "Sort by {} in descending order",
+    parent_directory: "Parent directory",
+    expand_directory: "Expand directory",
+    copy_link: "Copy link",
+    filter_entries: "Filter entries",
+    gallery_view: "Gallery",
+    table_view: "Table",
+    at: "at",
+    previous_page: "Previous page",
+    next_page: "Next page",
+    page_of: "Page {} of {}",
+    load_more: "Load more",
+    file: "file",
+    files: "files",
+    directory: "directory",
+    directories: "directories",
+    total: "{} total",
+    download_archive: "Download .{}",
+    download_selected: "Download selected",
+    archive_format: "Archive format",
+    select_entry: "Select for download",
+    qr_code: "QR code",
+    qr_code_title: "QR code of this page",
+    file_qr_code_title: "QR code of this file",
+    file_info: "File information",
+    mime_type: "Type",
+    permissions: "Permissions",
+    owner: "Owner",
+    checksum: "SHA-256 checksum",
+    compute_checksum: "Compute",
+    open_raw: "Open",
+    download: "Download",
+    preview: "Preview",
+    rename: "Rename",
+    extract: "Extract here",
+    file_name: "File name",
+    create_file: "Create file",
+    destination: "Destination directory",
+    move_selected: "Move selected",
+    copy_selected: "Copy selected",
+    share_stats: "Statistics",
+    file_count: "Files",
+    total_size: "Total size",
+    last_update: "Last update",
+    duplicates: "Duplicate files",
+    duplicate_groups: "Groups of duplicates",
+    duplicate_group: "{} files of {} each",
+    reclaimable_size: "Reclaimable space",
+    mounts: "Served directories",
+    change_theme: "Change theme...",
+    switch_theme: "Switch to {} theme",
+    go_back: "Go back to file listing",
+    did_you_mean: "Did you mean {}?",
+};
+
+static DE: Strings = Strings {
+    drop_to_upload: "Datei hier ablegen, um sie hochzuladen",
+    select_to_upload: "Datei zum Hochladen auswählen oder irgendwo ins Fenster ziehen",
+    upload_file: "Datei hochladen",
+    name: "Name",
+    extension: "Dateiendung",
+    size: "Größe",
+    last_modification: "Letzte Änderung",
+    sort_ascending: "Aufsteigend nach {} sortieren",
+    sort_descending: "Absteigend nach {} sortieren",
+    parent_directory: "Übergeordnetes Verzeichnis",
+    expand_directory: "Verzeichnis aufklappen",
+    copy_link: "Link kopieren",
+    filter_entries: "Einträge filtern",
+    gallery_view: "Galerie",
+    table_view: "Tabelle",
+    at: "um",
+    previous_page: "Vorherige Seite",
+    next_page: "Nächste Seite",
+    page_of: "Seite {} von {}",
+    load_more: "Mehr laden",
+    file: "Datei",
+    files: "Dateien",
+    directory: "Verzeichnis",
+    directories: "Verzeichnisse",
+    total: "{} insgesamt",
+    download_archive: ".{} herunterladen",
+    download_selected: "Auswahl herunterladen",
+    archive_format: "Archivformat",
+    select_entry: "Zum Herunterladen auswählen",
+    qr_code: "QR-Code",
+    qr_code_title: "QR-Code dieser Seite",
+    file_qr_code_title: "QR-Code dieser Datei",
+    file_info: "Dateiinformationen",
+    mime_type: "Typ",
+    permissions: "Berechtigungen",
+    owner: "Besitzer",
+    checksum: "SHA-256-Prüfsumme",
+    compute_checksum: "Berechnen",
+    open_raw: "Öffnen",
+    download: "Herunterladen",
+    preview: "Vorschau",
+    rename: "Umbenennen",
+    extract: "Hier entpacken",
+    file_name: "Dateiname",
+    create_file: "Datei erstellen",
+    destination: "Zielverzeichnis",
+    move_selected: "Auswahl verschieben",
+    copy_selected: "Auswahl kopieren",
+    share_stats: "Statistik",
+    file_count: "Dateien",
+    total_size: "Gesamtgröße",
+    last_update: "Letzte Aktualisierung",
+    duplicates: "Doppelte Dateien",
+    duplicate_groups: "Gruppen von Duplikaten",
+    duplicate_group: "{} Dateien mit je {}",
+    reclaimable_size: "Freigebbarer Speicher",
+    mounts: "Bereitgestellte Verzeichnisse",
+    change_theme: "Design ändern...",
+    switch_theme: "Zum Design {} wechseln",
+    go_back: "Zurück zur Dateiliste",
+    did_you_mean: "Meinten Sie {}?",
+};
+
+static FR: Strings = Strings {
+    drop_to_upload: "Déposez votre fichier ici pour l'envoyer",
+    select_to_upload:
+        "Sélectionnez un fichier à envoyer ou faites-le glisser n'importe où dans la fenêtre",
+    upload_file: "Envoyer le fichier",
+    name: "Nom",
+    extension: "Extension",
+    size: "Taille",
+    last_modification: "Dernière modification",
+    sort_ascending: "Trier par {} dans l'ordre croissant",
+    sort_descending: "Trier par {} dans l'ordre décroissant",
+    parent_directory: "Dossier parent",
+    expand_directory: "Déplier le dossier",
+    copy_link: "Copier le lien",
+    filter_entries: "Filtrer les entrées",
+    gallery_view: "Galerie",
+    table_view: "Tableau",
+    at: "à",
+    previous_page: "Page précédente",
+    next_page: "Page suivante",
+    page_of: "Page {} sur {}",
+    load_more: "Afficher plus",
+    file: "fichier",
+    files: "fichiers",
+    directory: "dossier",
+    directories: "dossiers",
+    total: "{} au total",
+    download_archive: "Télécharger .{}",
+    download_selected: "Télécharger la sélection",
+    archive_format: "Format d'archive",
+    select_entry: "Sélectionner pour le téléchargement",
+    qr_code: "Code QR",
+    qr_code_title: "Code QR de cette page",
+    file_qr_code_title: "Code QR de ce fichier",
+    file_info: "Informations sur le fichier",
+    mime_type: "Type",
+    permissions: "Permissions",
+    owner: "Propriétaire",
+    checksum: "Somme de contrôle SHA-256",
+    compute_checksum: "Calculer",
+    open_raw: "Ouvrir",
+    download: "Télécharger",
+    preview: "Aperçu",
+    rename: "Renommer",
+    extract: "Extraire ici",
+    file_name: "Nom du fichier",
+    create_file: "Créer un fichier",
+    destination: "Dossier de destination",
+    move_selected: "Déplacer la sélection",
+    copy_selected: "Copier la sélection",
+    share_stats: "Statistiques",
+    file_count: "Fichiers",
+    total_size: "Taille totale",
+    last_update: "Dernière mise à jour",
+    duplicates: "Fichiers en double",
+    duplicate_groups: "Groupes de doublons",
+    duplicate_group: "{} fichiers de {} chacun",
+    reclaimable_size: "Espace récupérable",
+    mounts: "Dossiers partagés",
+    change_theme: "Changer de thème...",
+    switch_theme: "Passer au thème {}",
+    go_back: "Retour à la liste des fichiers",
+    did_you_mean: "Vouliez-vous dire {} ?",
+};
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::HeaderValue;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    #[rstest(accept_language, forced, expected,
+        case(None, None, Language::En),
+        case(Some("de-DE,de;q=0.9,en;q=0.8"), None, Language::De),
+        case(Some("en;q=0.5, fr-CH"), None, Language::Fr),
+        case(Some("ja, de;q=0.3"), None, Language::De),
+        case(Some("ja, de;q=0"), None, Language::En),
+        case(Some("de"), Some(Language::Fr), Language::Fr),
+    )]
+    fn negotiate_language(accept_language: Option<&str>, forced: Option<Language>, expected: Language) {
+        let mut headers = HeaderMap::new();
+        if let Some(accept_language) = accept_language {
+            headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_str(accept_language).unwrap());
+        }
+
+        assert_eq!(Language::negotiate(&headers, forced), expected);
+    }
+
+    #[test]
+    fn fill_placeholders() {
+        assert_eq!(fill(EN.page_of, &["2", "5"]), "Page 2 of 5");
+    }
+}
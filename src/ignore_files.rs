@@ -0,0 +1,192 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use percent_encoding::percent_decode_str;
+use std::path::Path;
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::no_archive::NoArchive;
+use crate::renderer;
+
+/// Names of the files whose patterns exclude entries, read from every served directory
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".miniserveignore"];
+
+/// Patterns of the ignore files applying to a directory
+#[derive(Clone, Default)]
+pub struct IgnoreFiles {
+    /// One matcher per directory containing ignore files, outermost first
+    matchers: Vec<Gitignore>,
+
+    /// Patterns which exclude entries regardless of the ignore files, like `--archive-exclude`
+    excludes: Option<Gitignore>,
+
+    /// Whether ignore files are read at all, otherwise only `excludes` apply
+    read_files: bool,
+
+    /// Directories which can't be archived, excluded from archives of their parents
+    no_archive: Option<NoArchive>,
+}
+
+impl IgnoreFiles {
+    /// Reads the ignore files of `dir` and of all its parents up to the served `root`
+    pub fn for_dir(root: &Path, dir: &Path) -> Self {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let mut dirs = dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&root))
+            .collect::<Vec<_>>();
+        dirs.reverse();
+
+        let ignore_files = IgnoreFiles {
+            read_files: true,
+            ..IgnoreFiles::default()
+        };
+        dirs.into_iter()
+            .fold(ignore_files, |ignore_files, dir| ignore_files.with_dir(dir))
+    }
+
+    /// Returns these patterns extended by the ignore files of `dir`, a subdirectory of the
+    /// directories read so far
+    pub fn with_dir(&self, dir: &Path) -> Self {
+        if !self.read_files {
+            return self.clone();
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for name in &IGNORE_FILE_NAMES {
+            let path = dir.join(name);
+            if path.is_file() {
+                if let Some(e) = builder.add(&path) {
+                    log::warn!("Invalid pattern in {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        let mut matchers = self.matchers.clone();
+        match builder.build() {
+            Ok(matcher) if !matcher.is_empty() => matchers.push(matcher),
+            Ok(_) => (),
+            Err(e) => log::warn!("Failed to read ignore files in {}: {}", dir.display(), e),
+        }
+        IgnoreFiles {
+            matchers,
+            excludes: self.excludes.clone(),
+            read_files: true,
+            no_archive: self.no_archive.clone(),
+        }
+    }
+
+    /// Returns these patterns extended by `patterns` in gitignore syntax, relative to `dir`.
+    ///
+    /// Unlike patterns of ignore files, these can't be negated by deeper ignore files.
+    pub fn with_excludes(&self, dir: &Path, patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(dir);
+        for pattern in patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                log::warn!("Invalid exclude pattern {}: {}", pattern, e);
+            }
+        }
+
+        let excludes = match builder.build() {
+            Ok(excludes) => Some(excludes),
+            Err(e) => {
+                log::warn!("Failed to build exclude patterns: {}", e);
+                None
+            }
+        };
+        IgnoreFiles {
+            matchers: self.matchers.clone(),
+            excludes,
+            read_files: self.read_files,
+            no_archive: self.no_archive.clone(),
+        }
+    }
+
+    /// Returns these patterns also excluding the directories which can't be archived
+    pub fn with_no_archive(&self, no_archive: &NoArchive) -> Self {
+        IgnoreFiles {
+            no_archive: Some(no_archive.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns whether `path` or one of its parents is excluded.
+    ///
+    /// Like with git, ignore files in deeper directories take precedence.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(no_archive) = &self.no_archive {
+            if is_dir && no_archive.is_marked(path) {
+                return true;
+            }
+        }
+        if let Some(excludes) = &self.excludes {
+            if excludes
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+            {
+                return true;
+            }
+        }
+        for matcher in self.matchers.iter().rev() {
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => (),
+            }
+        }
+        false
+    }
+}
+
+/// Rejects requests for files and directories excluded by ignore files
+///
+/// Returns the response to send instead if the request has to be rejected.
+pub fn check_request(req: &ServiceRequest) -> Option<HttpResponse> {
+    let conf = req
+        .app_data::<crate::MiniserveConfig>()
+        .unwrap()
+        .for_path(req.path());
+    if !conf.ignore_files {
+        return None;
+    }
+
+    let route = format!("/{}", conf.random_route.clone().unwrap_or_default());
+    let relative_path = percent_decode_str(req.path().strip_prefix(&route)?)
+        .decode_utf8()
+        .ok()?;
+    let root = conf.path.canonicalize().ok()?;
+    let path = root.join(relative_path.trim_start_matches('/'));
+    if !IgnoreFiles::for_dir(&root, path.parent()?).is_ignored(&path, path.is_dir()) {
+        return None;
+    }
+
+    let error = ContextualError::RouteNotFoundError(req.path().to_string());
+    errors::log_error_chain(error.to_string());
+
+    Some(
+        HttpResponse::NotFound()
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    StatusCode::NOT_FOUND,
+                    "/",
+                    None,
+                    None,
+                    false,
+                    conf.random_route.is_none(),
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            ),
+    )
+}
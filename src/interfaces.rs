@@ -0,0 +1,110 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::errors::ContextualError;
+
+/// A `--interfaces` value, either an address or the name of a network interface like `eth0`
+#[derive(Clone, Debug)]
+pub enum InterfaceArg {
+    Address(IpAddr),
+    Name(String),
+}
+
+impl InterfaceArg {
+    /// Parses an address, or takes anything else as the name of an interface, which is only
+    /// resolved at startup
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        if let Ok(address) = src.parse::<IpAddr>() {
+            return Ok(InterfaceArg::Address(address));
+        }
+        if src.is_empty() || src.chars().any(|c| c.is_whitespace() || c == '/') {
+            return Err(ContextualError::ParseError(
+                "interface".to_string(),
+                format!("{} is neither an address nor the name of an interface", src),
+            ));
+        }
+        Ok(InterfaceArg::Name(src.to_string()))
+    }
+}
+
+/// Returns whether `ip` is an IPv6 link-local address, which can't be bound without the
+/// scope of its interface
+fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(ipv6) => ipv6.segments()[0] & 0xffc0 == 0xfe80,
+        IpAddr::V4(_) => false,
+    }
+}
+
+/// Returns the address this host reaches miniserve at when it is bound to `bound`, preferring
+/// loopback and then IPv4 addresses, which browsers handle best
+pub fn local(bound: &[IpAddr]) -> Option<IpAddr> {
+    bound
+        .iter()
+        .map(|address| match address {
+            IpAddr::V4(address) if address.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(address) if address.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            address => *address,
+        })
+        .min_by_key(|address| (!address.is_loopback(), address.is_ipv6()))
+}
+
+/// Returns the addresses other devices may reach miniserve at when it is bound to `bound`:
+/// unspecified ones like `0.0.0.0` stand for the addresses of all the network interfaces.
+///
+/// Loopback and IPv6 link-local addresses are left out, as they only work locally.
+pub fn reachable(bound: &[IpAddr]) -> Vec<IpAddr> {
+    let local = if bound.iter().any(IpAddr::is_unspecified) {
+        if_addrs::get_if_addrs()
+            .map(|interfaces| interfaces.iter().map(|interface| interface.ip()).collect())
+            .unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    let mut reachable = vec![];
+    for address in bound {
+        let addresses = if address.is_unspecified() {
+            local
+                .iter()
+                .filter(|local| local.is_ipv4() == address.is_ipv4())
+                .copied()
+                .collect()
+        } else {
+            vec![*address]
+        };
+        for address in addresses {
+            if !address.is_loopback() && !is_link_local(&address) && !reachable.contains(&address) {
+                reachable.push(address);
+            }
+        }
+    }
+    reachable
+}
+
+/// Returns the current addresses of the network interfaces `names`, along with their name.
+///
+/// Fails if one of them doesn't exist or has no address, e.g. because it is down.
+pub fn resolve(names: &[String]) -> Result<Vec<(String, Vec<IpAddr>)>, ContextualError> {
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let interfaces = if_addrs::get_if_addrs().map_err(|e| {
+        ContextualError::IoError("Failed to list the network interfaces".to_string(), e)
+    })?;
+    names
+        .iter()
+        .map(|name| {
+            let addresses = interfaces
+                .iter()
+                .filter(|interface| interface.name == *name)
+                .map(|interface| interface.ip())
+                .filter(|ip| !is_link_local(ip))
+                .collect::<Vec<_>>();
+            if addresses.is_empty() {
+                return Err(ContextualError::InterfaceNotFoundError(name.clone()));
+            }
+            Ok((name.clone(), addresses))
+        })
+        .collect()
+}
@@ -1,25 +1,38 @@
 use actix_web::body::Body;
-use actix_web::dev::ServiceResponse;
-use actix_web::http::StatusCode;
-use actix_web::web::Query;
+use actix_web::dev::{HttpResponseBuilder, ServiceResponse};
+use actix_web::http::header::Header;
+use actix_web::http::{Cookie, StatusCode};
+use actix_web::web::{Bytes, Query};
 use actix_web::{HttpRequest, HttpResponse, Result};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
 use bytesize::ByteSize;
+use chrono::DateTime;
 use percent_encoding::{percent_decode_str, utf8_percent_encode};
 use qrcodegen::{QrCode, QrCodeEcc};
 use serde::Deserialize;
-use std::io;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
 use std::path::{Component, Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use strum_macros::{Display, EnumString};
 
-use crate::archive::ArchiveMethod;
+use crate::archive::{self, ArchiveMethod, CompressionLevel};
+use crate::archive_pool::RateLimited;
+use crate::conditional;
 use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::ignore_files::IgnoreFiles;
+use crate::listing_cache;
+use crate::path_policy;
+use crate::permissions::{OwnerNames, Permissions};
 use crate::renderer;
+use crate::slow_ops;
 use percent_encode_sets::PATH_SEGMENT;
 
 /// "percent-encode sets" as defined by WHATWG specs:
 /// https://url.spec.whatwg.org/#percent-encoded-bytes
-mod percent_encode_sets {
+pub mod percent_encode_sets {
     use percent_encoding::{AsciiSet, CONTROLS};
     const BASE: &AsciiSet = &CONTROLS.add(b'%');
     pub const QUERY: &AsciiSet = &BASE.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
@@ -27,14 +40,47 @@ mod percent_encode_sets {
     pub const PATH_SEGMENT: &AsciiSet = &PATH.add(b'/');
 }
 
+/// Name of the cookie remembering the last chosen sorting method
+const SORT_COOKIE: &str = "miniserve-sort";
+
+/// Name of the cookie remembering the last chosen sorting order
+const ORDER_COOKIE: &str = "miniserve-order";
+
+/// Name of the file whose content is shown above the listing of its directory
+const HEADER_FILE_NAME: &str = "HEADER.html";
+
+/// Name of the file whose content is shown below the listing of its directory
+const FOOTER_FILE_NAME: &str = "FOOTER.html";
+
+/// Name of the file served instead of the listing of its directory with `--index-per-dir`
+const INDEX_PER_DIR_FILE_NAME: &str = "index.html";
+
 /// Query parameters
 #[derive(Deserialize)]
 pub struct QueryParameters {
     pub path: Option<PathBuf>,
     pub sort: Option<SortingMethod>,
     pub order: Option<SortingOrder>,
+    pub page: Option<usize>,
+    #[serde(rename = "type")]
+    pub entry_type: Option<EntryTypeFilter>,
+    #[serde(rename = "modified-since")]
+    pub modified_since: Option<String>,
+    pub view: Option<ListingView>,
     qrcode: Option<String>,
     download: Option<ArchiveMethod>,
+    compression: Option<CompressionLevel>,
+}
+
+/// Available ways to show the entries of a directory
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingView {
+    /// All entries in a table
+    Table,
+
+    /// Images in a grid, opening in a lightbox, and the remaining entries in a table
+    Gallery,
 }
 
 /// Available sorting methods
@@ -42,14 +88,18 @@ pub struct QueryParameters {
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum SortingMethod {
-    /// Sort by name
+    /// Sort by name (natural sort: numbers are compared by value, so "2" comes before "10")
+    #[serde(alias = "natural")]
     Name,
 
     /// Sort by size
     Size,
 
-    /// Sort by last modification date (natural sort: follows alphanumerical order)
+    /// Sort by last modification date
     Date,
+
+    /// Sort by file extension, then by name (directories have no extension)
+    Extension,
 }
 
 /// Available sorting orders
@@ -66,6 +116,35 @@ pub enum SortingOrder {
     Descending,
 }
 
+/// Types of entries a listing can be restricted to
+#[derive(Deserialize, Clone, Copy, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EntryTypeFilter {
+    /// Only list files
+    File,
+
+    /// Only list directories
+    Dir,
+
+    /// Only list symlinks, whatever they point to
+    Symlink,
+}
+
+/// How to handle requests for directories without a trailing slash
+#[derive(Clone, Copy, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum TrailingSlash {
+    /// Redirect to the URL with a trailing slash
+    Redirect,
+
+    /// Serve the listing under both URLs
+    Accept,
+
+    /// Only serve the listing under the URL with a trailing slash
+    Strict,
+}
+
 #[derive(PartialEq)]
 /// Possible entry types
 pub enum EntryType {
@@ -90,11 +169,21 @@ pub struct Entry {
     /// URL of the entry
     pub link: String,
 
-    /// Size in byte of the entry. Only available for EntryType::File
+    /// Size in byte of the entry. Only available for EntryType::File, or for
+    /// EntryType::Directory once computed when directory sizes are enabled
     pub size: Option<bytesize::ByteSize>,
 
     /// Last modification date
     pub last_modification_date: Option<SystemTime>,
+
+    /// Name to save the file as, if it differs from `name`
+    pub download_name: Option<String>,
+
+    /// Mode bits, owner and group. Only available on unix when permissions are shown
+    pub permissions: Option<Permissions>,
+
+    /// SHA-256 checksum, if the column is enabled and it has been computed before
+    pub checksum: Option<String>,
 }
 
 impl Entry {
@@ -105,6 +194,7 @@ impl Entry {
         link: String,
         size: Option<bytesize::ByteSize>,
         last_modification_date: Option<SystemTime>,
+        permissions: Option<Permissions>,
     ) -> Self {
         Entry {
             name,
@@ -113,6 +203,9 @@ impl Entry {
             link,
             size,
             last_modification_date,
+            download_name: None,
+            permissions,
+            checksum: None,
         }
     }
 
@@ -125,6 +218,106 @@ impl Entry {
     pub fn is_file(&self) -> bool {
         self.entry_type == EntryType::File
     }
+
+    /// Returns wether the entry is an image which browsers can display
+    pub fn is_image(&self) -> bool {
+        self.is_file()
+            && matches!(
+                self.extension().as_str(),
+                "png" | "jpg" | "jpeg" | "gif" | "bmp" | "svg" | "webp" | "ico" | "avif"
+            )
+    }
+
+    /// Returns the lowercase extension of the entry, which is empty for directories
+    pub fn extension(&self) -> String {
+        if self.is_dir() {
+            return String::new();
+        }
+        Path::new(&self.name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+/// Position of the rendered entries within a paginated listing
+#[derive(Clone, Copy)]
+pub struct Pagination {
+    /// Current page, starting at 1
+    pub page: usize,
+
+    /// Total number of pages
+    pub page_count: usize,
+}
+
+impl Pagination {
+    /// Computes the pagination for `entry_count` entries, clamping `page` to the existing pages
+    fn new(page: usize, page_size: usize, entry_count: usize) -> Self {
+        let page_count = entry_count.div_ceil(page_size).max(1);
+        Pagination {
+            page: page.clamp(1, page_count),
+            page_count,
+        }
+    }
+}
+
+/// Summary of all entries of the listed directory
+#[derive(Clone, Copy)]
+pub struct Totals {
+    /// Number of files
+    pub files: usize,
+
+    /// Number of directories
+    pub directories: usize,
+
+    /// Combined size of all entries whose size is known
+    pub size: ByteSize,
+}
+
+impl Totals {
+    fn new(entries: &[Entry]) -> Self {
+        let files = entries.iter().filter(|e| e.is_file()).count();
+        Totals {
+            files,
+            directories: entries.len() - files,
+            size: ByteSize::b(entries.iter().filter_map(|e| e.size).map(|s| s.0).sum()),
+        }
+    }
+}
+
+/// HTML snippets shown around the listing, like Apache's `HeaderName` and `ReadmeName`
+#[derive(Default)]
+pub struct Annotations {
+    /// Content of `HEADER.html` in the listed directory, shown above the entries
+    pub header: Option<String>,
+
+    /// Content of `FOOTER.html` in the listed directory, shown below the entries
+    pub footer: Option<String>,
+}
+
+impl Annotations {
+    /// Reads the annotation files of `dir`, if it has any
+    fn read(dir: &Path) -> Self {
+        let read = |name| std::fs::read_to_string(dir.join(name)).ok();
+        Annotations {
+            header: read(HEADER_FILE_NAME),
+            footer: read(FOOTER_FILE_NAME),
+        }
+    }
+
+    /// Returns whether `name` is the name of an annotation file, which isn't listed itself
+    fn is_annotation_file(name: &str) -> bool {
+        name == HEADER_FILE_NAME || name == FOOTER_FILE_NAME
+    }
+
+    /// Returns whether clients mustn't write a file named `name`, since it could be shown as an
+    /// annotation, whose HTML is inserted unescaped
+    ///
+    /// Names differing only in case are included for case-insensitive filesystems.
+    pub fn is_reserved_name(name: &OsStr) -> bool {
+        let name = name.to_string_lossy();
+        name.eq_ignore_ascii_case(HEADER_FILE_NAME) || name.eq_ignore_ascii_case(FOOTER_FILE_NAME)
+    }
 }
 
 /// One entry in the path to the listed directory
@@ -147,49 +340,114 @@ pub async fn file_handler(req: HttpRequest) -> Result<actix_files::NamedFile> {
     actix_files::NamedFile::open(path).map_err(Into::into)
 }
 
+/// Query parameters of the QR code route
+#[derive(Deserialize)]
+pub struct QrCodeParameters {
+    /// Percent-encoded path of the file, as linked in the listing
+    path: String,
+}
+
+/// Responds with a QR code of the absolute URL of a single file
+pub async fn file_qr_code(req: HttpRequest, query: Query<QrCodeParameters>) -> HttpResponse {
+    if !query.path.starts_with('/') {
+        return HttpResponse::BadRequest().body(Body::Empty);
+    }
+
+    let connection_info = req.connection_info();
+    let url = format!(
+        "{}://{}{}",
+        connection_info.scheme(),
+        connection_info.host(),
+        query.path
+    );
+    qr_code_response(&url)
+}
+
+/// Encodes `url` into an SVG QR code
+fn qr_code_response(url: &str) -> HttpResponse {
+    match QrCode::encode_text(url, QrCodeEcc::Medium) {
+        Ok(qr) => HttpResponse::Ok()
+            .header("Content-Type", "image/svg+xml")
+            .body(qr.to_svg_string(2)),
+        Err(err) => {
+            log::error!("URL is too long: {:?}", err);
+            HttpResponse::UriTooLong().body(Body::Empty)
+        }
+    }
+}
+
 /// List a directory and renders a HTML file accordingly
 /// Adapted from https://docs.rs/actix-web/0.7.13/src/actix_web/fs.rs.html#564
-#[allow(clippy::too_many_arguments)]
 pub fn directory_listing(
     dir: &actix_files::Directory,
     req: &HttpRequest,
-    skip_symlinks: bool,
-    show_hidden: bool,
-    file_upload: bool,
-    random_route: Option<String>,
-    favicon_route: String,
-    css_route: String,
-    default_color_scheme: &str,
-    default_color_scheme_dark: &str,
-    show_qrcode: bool,
-    upload_route: String,
-    tar_enabled: bool,
-    tar_gz_enabled: bool,
-    zip_enabled: bool,
-    dirs_first: bool,
-    hide_version_footer: bool,
-    title: Option<String>,
 ) -> Result<ServiceResponse, io::Error> {
     use actix_web::dev::BodyEncoding;
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let lang = Language::negotiate(req.headers(), conf.lang);
     let serve_path = req.path();
 
-    // In case the current path is a directory, we want to make sure that the current URL ends
-    // on a slash ("/").
+    // In case the current path is a directory, the canonical URL ends on a slash ("/").
+    // Depending on the configuration, we redirect to it, reject the request or just go on.
     if !serve_path.ends_with('/') {
-        let query = match req.query_string() {
-            "" => String::new(),
-            _ => format!("?{}", req.query_string()),
-        };
-        return Ok(ServiceResponse::new(
-            req.clone(),
-            HttpResponse::MovedPermanently()
-                .header("Location", format!("{}/{}", serve_path, query))
-                .body("301"),
-        ));
+        match conf.trailing_slash {
+            TrailingSlash::Redirect => {
+                let query = match req.query_string() {
+                    "" => String::new(),
+                    _ => format!("?{}", req.query_string()),
+                };
+                return Ok(ServiceResponse::new(
+                    req.clone(),
+                    HttpResponse::MovedPermanently()
+                        .header("Location", format!("{}/{}", serve_path, query))
+                        .body("301"),
+                ));
+            }
+            TrailingSlash::Strict => {
+                let err = ContextualError::RouteNotFoundError(serve_path.to_string());
+                errors::log_error_chain(err.to_string());
+                return Ok(ServiceResponse::new(
+                    req.clone(),
+                    HttpResponse::NotFound()
+                        .content_type("text/html; charset=utf-8")
+                        .body(
+                            renderer::render_error(
+                                &err.to_string(),
+                                StatusCode::NOT_FOUND,
+                                "/",
+                                None,
+                                None,
+                                false,
+                                conf.random_route.is_none(),
+                                &conf.favicon_route,
+                                &conf.css_route,
+                                &conf.reloadable.get().default_color_scheme,
+                                &conf.reloadable.get().default_color_scheme_dark,
+                                conf.hide_version_footer,
+                                conf.templates.as_ref(),
+                                lang,
+                            )
+                            .into_string(),
+                        ),
+                ));
+            }
+            TrailingSlash::Accept => (),
+        }
+    }
+
+    // Directories of static sites are served as the site, the others are still listed
+    if conf.index_per_dir {
+        let index_path = dir.path.join(INDEX_PER_DIR_FILE_NAME);
+        if index_path.is_file() {
+            let res = actix_files::NamedFile::open(index_path)?
+                .into_response(req)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            return Ok(ServiceResponse::new(req.clone(), res));
+        }
     }
 
     let base = Path::new(serve_path);
-    let random_route_abs = format!("/{}", random_route.clone().unwrap_or_default());
+    let random_route_abs = format!("/{}", conf.random_route.clone().unwrap_or_default());
     let is_root = base.parent().is_none() || Path::new(&req.path()) == Path::new(&random_route_abs);
 
     let encoded_dir = match base.strip_prefix(random_route_abs) {
@@ -200,13 +458,21 @@ pub fn directory_listing(
     .to_string();
 
     let breadcrumbs = {
-        let title = title.unwrap_or_else(|| req.connection_info().host().into());
+        let title = conf
+            .title
+            .clone()
+            .unwrap_or_else(|| req.connection_info().host().into());
 
         let decoded = percent_decode_str(&encoded_dir).decode_utf8_lossy();
 
         let mut res: Vec<Breadcrumb> = Vec::new();
-        let mut link_accumulator =
-            format!("/{}", random_route.map(|r| r + "/").unwrap_or_default());
+        let mut link_accumulator = format!(
+            "/{}",
+            conf.random_route
+                .clone()
+                .map(|r| r + "/")
+                .unwrap_or_default()
+        );
 
         let mut components = Path::new(&*decoded).components().peekable();
 
@@ -237,29 +503,124 @@ pub fn directory_listing(
         res
     };
 
-    let query_params = extract_query_parameters(req);
+    let mut query_params = extract_query_parameters(req);
+
+    // Sorting chosen explicitly is remembered, otherwise fall back to the one chosen last time
+    let remember_sorting = query_params.sort.is_some();
+    if !remember_sorting {
+        query_params.sort = req
+            .cookie(SORT_COOKIE)
+            .and_then(|cookie| cookie.value().parse().ok());
+        query_params.order = req
+            .cookie(ORDER_COOKIE)
+            .and_then(|cookie| cookie.value().parse().ok());
+    }
 
     // If the `qrcode` parameter is included in the url, then should respond to the QR code
-    if let Some(url) = query_params.qrcode {
-        let res = match QrCode::encode_text(&url, QrCodeEcc::Medium) {
-            Ok(qr) => HttpResponse::Ok()
-                .header("Content-Type", "image/svg+xml")
-                .body(qr.to_svg_string(2)),
-            Err(err) => {
-                log::error!("URL is too long: {:?}", err);
-                HttpResponse::UriTooLong().body(Body::Empty)
-            }
-        };
-        return Ok(ServiceResponse::new(req.clone(), res));
+    if let Some(url) = query_params.qrcode.as_deref() {
+        return Ok(ServiceResponse::new(req.clone(), qr_code_response(url)));
     }
 
+    // Only list the entries modified after the given time, if any
+    let modified_since = match query_params
+        .modified_since
+        .as_deref()
+        .map(DateTime::parse_from_rfc3339)
+    {
+        Some(Ok(date)) => Some(SystemTime::from(date)),
+        Some(Err(e)) => {
+            let err = ContextualError::ParseError("modified-since".to_string(), e.to_string());
+            errors::log_error_chain(err.to_string());
+            return Ok(ServiceResponse::new(
+                req.clone(),
+                HttpResponse::BadRequest()
+                    .content_type("text/html; charset=utf-8")
+                    .body(
+                        renderer::render_error(
+                            &err.to_string(),
+                            StatusCode::BAD_REQUEST,
+                            "/",
+                            None,
+                            None,
+                            false,
+                            conf.random_route.is_none(),
+                            &conf.favicon_route,
+                            &conf.css_route,
+                            &conf.reloadable.get().default_color_scheme,
+                            &conf.reloadable.get().default_color_scheme_dark,
+                            conf.hide_version_footer,
+                            conf.templates.as_ref(),
+                            lang,
+                        )
+                        .into_string(),
+                    ),
+            ));
+        }
+        None => None,
+    };
+
+    // Previews of shared links need absolute URLs
+    let origin = {
+        let connection_info = req.connection_info();
+        format!("{}://{}", connection_info.scheme(), connection_info.host())
+    };
+
+    // Rendered listings may be served from memory, archives never are
+    let cache_key = match conf.listing_cache {
+        Some(_) if query_params.download.is_none() && modified_since.is_none() => {
+            Some(listing_cache::CacheKey {
+                uri: req.uri().to_string(),
+                sorting: (
+                    query_params.sort.map(|sort| sort.to_string()),
+                    query_params.order.map(|order| order.to_string()),
+                ),
+                lang: lang.to_string(),
+                origin: origin.clone(),
+                user: Authorization::<Basic>::parse(req)
+                    .ok()
+                    .map(|auth| auth.into_scheme().user_id().to_string()),
+            })
+        }
+        _ => None,
+    };
+    if let (Some(listing_cache), Some(cache_key)) = (&conf.listing_cache, &cache_key) {
+        if let Some(body) = listing_cache.get(cache_key, &dir.path) {
+            return Ok(ServiceResponse::new(
+                req.clone(),
+                conditional::respond(
+                    req,
+                    listing_response(remember_sorting, &query_params, conf),
+                    body,
+                    conditional::listing_modified(&dir.path),
+                ),
+            ));
+        }
+    }
+
+    let ignore_files = if conf.ignore_files {
+        Some(IgnoreFiles::for_dir(&conf.path, &dir.path))
+    } else {
+        None
+    };
+
+    let owner_names = if conf.show_permissions {
+        Some(OwnerNames::load())
+    } else {
+        None
+    };
+
     let mut entries: Vec<Entry> = Vec::new();
 
+    // Reading the directory and the metadata of its entries are timed separately
+    let listing_start = Instant::now();
+    let mut stat_duration = Duration::default();
+
     for entry in dir.path.read_dir()? {
-        if dir.is_visible(&entry) || show_hidden {
+        if dir.is_visible(&entry) || conf.show_hidden {
             let entry = entry?;
             // show file url as relative to static path
             let file_name = entry.file_name().to_string_lossy().to_string();
+            let stat_start = Instant::now();
             let (is_symlink, metadata) = match entry.metadata() {
                 Ok(metadata) if metadata.file_type().is_symlink() => {
                     // for symlinks, get the metadata of the original file
@@ -267,6 +628,7 @@ pub fn directory_listing(
                 }
                 res => (false, res),
             };
+            stat_duration += stat_start.elapsed();
             let file_url = base
                 .join(&utf8_percent_encode(&file_name, PATH_SEGMENT).to_string())
                 .to_string_lossy()
@@ -274,32 +636,74 @@ pub fn directory_listing(
 
             // if file is a directory, add '/' to the end of the name
             if let Ok(metadata) = metadata {
-                if skip_symlinks && is_symlink {
+                if conf.no_symlinks && is_symlink {
                     continue;
                 }
+                if let Some(ignore_files) = &ignore_files {
+                    if ignore_files.is_ignored(&entry.path(), metadata.is_dir()) {
+                        continue;
+                    }
+                }
                 let last_modification_date = match metadata.modified() {
                     Ok(date) => Some(date),
                     Err(_) => None,
                 };
+                let permissions = owner_names
+                    .as_ref()
+                    .and_then(|owner_names| owner_names.permissions(&metadata));
 
                 if metadata.is_dir() {
+                    let size = conf
+                        .dir_sizes
+                        .as_ref()
+                        .and_then(|dir_sizes| dir_sizes.get(&entry.path(), conf))
+                        .map(ByteSize::b);
+                    if let (Some(since), false) = (modified_since, is_symlink) {
+                        push_changed_entries(
+                            &entry.path(),
+                            &file_name,
+                            &file_url,
+                            since,
+                            ignore_files
+                                .as_ref()
+                                .map(|ignore_files| ignore_files.with_dir(&entry.path()))
+                                .as_ref(),
+                            owner_names.as_ref(),
+                            conf,
+                            &mut entries,
+                        );
+                    }
                     entries.push(Entry::new(
                         file_name,
                         EntryType::Directory,
                         is_symlink,
                         file_url,
-                        None,
+                        size,
                         last_modification_date,
+                        permissions,
                     ));
                 } else if metadata.is_file() {
-                    entries.push(Entry::new(
+                    if Annotations::is_annotation_file(&file_name) {
+                        continue;
+                    }
+                    let mut file_entry = Entry::new(
                         file_name,
                         EntryType::File,
                         is_symlink,
                         file_url,
                         Some(ByteSize::b(metadata.len())),
                         last_modification_date,
-                    ));
+                        permissions,
+                    );
+                    if conf.show_checksums {
+                        // Checksums are cached by the path symlinks resolve to
+                        file_entry.checksum = entry
+                            .path()
+                            .canonicalize()
+                            .ok()
+                            .and_then(|path| conf.checksums.get(&path, &metadata));
+                    }
+                    entries.push(file_entry);
                 }
             } else {
                 continue;
@@ -307,7 +711,38 @@ pub fn directory_listing(
         }
     }
 
-    match query_params.sort.unwrap_or(SortingMethod::Name) {
+    disambiguate_case_collisions(&mut entries, &dir.path);
+
+    slow_ops::check(
+        conf.slow_op_threshold,
+        "reading directory",
+        &dir.path,
+        listing_start.elapsed() - stat_duration,
+    );
+    slow_ops::check(
+        conf.slow_op_threshold,
+        "reading metadata of the entries of",
+        &dir.path,
+        stat_duration,
+    );
+
+    if let Some(entry_type) = query_params.entry_type {
+        entries.retain(|entry| match entry_type {
+            EntryTypeFilter::File => entry.is_file(),
+            EntryTypeFilter::Dir => entry.is_dir(),
+            EntryTypeFilter::Symlink => entry.is_symlink,
+        });
+    }
+
+    if let Some(modified_since) = modified_since {
+        entries.retain(|entry| {
+            entry
+                .last_modification_date
+                .map_or(false, |date| date > modified_since)
+        });
+    }
+
+    match query_params.sort.unwrap_or(conf.default_sort) {
         SortingMethod::Name => entries.sort_by(|e1, e2| {
             alphanumeric_sort::compare_str(e1.name.to_lowercase(), e2.name.to_lowercase())
         }),
@@ -325,6 +760,11 @@ pub fn directory_listing(
                 .unwrap_or(SystemTime::UNIX_EPOCH)
                 .cmp(&e1.last_modification_date.unwrap_or(SystemTime::UNIX_EPOCH))
         }),
+        SortingMethod::Extension => entries.sort_by(|e1, e2| {
+            e1.extension().cmp(&e2.extension()).then_with(|| {
+                alphanumeric_sort::compare_str(e1.name.to_lowercase(), e2.name.to_lowercase())
+            })
+        }),
     };
 
     if let Some(SortingOrder::Descending) = query_params.order {
@@ -332,12 +772,20 @@ pub fn directory_listing(
     }
 
     // List directories first
-    if dirs_first {
+    if conf.dirs_first {
         entries.sort_by_key(|e| !e.is_dir());
     }
 
+    let archives_allowed = !conf.no_archive.is_disabled(&dir.path);
+
     if let Some(archive_method) = query_params.download {
-        if !archive_method.is_enabled(tar_enabled, tar_gz_enabled, zip_enabled) {
+        if !archive_method.is_enabled(
+            conf.tar_enabled,
+            conf.tar_gz_enabled,
+            conf.zip_enabled,
+            conf.seven_zip_enabled,
+        ) || !archives_allowed
+        {
             return Ok(ServiceResponse::new(
                 req.clone(),
                 HttpResponse::Forbidden()
@@ -351,11 +799,13 @@ pub fn directory_listing(
                             None,
                             false,
                             false,
-                            &favicon_route,
-                            &css_route,
-                            default_color_scheme,
-                            default_color_scheme_dark,
-                            hide_version_footer,
+                            &conf.favicon_route,
+                            &conf.css_route,
+                            &conf.reloadable.get().default_color_scheme,
+                            &conf.reloadable.get().default_color_scheme_dark,
+                            conf.hide_version_footer,
+                            conf.templates.as_ref(),
+                            lang,
                         )
                         .into_string(),
                     ),
@@ -367,67 +817,510 @@ pub fn directory_listing(
             path = &dir.path.display().to_string()
         );
 
-        let file_name = format!(
-            "{}.{}",
-            dir.path.file_name().unwrap().to_str().unwrap(),
-            archive_method.extension()
-        );
-
-        // We will create the archive in a separate thread, and stream the content using a pipe.
-        // The pipe is made of a futures channel, and an adapter to implement the `Write` trait.
-        // Include 10 messages of buffer for erratic connection speeds.
-        let (tx, rx) = futures::channel::mpsc::channel::<Result<actix_web::web::Bytes, ()>>(10);
-        let pipe = crate::pipe::Pipe::new(tx);
-
-        // Start the actual archive creation in a separate thread.
-        let dir = dir.path.to_path_buf();
-        std::thread::spawn(move || {
-            if let Err(err) = archive_method.create_archive(dir, skip_symlinks, pipe) {
-                log::error!("Error during archive creation: {:?}", err);
-            }
-        });
-
         Ok(ServiceResponse::new(
             req.clone(),
-            HttpResponse::Ok()
-                .content_type(archive_method.content_type())
-                .encoding(archive_method.content_encoding())
-                .header("Content-Transfer-Encoding", "binary")
-                .header(
-                    "Content-Disposition",
-                    format!("attachment; filename={:?}", file_name),
-                )
-                .body(actix_web::body::BodyStream::new(rx)),
+            archive_response(
+                archive_method,
+                dir.path.to_path_buf(),
+                archive_ignore_files(ignore_files, &dir.path, conf),
+                None,
+                query_params.compression,
+                conf,
+                lang,
+            ),
         ))
     } else {
+        let totals = Totals::new(&entries);
+
+        // Only render a single page of entries so that huge directories stay manageable
+        let pagination = conf.page_size.map(|page_size| {
+            let pagination =
+                Pagination::new(query_params.page.unwrap_or(1), page_size, entries.len());
+            entries = entries
+                .drain(..)
+                .skip((pagination.page - 1) * page_size)
+                .take(page_size)
+                .collect();
+            pagination
+        });
+
+        let res = listing_response(remember_sorting, &query_params, conf);
+        let body = renderer::page(
+            entries,
+            is_root,
+            query_params,
+            pagination,
+            totals,
+            Annotations::read(&dir.path),
+            archives_allowed,
+            breadcrumbs,
+            &encoded_dir,
+            &origin,
+            lang,
+            conf,
+        )
+        .into_string();
+
+        if let (Some(listing_cache), Some(cache_key)) = (&conf.listing_cache, cache_key) {
+            listing_cache.insert(cache_key, &dir.path, body.clone());
+        }
+
+        // Changes below the direct entries don't show in their modification dates
+        let last_modified = match modified_since {
+            Some(_) => None,
+            None => conditional::listing_modified(&dir.path),
+        };
         Ok(ServiceResponse::new(
             req.clone(),
-            HttpResponse::Ok()
+            conditional::respond(req, res, body, last_modified),
+        ))
+    }
+}
+
+/// Appends the entries below the directory `path` that were modified after `since`
+///
+/// They are named by their path relative to the listed directory, starting with `name`, the
+/// name of `path` there, and linked below `link`. Entries hidden from listings are left out, and
+/// symlinked directories aren't descended into.
+#[allow(clippy::too_many_arguments)]
+fn push_changed_entries(
+    path: &Path,
+    name: &str,
+    link: &str,
+    since: SystemTime,
+    ignore_files: Option<&IgnoreFiles>,
+    owner_names: Option<&OwnerNames>,
+    conf: &MiniserveConfig,
+    entries: &mut Vec<Entry>,
+) {
+    let dir_entries = match path.read_dir() {
+        Ok(dir_entries) => dir_entries,
+        Err(_) => return,
+    };
+    for entry in dir_entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with('.') && !conf.show_hidden {
+            continue;
+        }
+        let is_symlink = entry
+            .file_type()
+            .map_or(false, |file_type| file_type.is_symlink());
+        if conf.no_symlinks && is_symlink {
+            continue;
+        }
+        let entry_path = entry.path();
+        let metadata = match entry_path.metadata() {
+            Ok(metadata) if metadata.is_dir() || metadata.is_file() => metadata,
+            _ => continue,
+        };
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&entry_path, metadata.is_dir()) {
+                continue;
+            }
+        }
+        if metadata.is_file() && Annotations::is_annotation_file(&file_name) {
+            continue;
+        }
+
+        let entry_name = format!("{}/{}", name, file_name);
+        let entry_link = Path::new(link)
+            .join(&utf8_percent_encode(&file_name, PATH_SEGMENT).to_string())
+            .to_string_lossy()
+            .to_string();
+        let last_modification_date = metadata.modified().ok();
+        if last_modification_date.map_or(false, |date| date > since) {
+            let (entry_type, size) = if metadata.is_dir() {
+                (EntryType::Directory, None)
+            } else {
+                (EntryType::File, Some(ByteSize::b(metadata.len())))
+            };
+            entries.push(Entry::new(
+                entry_name.clone(),
+                entry_type,
+                is_symlink,
+                entry_link.clone(),
+                size,
+                last_modification_date,
+                owner_names.and_then(|owner_names| owner_names.permissions(&metadata)),
+            ));
+        }
+        if metadata.is_dir() && !is_symlink {
+            push_changed_entries(
+                &entry_path,
+                &entry_name,
+                &entry_link,
+                since,
+                ignore_files
+                    .map(|ignore_files| ignore_files.with_dir(&entry_path))
+                    .as_ref(),
+                owner_names,
+                conf,
+                entries,
+            );
+        }
+    }
+}
+
+/// Starts the response to a listing, remembering the chosen sorting if asked to
+fn listing_response(
+    remember_sorting: bool,
+    query_params: &QueryParameters,
+    conf: &crate::MiniserveConfig,
+) -> HttpResponseBuilder {
+    let mut res = HttpResponse::Ok();
+    if remember_sorting {
+        let sort = query_params.sort.unwrap_or(conf.default_sort);
+        let order = query_params.order.unwrap_or(SortingOrder::Ascending);
+        res.cookie(
+            Cookie::build(SORT_COOKIE, sort.to_string())
+                .path("/")
+                .finish(),
+        )
+        .cookie(
+            Cookie::build(ORDER_COOKIE, order.to_string())
+                .path("/")
+                .finish(),
+        );
+    }
+    res
+}
+
+/// Gives files whose names only differ in case distinct download names
+///
+/// Clients on case-insensitive filesystems can't store such files side by side, so all but the
+/// first of them are saved with a counter, like `file (2).TXT`.
+fn disambiguate_case_collisions(entries: &mut [Entry], dir: &Path) {
+    let mut indices: Vec<usize> = (0..entries.len()).collect();
+    indices.sort_by(|&a, &b| entries[a].name.cmp(&entries[b].name));
+
+    // First name and number of entries per case-folded name
+    let mut seen: HashMap<String, (String, usize)> = HashMap::new();
+    for index in indices {
+        let entry = &mut entries[index];
+        let (first_name, count) = seen
+            .entry(entry.name.to_lowercase())
+            .or_insert_with(|| (entry.name.clone(), 0));
+        *count += 1;
+        if *count == 1 {
+            continue;
+        }
+
+        log::warn!(
+            "Entries {:?} and {:?} of {} only differ in case",
+            first_name,
+            entry.name,
+            dir.display()
+        );
+        if entry.is_file() {
+            let path = Path::new(&entry.name);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            entry.download_name = Some(match path.extension() {
+                Some(extension) => {
+                    format!("{} ({}).{}", stem, count, extension.to_string_lossy())
+                }
+                None => format!("{} ({})", stem, count),
+            });
+        }
+    }
+}
+
+/// Streams an archive of `dir`, or only of the `selection` of its entries if set
+fn archive_response(
+    archive_method: ArchiveMethod,
+    dir: PathBuf,
+    ignore_files: Option<IgnoreFiles>,
+    selection: Option<Vec<OsString>>,
+    compression: Option<CompressionLevel>,
+    conf: &crate::MiniserveConfig,
+    lang: Language,
+) -> HttpResponse {
+    let skip_symlinks = conf.no_symlinks;
+    let slow_op_threshold = conf.slow_op_threshold;
+    let read_ignore_files = conf.ignore_files;
+    let archive_cache = conf.archive_cache.clone();
+    let file_name = format!(
+        "{}.{}",
+        dir.file_name().unwrap().to_str().unwrap(),
+        archive_method.extension()
+    );
+
+    // Huge archives are refused up front instead of keeping the connection busy for hours
+    let size = slow_ops::timed(slow_op_threshold, "computing archive size of", &dir, || {
+        archive::content_size(
+            &dir,
+            skip_symlinks,
+            ignore_files.as_ref(),
+            selection.as_deref(),
+        )
+    });
+    if let Some(max_archive_size) = conf.max_archive_size {
+        if size > max_archive_size {
+            let err = ContextualError::ArchiveTooLargeError(
+                ByteSize::b(size).to_string(),
+                ByteSize::b(max_archive_size).to_string(),
+            );
+            errors::log_error_chain(err.to_string());
+            return HttpResponse::Forbidden()
                 .content_type("text/html; charset=utf-8")
                 .body(
-                    renderer::page(
-                        entries,
-                        is_root,
-                        query_params.sort,
-                        query_params.order,
-                        show_qrcode,
-                        file_upload,
-                        &upload_route,
-                        &favicon_route,
-                        &css_route,
-                        default_color_scheme,
-                        default_color_scheme_dark,
-                        &encoded_dir,
-                        breadcrumbs,
-                        tar_enabled,
-                        tar_gz_enabled,
-                        zip_enabled,
-                        hide_version_footer,
+                    renderer::render_error(
+                        &err.to_string(),
+                        StatusCode::FORBIDDEN,
+                        "/",
+                        None,
+                        None,
+                        false,
+                        false,
+                        &conf.favicon_route,
+                        &conf.css_route,
+                        &conf.reloadable.get().default_color_scheme,
+                        &conf.reloadable.get().default_color_scheme_dark,
+                        conf.hide_version_footer,
+                        conf.templates.as_ref(),
+                        lang,
                     )
                     .into_string(),
+                );
+        }
+    }
+
+    // We will create the archive in a separate thread, and stream the content using a pipe.
+    // The pipe is made of a futures channel, and an adapter to implement the `Write` trait.
+    // Include 10 messages of buffer for erratic connection speeds.
+    let (tx, rx) = futures::channel::mpsc::channel::<Result<actix_web::web::Bytes, ()>>(10);
+    let mut pipe = RateLimited::new(crate::pipe::Pipe::new(tx), conf.archive_rate_limit);
+
+    // Start the actual archive creation on a thread of the archive pool.
+    conf.archive_pool.spawn(move || {
+        // Only archives of whole directories are cached, selections hardly ever repeat
+        let cache_entry = match (&archive_cache, &selection) {
+            (Some(archive_cache), None) => Some(slow_ops::timed(
+                slow_op_threshold,
+                "checking archive cache for",
+                &dir,
+                || {
+                    archive_cache.entry(
+                        archive_method,
+                        &dir,
+                        skip_symlinks,
+                        read_ignore_files,
+                        compression,
+                    )
+                },
+            )),
+            _ => None,
+        };
+
+        let create_archive = |out: &mut dyn Write| {
+            slow_ops::timed(slow_op_threshold, "creating archive of", &dir, || {
+                archive_method.create_archive(
+                    &dir,
+                    skip_symlinks,
+                    ignore_files.as_ref(),
+                    selection.as_deref(),
+                    compression,
+                    out,
+                )
+            })
+        };
+        let result = match cache_entry {
+            Some(cache_entry) => cache_entry.send_or_create(pipe, create_archive),
+            None => create_archive(&mut pipe),
+        };
+        if let Err(err) = result {
+            log::error!("Error during archive creation: {:?}", err);
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(archive_method.content_type())
+        .encoding(archive_method.content_encoding())
+        .header("Content-Transfer-Encoding", "binary")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename={:?}", file_name),
+        )
+        // Archives are streamed without a length, this lets clients show the progress anyway
+        .header("X-Estimated-Size", size.to_string())
+        .body(actix_web::body::BodyStream::new(rx))
+}
+
+/// Adds the patterns of `--archive-exclude` and the directories which can't be archived to the
+/// ignore files applying to an archive of `dir`
+fn archive_ignore_files(
+    ignore_files: Option<IgnoreFiles>,
+    dir: &Path,
+    conf: &crate::MiniserveConfig,
+) -> Option<IgnoreFiles> {
+    let mut ignore_files = ignore_files.unwrap_or_default();
+    if !conf.archive_excludes.is_empty() {
+        ignore_files = ignore_files.with_excludes(dir, &conf.archive_excludes);
+    }
+    Some(ignore_files.with_no_archive(&conf.no_archive))
+}
+
+/// Returns whether the resolved path `path` inside the served directory is shown in listings,
+/// i.e. neither it nor one of its parents is hidden or excluded by ignore files
+pub fn is_listed_path(path: &Path, conf: &crate::MiniserveConfig) -> bool {
+    let root = match conf.path.canonicalize() {
+        Ok(root) => root,
+        Err(_) => return false,
+    };
+    let relative_path = match path.strip_prefix(&root) {
+        Ok(relative_path) => relative_path,
+        Err(_) => return false,
+    };
+    if !conf.show_hidden && path_policy::is_hidden(relative_path) {
+        return false;
+    }
+    match path.parent() {
+        Some(parent) if conf.ignore_files && path != root => {
+            !IgnoreFiles::for_dir(&root, parent).is_ignored(path, path.is_dir())
+        }
+        _ => true,
+    }
+}
+
+/// Returns whether the entry `name` of `dir` is shown in the listing, so that clients may act
+/// on it.
+///
+/// Entries of directories which aren't listed themselves, like hidden directories, aren't
+/// listed either.
+pub fn is_listed_entry(dir: &Path, name: &OsStr, conf: &crate::MiniserveConfig) -> bool {
+    let path = Path::new(name);
+    path_policy::is_entry_name(path)
+        && dir.join(path).symlink_metadata().map_or(false, |metadata| {
+            !(conf.no_symlinks && metadata.file_type().is_symlink())
+        })
+        && is_listed_path(&dir.join(path), conf)
+}
+
+/// Creates an archive of the entries selected in the listing
+///
+/// Like for uploads, the directory is given by the `path` query parameter. The form data
+/// contains the archive `method` and one `entry` field per selected entry of the directory.
+pub async fn download_selection(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let error_response = |error: ContextualError, status: StatusCode| {
+        errors::log_error_chain(error.to_string());
+        HttpResponse::build(status)
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    status,
+                    "/",
+                    None,
+                    None,
+                    false,
+                    false,
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            )
+    };
+
+    let mut archive_method = None;
+    let mut selection = Vec::new();
+    for (key, value) in form_urlencoded::parse(&body) {
+        match key.as_ref() {
+            "method" => archive_method = value.parse::<ArchiveMethod>().ok(),
+            "entry" => selection.push(OsString::from(value.into_owned())),
+            _ => {}
+        }
+    }
+
+    let archive_method = match archive_method {
+        Some(archive_method)
+            if archive_method.is_enabled(
+                conf.tar_enabled,
+                conf.tar_gz_enabled,
+                conf.zip_enabled,
+                conf.seven_zip_enabled,
+            ) =>
+        {
+            archive_method
+        }
+        _ => {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(
+                    "Missing or disabled archive method".to_string(),
                 ),
-        ))
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+    if selection.is_empty() {
+        return error_response(
+            ContextualError::InvalidHttpRequestError("No entries selected".to_string()),
+            StatusCode::BAD_REQUEST,
+        );
+    }
+
+    // The directory has to be inside the served directory, like the target of uploads
+    let query_params = extract_query_parameters(&req);
+    let dir_path = query_params.path.unwrap_or_default();
+    let dir = match path_policy::resolve_request_path(&conf.path, &dir_path) {
+        Ok(dir) if dir.is_dir() => dir,
+        _ => {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(
+                    "Invalid value for 'path' parameter".to_string(),
+                ),
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+    if conf.no_archive.is_disabled(&dir) {
+        return error_response(
+            ContextualError::InvalidHttpRequestError(
+                "Archive creation is disabled for this directory".to_string(),
+            ),
+            StatusCode::FORBIDDEN,
+        );
     }
+
+    // Only entries which are listed may be selected
+    for name in &selection {
+        if !is_listed_entry(&dir, name, conf) {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(format!(
+                    "Invalid entry '{}'",
+                    name.to_string_lossy()
+                )),
+                StatusCode::BAD_REQUEST,
+            );
+        }
+    }
+
+    log::info!(
+        "Creating an archive ({extension}) of {count} entries of {path}...",
+        extension = archive_method.extension(),
+        count = selection.len(),
+        path = dir.display()
+    );
+
+    let ignore_files = if conf.ignore_files {
+        Some(IgnoreFiles::for_dir(&conf.path, &dir))
+    } else {
+        None
+    };
+    let ignore_files = archive_ignore_files(ignore_files, &dir, conf);
+    archive_response(
+        archive_method,
+        dir,
+        ignore_files,
+        Some(selection),
+        query_params.compression,
+        conf,
+        Language::negotiate(req.headers(), conf.lang),
+    )
 }
 
 pub fn extract_query_parameters(req: &HttpRequest) -> QueryParameters {
@@ -435,7 +1328,12 @@ pub fn extract_query_parameters(req: &HttpRequest) -> QueryParameters {
         Ok(query) => QueryParameters {
             sort: query.sort,
             order: query.order,
+            page: query.page,
+            entry_type: query.entry_type,
+            modified_since: query.modified_since.clone(),
             download: query.download,
+            compression: query.compression,
+            view: query.view,
             qrcode: query.qrcode.to_owned(),
             path: query.path.clone(),
         },
@@ -445,7 +1343,12 @@ pub fn extract_query_parameters(req: &HttpRequest) -> QueryParameters {
             QueryParameters {
                 sort: None,
                 order: None,
+                page: None,
+                entry_type: None,
+                modified_since: None,
                 download: None,
+                compression: None,
+                view: None,
                 qrcode: None,
                 path: None,
             }
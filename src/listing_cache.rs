@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time after which a cached listing is rendered again, so that sizes and relative dates of
+/// entries which changed in place don't stay outdated
+const MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Everything a rendered listing depends on besides the directory itself
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Path and query string of the request
+    pub uri: String,
+
+    /// Sorting taken from the cookies if the request doesn't choose one
+    pub sorting: (Option<String>, Option<String>),
+
+    /// Negotiated language
+    pub lang: String,
+
+    /// Scheme and host the listing is served on, used by link previews
+    pub origin: String,
+
+    /// User who authenticated, if any
+    pub user: Option<String>,
+}
+
+/// One rendered listing
+struct CachedListing {
+    /// Rendered page
+    body: String,
+
+    /// Modification date of the directory when the page was rendered
+    dir_modified: Option<SystemTime>,
+
+    /// When the page was rendered
+    rendered_at: Instant,
+
+    /// Value of the use counter when the page was last served, the lowest one is evicted first
+    last_used: u64,
+}
+
+/// Least recently used rendered listings
+#[derive(Clone)]
+pub struct ListingCache {
+    capacity: usize,
+    listings: Arc<Mutex<(HashMap<CacheKey, CachedListing>, u64)>>,
+}
+
+impl ListingCache {
+    pub fn new(capacity: usize) -> Self {
+        ListingCache {
+            capacity,
+            listings: Arc::default(),
+        }
+    }
+
+    /// Returns the listing rendered for `key`, unless `dir` changed since then.
+    ///
+    /// Adding, removing or renaming entries updates the modification date of `dir`, so these
+    /// changes show up right away; anything else after `MAX_AGE` at most.
+    pub fn get(&self, key: &CacheKey, dir: &Path) -> Option<String> {
+        let mut guard = self.listings.lock().unwrap();
+        let (listings, counter) = &mut *guard;
+        let listing = listings.get_mut(key)?;
+
+        if listing.rendered_at.elapsed() > MAX_AGE || listing.dir_modified != dir_modified(dir) {
+            listings.remove(key);
+            return None;
+        }
+
+        *counter += 1;
+        listing.last_used = *counter;
+        Some(listing.body.clone())
+    }
+
+    /// Caches the listing rendered for `key`, evicting the least recently used one if full
+    pub fn insert(&self, key: CacheKey, dir: &Path, body: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut guard = self.listings.lock().unwrap();
+        let (listings, counter) = &mut *guard;
+
+        if listings.len() >= self.capacity && !listings.contains_key(&key) {
+            let least_recently_used = listings
+                .iter()
+                .min_by_key(|(_, listing)| listing.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                listings.remove(&least_recently_used);
+            }
+        }
+
+        *counter += 1;
+        listings.insert(
+            key,
+            CachedListing {
+                body,
+                dir_modified: dir_modified(dir),
+                rendered_at: Instant::now(),
+                last_used: *counter,
+            },
+        );
+    }
+
+    /// Forgets all listings, e.g. after the served directory has been replaced
+    pub fn clear(&self) {
+        self.listings.lock().unwrap().0.clear();
+    }
+}
+
+fn dir_modified(dir: &Path) -> Option<SystemTime> {
+    dir.metadata().and_then(|metadata| metadata.modified()).ok()
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::fixture::TempDir;
+    use pretty_assertions::assert_eq;
+
+    fn key(uri: &str) -> CacheKey {
+        CacheKey {
+            uri: uri.to_string(),
+            sorting: (None, None),
+            lang: "en".to_string(),
+            origin: "http://localhost".to_string(),
+            user: None,
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let cache = ListingCache::new(2);
+        cache.insert(key("/a"), dir.path(), "a".to_string());
+        cache.insert(key("/b"), dir.path(), "b".to_string());
+        assert_eq!(cache.get(&key("/a"), dir.path()).as_deref(), Some("a"));
+
+        cache.insert(key("/c"), dir.path(), "c".to_string());
+        assert_eq!(cache.get(&key("/a"), dir.path()).as_deref(), Some("a"));
+        assert_eq!(cache.get(&key("/b"), dir.path()), None);
+        assert_eq!(cache.get(&key("/c"), dir.path()).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn disabled_with_zero_capacity() {
+        let dir = TempDir::new().unwrap();
+        let cache = ListingCache::new(0);
+        cache.insert(key("/a"), dir.path(), "a".to_string());
+        assert_eq!(cache.get(&key("/a"), dir.path()), None);
+    }
+}
@@ -1,30 +1,82 @@
+use std::collections::HashMap;
 use std::io;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
 use std::thread;
 use std::time::Duration;
 use std::{io::Write, path::PathBuf};
 
+use actix_web::dev::Service;
 use actix_web::web;
+use actix_web::{guard, middleware, App, HttpRequest, HttpResponse};
 use actix_web::{
-    http::{header::ContentType, StatusCode},
+    http::{header::ContentType, ConnectionType, StatusCode, Version},
     Responder,
 };
-use actix_web::{middleware, App, HttpRequest, HttpResponse};
 use actix_web_httpauth::middleware::HttpAuthentication;
-use http::header::HeaderMap;
+use futures::future::{self, Either};
+use futures::TryFutureExt;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use ignore::gitignore::Gitignore;
 use log::{error, warn};
 use structopt::clap::crate_version;
 use structopt::StructOpt;
 use yansi::{Color, Paint};
 
+mod acme;
+mod allowed_hosts;
 mod archive;
+mod archive_cache;
+mod archive_pool;
 mod args;
 mod auth;
+mod cache_control;
+mod checksum;
+mod conditional;
+mod config_file;
+mod connection_limit;
+mod cors;
+mod csp;
+mod dir_size;
+mod duplicates;
 mod errors;
+mod extract;
+mod file_info;
 mod file_upload;
+mod force_download;
+mod geoip;
+mod i18n;
+mod ignore_files;
+mod interfaces;
 mod listing;
+mod listing_cache;
+mod mime_map;
+mod mirror;
+mod mounts;
+mod no_archive;
+mod path_policy;
+mod permissions;
 mod pipe;
+mod plain_http;
+mod precompressed;
+mod proxy;
+mod psk;
+mod public_ip;
+mod public_stats;
+mod publish;
+mod qrcode;
+mod random_route;
+mod ranges;
+mod rate_limit;
+mod reload;
+mod rename;
 mod renderer;
+mod security_headers;
+mod shutdown;
+mod slow_ops;
+mod suggestions;
+mod templates;
+mod tls;
+mod transfer;
 
 use crate::errors::ContextualError;
 
@@ -42,14 +94,69 @@ pub struct MiniserveConfig {
     /// Path to be served by miniserve
     pub path: std::path::PathBuf,
 
+    /// All paths to be served, if several are given
+    pub roots: Vec<PathBuf>,
+
+    /// Configuration serving each of `roots` under its name, if several are given
+    pub mounts: Vec<(String, MiniserveConfig)>,
+
     /// Port on which miniserve will be listening
     pub port: u16,
 
+    /// Unprivileged port to fall back to if binding to a privileged port is not permitted
+    pub fallback_port: Option<u16>,
+
+    /// TLS certificate chain and private key to serve HTTPS with
+    pub tls_files: Option<(PathBuf, PathBuf)>,
+
+    /// Port on which plain HTTP requests are redirected to HTTPS, if set
+    pub redirect_http_port: Option<u16>,
+
+    /// Enable serving HTTPS with a self-signed certificate generated at startup
+    pub tls_self_signed: bool,
+
+    /// If set, the TLS certificate is obtained from Let's Encrypt
+    pub acme: Option<acme::Acme>,
+
+    /// TLS configuration built from `tls_files`, `tls_self_signed` or `acme` at startup
+    pub tls: Option<rustls::ServerConfig>,
+
     /// IP address(es) on which miniserve will be available
     pub interfaces: Vec<IpAddr>,
 
-    /// Enable HTTP basic authentication
-    pub auth: Vec<auth::RequiredAuth>,
+    /// Names of the network interfaces on which miniserve will be available, whose addresses are
+    /// added to `interfaces` at startup
+    pub interface_names: Vec<String>,
+
+    /// Path to a MaxMind GeoIP database used to tag and filter clients by country
+    pub geoip_db: Option<PathBuf>,
+
+    /// Countries clients have to be located in, if not empty
+    pub allowed_countries: Vec<String>,
+
+    /// Countries clients must not be located in
+    pub denied_countries: Vec<String>,
+
+    /// Country filter built from `geoip_db` at startup
+    pub geoip: Option<geoip::GeoIpFilter>,
+
+    /// Origins whose scripts may fetch from miniserve, `*` for any, if not empty
+    pub cors_allowed_origins: Vec<String>,
+
+    /// Methods the allowed origins may use
+    pub cors_allowed_methods: Vec<String>,
+
+    /// Reverse proxies whose forwarding headers tell the real client and scheme, if not empty
+    pub trusted_proxies: Vec<proxy::TrustedProxy>,
+
+    /// Host names clients may address miniserve by, besides local ones, if not empty
+    pub allowed_hosts: Vec<String>,
+
+    /// If set, the metadata of every request is forwarded to this URL
+    pub mirror_requests: Option<String>,
+
+    /// If set, file downloads are encrypted with this pre-shared key
+    pub psk: Option<String>,
 
     /// If false, miniserve will serve the current working directory
     pub path_explicitly_chosen: bool,
@@ -57,23 +164,38 @@ pub struct MiniserveConfig {
     /// Enable symlink resolution
     pub no_symlinks: bool,
 
+    /// Hide entries matched by ignore files
+    pub ignore_files: bool,
+
+    /// How to handle requests for directories without a trailing slash
+    pub trailing_slash: listing::TrailingSlash,
+
     /// Show hidden files
     pub show_hidden: bool,
 
     /// Enable random route generation
     pub random_route: Option<String>,
 
+    /// File the random route is read from at startup, or saved to if it doesn't exist
+    pub random_route_file: Option<PathBuf>,
+
+    /// Number of characters of generated random routes
+    pub random_route_length: usize,
+
+    /// Characters generated random routes are made of
+    pub random_route_alphabet: String,
+
     /// Randomly generated favicon route
     pub favicon_route: String,
 
     /// Randomly generated css route
     pub css_route: String,
 
-    /// Default color scheme
-    pub default_color_scheme: String,
+    /// Authentication and theming, which are reloaded from `config_file` on SIGHUP
+    pub reloadable: reload::Reloadable,
 
-    /// Default dark mode color scheme
-    pub default_color_scheme_dark: String,
+    /// Config file of --config, if any
+    pub config_file: Option<PathBuf>,
 
     /// The name of a directory index file to serve, like "index.html"
     ///
@@ -81,12 +203,39 @@ pub struct MiniserveConfig {
     /// However, if a directory contains this file, miniserve will serve that file instead.
     pub index: Option<std::path::PathBuf>,
 
+    /// If enabled, directories containing an index.html are served as that file
+    pub index_per_dir: bool,
+
     /// Enable QR code display
     pub show_qrcode: bool,
 
+    /// If enabled, the served URL is opened in the default browser on startup
+    pub open_browser: bool,
+
+    /// Enable the info pages of files
+    pub show_file_info: bool,
+
+    /// If enabled, PDFs are shown inline and have a preview page
+    pub preview: bool,
+
+    /// If enabled, files can be downloaded compressed on the fly
+    pub compressed_downloads: bool,
+
+    /// If set, precompressed siblings of files are served to clients accepting their encoding
+    pub serve_precompressed: bool,
+
     /// Enable file upload
     pub file_upload: bool,
 
+    /// Enable renaming entries from the listing
+    pub allow_rename: bool,
+
+    /// Enable moving and copying entries from the listing
+    pub allow_move: bool,
+
+    /// Enable extracting archives on the server from the listing
+    pub allow_extract: bool,
+
     /// Enable upload to override existing files
     pub overwrite_files: bool,
 
@@ -99,24 +248,178 @@ pub struct MiniserveConfig {
     /// If false, creation of zip archives is disabled
     pub zip_enabled: bool,
 
+    /// If false, creation of 7z archives is disabled
+    pub seven_zip_enabled: bool,
+
     /// If enabled, directories are listed first
     pub dirs_first: bool,
 
+    /// Sorting method used when the visitor hasn't chosen one
+    pub default_sort: listing::SortingMethod,
+
+    /// If set, listings are split into pages of this many entries
+    pub page_size: Option<usize>,
+
+    /// If enabled, directories can be expanded in place in the listing
+    pub tree_view: bool,
+
+    /// Filesystem operations taking longer than this are logged, if set
+    pub slow_op_threshold: Option<Duration>,
+
+    /// If set, recursive directory sizes are shown, using this cache
+    pub dir_sizes: Option<dir_size::DirSizeCache>,
+
+    /// If enabled, mode bits, owner and group of entries are shown on unix
+    pub show_permissions: bool,
+
+    /// If enabled, a column shows the checksums of files and allows computing them
+    pub show_checksums: bool,
+
+    /// Checksums of files computed for the checksum column or the info pages
+    pub checksums: checksum::ChecksumCache,
+
+    /// Threads creating archives and compressed downloads
+    pub archive_pool: archive_pool::ArchivePool,
+
+    /// If set, each archive is sent at no more than this many bytes per second
+    pub archive_rate_limit: Option<u64>,
+
+    /// If set, archives whose files are larger than this many bytes in total are refused
+    pub max_archive_size: Option<u64>,
+
+    /// Patterns of entries left out of archives
+    pub archive_excludes: Vec<String>,
+
+    /// Directories which can't be downloaded as archives
+    pub no_archive: no_archive::NoArchive,
+
+    /// If set, archives of whole directories are cached on disk
+    pub archive_cache: Option<archive_cache::ArchiveCache>,
+
+    /// If set, rendered listings are served from this cache while their directory is unchanged
+    pub listing_cache: Option<listing_cache::ListingCache>,
+
+    /// If set, the staging directory can be published in place of the served directory
+    pub publisher: Option<publish::Publisher>,
+
+    /// If set, a stats page of the served directory is shown without authentication
+    pub public_stats: Option<public_stats::StatsCache>,
+
+    /// If set, a page lists the files with the same content
+    pub duplicates: Option<duplicates::DuplicatesCache>,
+
+    /// Directory with custom templates for the listing and error pages
+    pub template_dir: Option<PathBuf>,
+
+    /// Templates loaded from `template_dir` at startup
+    pub templates: Option<templates::Templates>,
+
+    /// If enabled, responses to HTTP/1.0 clients avoid chunked transfer encoding
+    pub legacy_http: bool,
+
+    /// Language of the interface, negotiated with each client if unset
+    pub lang: Option<i18n::Language>,
+
     /// Shown instead of host in page title and heading
     pub title: Option<String>,
 
+    /// HTML of the `--header-text` Markdown, shown above the listing
+    pub header_text: Option<String>,
+
     /// If specified, header will be added
     pub header: Vec<HeaderMap>,
 
+    /// Files of the custom error pages by their status
+    pub error_page_files: Vec<(StatusCode, PathBuf)>,
+
+    /// Custom error pages loaded from `error_page_files` at startup
+    pub error_pages: HashMap<StatusCode, String>,
+
+    /// Mappings of extensions to MIME types, single ones or files of them
+    pub mime_map_args: Vec<mime_map::MimeMapArg>,
+
+    /// MIME types of served files by their extension, loaded from `mime_map_args` at startup
+    pub mime_map: HashMap<String, HeaderValue>,
+
+    /// If set, files are served as attachments, all of them or the ones matching a glob
+    pub force_download: Option<Vec<Gitignore>>,
+
+    /// Seconds a client may take to send the head of its request, 0 for no limit
+    pub request_timeout: u64,
+
+    /// Seconds idle connections are kept open, 0 to disable keep-alive
+    pub keep_alive: usize,
+
+    /// Size of the buffer collecting uploaded data before it is written
+    pub client_body_buffer_size: usize,
+
+    /// Headers sent by `--security-headers`, if given
+    pub security_headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Content-Security-Policy of generated pages
+    pub csp: String,
+
+    /// Stands for the nonce of inline scripts in rendered pages, until it is replaced by a fresh
+    /// one in each response
+    pub nonce_placeholder: String,
+
+    /// If set, each file is sent at no more than this many bytes per second
+    pub rate_limit: Option<u64>,
+
+    /// If set, all files together are sent at no more than this rate
+    pub rate_limit_total: Option<rate_limit::TotalRateLimit>,
+
+    /// Limits of the requests handled at once, overall and per client, if any
+    pub connection_limits: Option<connection_limit::ConnectionLimits>,
+
+    /// If set, miniserve shuts down after this long
+    pub shutdown_after: Option<Duration>,
+
+    /// If set, miniserve shuts down once this many files have been downloaded
+    pub download_limit: Option<shutdown::DownloadLimit>,
+
+    /// Cache-Control values of served files, for all of them or the ones matching a glob
+    pub cache_control: Vec<cache_control::CacheControlRule>,
+
     /// If enabled, version footer is hidden
     pub hide_version_footer: bool,
+
+    /// strftime format of modification dates, replacing the default date and time
+    pub time_format: Option<String>,
+
+    /// If enabled, modification dates are shown in local time instead of UTC
+    pub local_time: bool,
+
+    /// If enabled, the 404 page links to a similarly named entry if there is one
+    pub suggest_on_404: bool,
+
+    /// If enabled, the configuration is checked without starting the server
+    pub check: bool,
+
+    /// If set, the URL miniserve is likely reachable at from the internet is printed, with the
+    /// public IP address discovered from this source
+    pub public_ip_source: Option<public_ip::PublicIpSource>,
 }
 
 impl MiniserveConfig {
     /// Parses the command line arguments
     fn from_args(args: args::CliArgs) -> Self {
+        let interface_names = args
+            .interfaces
+            .iter()
+            .filter_map(|interface| match interface {
+                interfaces::InterfaceArg::Name(name) => Some(name.clone()),
+                interfaces::InterfaceArg::Address(_) => None,
+            })
+            .collect();
         let interfaces = if !args.interfaces.is_empty() {
             args.interfaces
+                .iter()
+                .filter_map(|interface| match interface {
+                    interfaces::InterfaceArg::Address(address) => Some(*address),
+                    interfaces::InterfaceArg::Name(_) => None,
+                })
+                .collect()
         } else {
             vec![
                 IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)),
@@ -125,7 +428,10 @@ impl MiniserveConfig {
         };
 
         let random_route = if args.random_route {
-            Some(nanoid::nanoid!(6, &ROUTE_ALPHABET))
+            Some(random_route::generate(
+                args.random_route_length,
+                &args.random_route_alphabet,
+            ))
         } else {
             None
         };
@@ -135,10 +441,30 @@ impl MiniserveConfig {
         let favicon_route = nanoid::nanoid!(10, &ROUTE_ALPHABET);
         let css_route = nanoid::nanoid!(10, &ROUTE_ALPHABET);
 
-        let default_color_scheme = args.color_scheme;
-        let default_color_scheme_dark = args.color_scheme_dark;
+        let reloadable = reload::Reloadable::new(reload::Settings::from_args(&args));
+
+        let path_explicitly_chosen = !args.path.is_empty() || args.index.is_some();
+        let path = args
+            .path
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let no_archive = no_archive::NoArchive::new(&path, &args.no_archive_path);
+
+        // The page for missing files takes precedence over the ones of `--error-page`
+        let mut error_page_files = args.error_page;
+        if let Some(error_page_404) = args.error_page_404 {
+            error_page_files.push((StatusCode::NOT_FOUND, error_page_404));
+        }
 
-        let path_explicitly_chosen = args.path.is_some() || args.index.is_some();
+        let cors_allowed_methods = if args.cors_allow_methods.is_empty() {
+            cors::DEFAULT_METHODS
+                .iter()
+                .map(|method| method.to_string())
+                .collect()
+        } else {
+            args.cors_allow_methods
+        };
 
         let port = match args.port {
             0 => port_check::free_local_port().expect("no free ports available"),
@@ -147,35 +473,180 @@ impl MiniserveConfig {
 
         crate::MiniserveConfig {
             verbose: args.verbose,
-            path: args.path.unwrap_or_else(|| PathBuf::from(".")),
+            path,
+            roots: args.path,
+            mounts: vec![],
             port,
+            fallback_port: args.fallback_port,
+            tls_files: args.tls_cert.zip(args.tls_key),
+            tls_self_signed: args.tls_self_signed,
+            redirect_http_port: args.redirect_http_port,
+            acme: args
+                .acme_domain
+                .zip(args.acme_email)
+                .map(|(domain, email)| acme::Acme::new(domain, email, args.acme_cache_dir)),
+            tls: None,
             interfaces,
-            auth: args.auth,
+            interface_names,
+            geoip_db: args.geoip_db,
+            allowed_countries: args.allow_country,
+            denied_countries: args.deny_country,
+            geoip: None,
+            cors_allowed_origins: args.cors_allow_origin,
+            cors_allowed_methods,
+            trusted_proxies: args.trusted_proxies,
+            allowed_hosts: args.allowed_hosts,
+            mirror_requests: args.mirror_requests,
+            psk: args.psk,
             path_explicitly_chosen,
             no_symlinks: args.no_symlinks,
+            ignore_files: args.ignore_files,
+            trailing_slash: args.trailing_slash,
             show_hidden: args.hidden,
             random_route,
+            random_route_file: args.random_route_file,
+            random_route_length: args.random_route_length,
+            random_route_alphabet: args.random_route_alphabet,
             favicon_route,
             css_route,
-            default_color_scheme,
-            default_color_scheme_dark,
+            reloadable,
+            config_file: args.config,
             index: args.index,
+            index_per_dir: args.index_per_dir,
             overwrite_files: args.overwrite_files,
             show_qrcode: args.qrcode,
+            open_browser: args.open,
+            show_file_info: args.file_info,
+            preview: args.preview,
+            compressed_downloads: args.compressed_downloads,
+            serve_precompressed: args.serve_precompressed,
             file_upload: args.file_upload,
+            allow_rename: args.allow_rename,
+            allow_move: args.allow_move,
+            allow_extract: args.allow_extract,
             tar_enabled: args.enable_tar,
             tar_gz_enabled: args.enable_tar_gz,
             zip_enabled: args.enable_zip,
+            seven_zip_enabled: args.enable_7z,
             dirs_first: args.dirs_first,
+            default_sort: args.default_sort,
+            page_size: args.page_size,
+            tree_view: args.tree_view,
+            slow_op_threshold: args.slow_op_threshold.map(Duration::from_millis),
+            dir_sizes: if args.show_dir_sizes {
+                Some(dir_size::DirSizeCache::default())
+            } else {
+                None
+            },
+            show_permissions: cfg!(unix) && args.show_permissions,
+            show_checksums: args.show_checksums,
+            checksums: checksum::ChecksumCache::default(),
+            listing_cache: args.listing_cache.map(listing_cache::ListingCache::new),
+            archive_cache: args.archive_cache_dir.map(|dir| {
+                archive_cache::ArchiveCache::new(
+                    dir,
+                    args.archive_cache_size * 1024 * 1024,
+                    args.archive_exclude
+                        .iter()
+                        .chain(&args.no_archive_path)
+                        .cloned()
+                        .collect(),
+                )
+            }),
+            archive_excludes: args.archive_exclude,
+            no_archive,
+            archive_pool: archive_pool::ArchivePool::new(args.archive_threads),
+            archive_rate_limit: args.archive_rate_limit.map(|rate| rate * 1024),
+            max_archive_size: args.max_archive_size.map(|size| size * 1024 * 1024),
+            publisher: args
+                .publish_from
+                .zip(args.publish_token)
+                .map(|(staging, token)| publish::Publisher::new(staging, &token)),
+            public_stats: if args.public_stats {
+                Some(public_stats::StatsCache::default())
+            } else {
+                None
+            },
+            duplicates: if args.show_duplicates {
+                Some(duplicates::DuplicatesCache::default())
+            } else {
+                None
+            },
+            template_dir: args.template,
+            templates: None,
+            legacy_http: args.legacy_http,
+            lang: args.lang,
             title: args.title,
+            header_text: args.header_text.as_deref().map(renderer::markdown_to_html),
             header: args.header,
+            error_page_files,
+            error_pages: HashMap::new(),
+            mime_map_args: args.mime_map,
+            mime_map: HashMap::new(),
+            force_download: args.force_download,
+            request_timeout: args.request_timeout,
+            keep_alive: args.keep_alive,
+            client_body_buffer_size: args.client_body_buffer_size,
+            security_headers: args
+                .security_headers
+                .map(|args| security_headers::headers(&args))
+                .unwrap_or_default(),
+            csp: args.csp,
+            nonce_placeholder: nanoid::nanoid!(),
+            rate_limit: args.rate_limit,
+            rate_limit_total: args.rate_limit_total.map(rate_limit::TotalRateLimit::new),
+            connection_limits: connection_limit::ConnectionLimits::new(
+                args.max_connections,
+                args.max_connections_per_ip,
+            ),
+            shutdown_after: args.shutdown_after,
+            download_limit: args
+                .shutdown_after_downloads
+                .map(shutdown::DownloadLimit::new),
+            cache_control: args.cache_control,
             hide_version_footer: args.hide_version_footer,
+            time_format: args.time_format,
+            local_time: args.local && !args.utc,
+            suggest_on_404: args.suggest_on_404,
+            check: args.check,
+            public_ip_source: args.show_public_url.map(Option::unwrap_or_default),
         }
     }
+
+    /// Returns the configuration serving `request_path`, which is the one of its directory when
+    /// serving several
+    pub fn for_path(&self, request_path: &str) -> &MiniserveConfig {
+        self.mounts
+            .iter()
+            .map(|(_, mount)| mount)
+            .find(|mount| {
+                let route = format!("/{}", mount.random_route.clone().unwrap_or_default());
+                request_path == route || request_path.starts_with(&format!("{}/", route))
+            })
+            .unwrap_or(self)
+    }
 }
 
 fn main() {
-    let args = args::CliArgs::from_args();
+    let cli_args = match config_file::load_args(std::env::args_os().collect()) {
+        Ok(cli_args) => cli_args,
+        Err(e) => e.exit(),
+    };
+    let args = match args::CliArgs::from_iter_safe(&cli_args) {
+        Ok(args) => args,
+        Err(e) => e.exit(),
+    };
+
+    if let Some(format) = args.print_config {
+        if let Err(e) = config_file::print_config(cli_args, format) {
+            structopt::clap::Error::with_description(
+                &e.to_string(),
+                structopt::clap::ErrorKind::Io,
+            )
+            .exit();
+        }
+        return;
+    }
 
     if let Some(shell) = args.print_completions {
         args::CliArgs::clap().gen_completions_to("miniserve", shell, &mut std::io::stdout());
@@ -186,12 +657,15 @@ fn main() {
 
     match run(miniserve_config) {
         Ok(()) => (),
-        Err(e) => errors::log_error_chain(e.to_string()),
+        Err(e) => {
+            errors::log_error_chain(e.to_string());
+            std::process::exit(1);
+        }
     }
 }
 
 #[actix_web::main(miniserve)]
-async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
+async fn run(mut miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
     if cfg!(windows) && !Paint::enable_windows_ascii() {
         Paint::disable();
     }
@@ -231,6 +705,102 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         }
     }
 
+    if let Some(geoip_db) = &miniserve_config.geoip_db {
+        miniserve_config.geoip = Some(geoip::GeoIpFilter::open(
+            geoip_db,
+            miniserve_config.allowed_countries.clone(),
+            miniserve_config.denied_countries.clone(),
+        )?);
+    }
+
+    if let Some((cert, key)) = &miniserve_config.tls_files {
+        miniserve_config.tls = Some(tls::load_config(cert, key)?);
+    }
+
+    let mut tls_fingerprint = None;
+    if miniserve_config.tls_self_signed {
+        let (config, fingerprint) = tls::self_signed_config()?;
+        miniserve_config.tls = Some(config);
+        tls_fingerprint = Some(fingerprint);
+    }
+
+    if let Some(acme) = &miniserve_config.acme {
+        miniserve_config.tls = Some(acme.server_config());
+    }
+
+    if miniserve_config.redirect_http_port.is_some() && miniserve_config.tls.is_none() {
+        return Err(ContextualError::RedirectWithoutTlsError);
+    }
+
+    miniserve_config.error_pages = errors::load_error_pages(&miniserve_config.error_page_files)?;
+
+    miniserve_config.mime_map = mime_map::load(&miniserve_config.mime_map_args)?;
+
+    if let Some(template_dir) = &miniserve_config.template_dir {
+        miniserve_config.templates = Some(templates::Templates::load(template_dir)?);
+    }
+
+    if let Some(random_route_file) = &miniserve_config.random_route_file {
+        miniserve_config.random_route = Some(random_route::load_or_create(
+            random_route_file,
+            miniserve_config.random_route_length,
+            &miniserve_config.random_route_alphabet,
+        )?);
+    }
+
+    miniserve_config.mounts = mounts::mount_configs(&miniserve_config)?;
+
+    let named_interfaces = interfaces::resolve(&miniserve_config.interface_names)?;
+    for (_, addresses) in &named_interfaces {
+        for address in addresses {
+            if !miniserve_config.interfaces.contains(address) {
+                miniserve_config.interfaces.push(*address);
+            }
+        }
+    }
+
+    miniserve_config.port = check_bind_capability(
+        &miniserve_config.interfaces,
+        miniserve_config.port,
+        miniserve_config.fallback_port,
+    )?;
+
+    if miniserve_config.check {
+        check_config(&miniserve_config)?;
+        println!("The configuration is valid");
+        return Ok(());
+    }
+
+    // Let's Encrypt sends its challenges to port 80, which may also redirect to HTTPS
+    let mut plain_http_ports = Vec::new();
+    if miniserve_config.acme.is_some() {
+        plain_http_ports.push(acme::CHALLENGE_PORT);
+    }
+    if let Some(port) = miniserve_config.redirect_http_port {
+        if !plain_http_ports.contains(&port) {
+            plain_http_ports.push(port);
+        }
+    }
+    let https_port = miniserve_config
+        .redirect_http_port
+        .map(|_| miniserve_config.port);
+    for port in plain_http_ports {
+        plain_http::start(
+            &miniserve_config.interfaces,
+            port,
+            miniserve_config.acme.clone(),
+            https_port,
+        )?;
+    }
+    if let Some(acme) = &miniserve_config.acme {
+        acme.spawn_renewal();
+    }
+    if miniserve_config.config_file.is_some() {
+        miniserve_config
+            .reloadable
+            .spawn_on_hangup(miniserve_config.listing_cache.clone());
+    }
+
     let inside_config = miniserve_config.clone();
 
     let interfaces = miniserve_config
@@ -263,7 +833,16 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
             );
         }
     }
-    let path_string = canon_path.to_string_lossy();
+    let path_string = if miniserve_config.mounts.is_empty() {
+        canon_path.to_string_lossy().to_string()
+    } else {
+        miniserve_config
+            .roots
+            .iter()
+            .map(|root| root.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
 
     println!(
         "{name} v{version}",
@@ -295,6 +874,23 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
             thread::sleep(Duration::from_millis(500));
         }
     }
+    for (name, addresses) in &named_interfaces {
+        let addresses = addresses
+            .iter()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "Interface {} has the addresses {}",
+            Color::Yellow.paint(name).bold(),
+            addresses
+        );
+    }
+    let scheme = if miniserve_config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
     let mut addresses = String::new();
     for interface in &interfaces {
         if !addresses.is_empty() {
@@ -304,7 +900,8 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
             "{}",
             Color::Green
                 .paint(format!(
-                    "http://{interface}:{port}",
+                    "{scheme}://{interface}:{port}",
+                    scheme = scheme,
                     interface = &interface,
                     port = miniserve_config.port
                 ))
@@ -346,15 +943,145 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         }
     };
 
-    let srv = actix_web::HttpServer::new(move || {
+    let server = actix_web::HttpServer::new(move || {
         App::new()
+            // Innermost, so that precompressed files get the custom headers and require auth
+            .wrap_fn(|req, srv| match precompressed::serve(&req) {
+                Some(res) => Either::Left(future::ok(req.into_response(res))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap_fn(|mut req, srv| match ranges::check_request(&mut req) {
+                Some(res) => Either::Left(future::ok(req.into_response(res))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap_fn(|req, srv| {
+                let limited = rate_limit::is_limited(&req);
+                srv.call(req)
+                    .map_ok(move |res| rate_limit::apply(res, limited))
+            })
+            .wrap_fn(|req, srv| {
+                let download = shutdown::is_download(&req);
+                srv.call(req)
+                    .map_ok(move |res| shutdown::count(res, download))
+            })
+            .wrap_fn(|req, srv| {
+                let content_type = mime_map::content_type(&req);
+                srv.call(req)
+                    .map_ok(move |res| mime_map::apply(res, content_type))
+            })
+            .wrap_fn(|req, srv| {
+                let cache_control = cache_control::for_request(&req);
+                srv.call(req)
+                    .map_ok(move |res| cache_control::apply(res, cache_control))
+            })
             .wrap(configure_header(&inside_config.clone()))
             .app_data(inside_config.clone())
             .wrap(middleware::Condition::new(
-                !inside_config.auth.is_empty(),
+                !inside_config.reloadable.get().auth.is_empty(),
                 HttpAuthentication::basic(auth::handle_auth),
             ))
-            .wrap(middleware::Logger::default())
+            .wrap_fn(|req, srv| {
+                if let Some(res) = cors::preflight(&req) {
+                    return Either::Left(future::ok(req.into_response(res)));
+                }
+                let origin = cors::allowed_origin(&req);
+                Either::Right(srv.call(req).map_ok(move |res| cors::apply(res, origin)))
+            })
+            .wrap_fn(|req, srv| {
+                let preview = req
+                    .app_data::<MiniserveConfig>()
+                    .map_or(false, |conf| conf.preview);
+                srv.call(req).map_ok(move |res| {
+                    if preview {
+                        file_info::inline_pdf(res)
+                    } else {
+                        res
+                    }
+                })
+            })
+            .wrap_fn(|req, srv| {
+                let forced = force_download::is_forced(&req);
+                srv.call(req)
+                    .map_ok(move |res| force_download::apply(res, forced))
+            })
+            .wrap_fn(|mut req, srv| {
+                let key = req
+                    .app_data::<MiniserveConfig>()
+                    .and_then(|conf| conf.psk.clone());
+                if key.is_some() {
+                    // Encrypted bodies can't be served partially
+                    req.headers_mut().remove(actix_web::http::header::RANGE);
+                }
+                srv.call(req).and_then(move |res| async move {
+                    Ok::<_, actix_web::Error>(match key {
+                        Some(key) => psk::encrypt_response(res, key).await,
+                        None => res,
+                    })
+                })
+            })
+            .wrap_fn(|req, srv| {
+                if public_stats::is_stats_request(&req) {
+                    Either::Left(public_stats::stats_page(req))
+                } else {
+                    Either::Right(srv.call(req))
+                }
+            })
+            .wrap_fn(|req, srv| match ignore_files::check_request(&req) {
+                Some(res) => Either::Left(future::ok(req.into_response(res))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap_fn(|req, srv| match geoip::check_request(&req) {
+                Some(res) => Either::Left(future::ok(req.into_response(res))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap_fn(|req, srv| match allowed_hosts::check_request(&req) {
+                Some(res) => Either::Left(future::ok(req.into_response(res))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap_fn(|req, srv| {
+                mirror::mirror_request(&req);
+                srv.call(req)
+            })
+            .wrap_fn(|req, srv| {
+                let legacy_http = req.version() == Version::HTTP_10
+                    && req
+                        .app_data::<MiniserveConfig>()
+                        .map_or(false, |conf| conf.legacy_http);
+                srv.call(req).map_ok(move |mut res| {
+                    if legacy_http {
+                        // HTTP/1.0 clients don't know chunked bodies, so closing the
+                        // connection has to signal the end of the body
+                        let head = res.response_mut().head_mut();
+                        head.no_chunking(true);
+                        head.set_connection_type(ConnectionType::Close);
+                    }
+                    res
+                })
+            })
+            .wrap_fn(|req, srv| match connection_limit::check_request(&req) {
+                Ok(guard) => Either::Left(
+                    srv.call(req)
+                        .map_ok(move |res| connection_limit::hold(res, guard)),
+                ),
+                Err(res) => Either::Right(future::ok(req.into_response(res))),
+            })
+            .wrap_fn(|req, srv| srv.call(req).map_ok(csp::apply))
+            .wrap_fn(|req, srv| {
+                let headers = security_headers::for_request(&req);
+                srv.call(req)
+                    .map_ok(move |res| security_headers::apply(res, headers))
+            })
+            .wrap_fn(|req, srv| srv.call(req).map_ok(errors::custom_error_page))
+            .wrap(if inside_config.trusted_proxies.is_empty() {
+                middleware::Logger::default()
+            } else {
+                // The forwarding headers are sanitized before, so the real IP can be trusted
+                middleware::Logger::new(r#"%{r}a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T"#)
+            })
+            .wrap_fn(|mut req, srv| {
+                proxy::sanitize_request(&mut req);
+                srv.call(req)
+            })
             .route(
                 &format!("/{}", inside_config.favicon_route),
                 web::get().to(favicon),
@@ -362,18 +1089,106 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
             .route(&format!("/{}", inside_config.css_route), web::get().to(css))
             .configure(|c| configure_app(c, &inside_config))
             .default_service(web::get().to(error_404))
-    })
-    .bind(socket_addresses.as_slice())
+    });
+    let server = server
+        .client_timeout(miniserve_config.request_timeout * 1000)
+        .keep_alive(Some(miniserve_config.keep_alive).filter(|seconds| *seconds > 0));
+    let srv = match miniserve_config.tls.clone() {
+        Some(tls) => server.bind_rustls(socket_addresses.as_slice(), tls),
+        None => server.bind(socket_addresses.as_slice()),
+    }
     .map_err(|e| ContextualError::IoError("Failed to bind server".to_string(), e))?
-    .shutdown_timeout(0)
+    .shutdown_timeout(
+        if miniserve_config.shutdown_after.is_some() || miniserve_config.download_limit.is_some() {
+            shutdown::GRACE_PERIOD
+        } else {
+            0
+        },
+    )
     .run();
+    shutdown::schedule(&srv, &miniserve_config);
 
     println!(
-        "Serving path {path} at {addresses}",
+        "Serving {paths} {path} at {addresses}",
+        paths = if miniserve_config.mounts.is_empty() {
+            "path"
+        } else {
+            "paths"
+        },
         path = Color::Yellow.paint(path_string).bold(),
         addresses = addresses,
     );
 
+    let route = miniserve_config
+        .random_route
+        .as_ref()
+        .map(|random_route| format!("/{}", random_route))
+        .unwrap_or_default();
+
+    if let Some(source) = &miniserve_config.public_ip_source {
+        match public_ip::discover(source).await {
+            Ok(public_ip) => println!(
+                "Likely reachable from the internet at {}, if port {} is forwarded to this host",
+                Color::Green
+                    .paint(format!(
+                        "{}://{}{}",
+                        scheme,
+                        SocketAddr::new(public_ip, miniserve_config.port),
+                        route
+                    ))
+                    .bold(),
+                miniserve_config.port
+            ),
+            Err(e) => warn!("{}", e.to_string().replace('\n', ": ")),
+        }
+    }
+
+    // Codes are only useful to scan right away, not in logs
+    if miniserve_config.show_qrcode && atty::is(atty::Stream::Stdout) {
+        for address in interfaces::reachable(&miniserve_config.interfaces) {
+            let url = format!(
+                "{}://{}{}",
+                scheme,
+                SocketAddr::new(address, miniserve_config.port),
+                route
+            );
+            if let Some(qr_code) = qrcode::to_terminal(&url) {
+                println!("\n{}\n{}", Color::Green.paint(&url).bold(), qr_code);
+            }
+        }
+    }
+
+    if miniserve_config.open_browser {
+        if let Some(address) = interfaces::local(&miniserve_config.interfaces) {
+            let url = format!(
+                "{}://{}{}",
+                scheme,
+                SocketAddr::new(address, miniserve_config.port),
+                route
+            );
+            // Some browsers only return once they are closed
+            thread::spawn(move || {
+                if let Err(e) = webbrowser::open(&url) {
+                    warn!("Failed to open {} in the browser: {}", url, e);
+                }
+            });
+        }
+    }
+
+    if let Some(fingerprint) = tls_fingerprint {
+        println!(
+            "The self-signed certificate has the SHA-256 fingerprint {}",
+            Color::Cyan.paint(fingerprint).bold()
+        );
+    }
+
+    if miniserve_config.psk.is_some() {
+        println!(
+            "File downloads are encrypted with the pre-shared key, decrypt them with age, e.g.:\n    {}",
+            Color::Cyan.paint("curl <file URL> | age -d > <file>").bold()
+        );
+    }
+
     if atty::is(atty::Stream::Stdout) {
         println!("\nQuit by pressing CTRL-C");
     }
@@ -382,6 +1197,101 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         .map_err(|e| ContextualError::IoError("".to_owned(), e))
 }
 
+/// Checks what `--check` promises beyond the loading done at startup: that the paths to serve
+/// and their index file exist, and that the interfaces can be bound.
+///
+/// The interfaces are bound to any free port, so that a running miniserve doesn't fail the
+/// check of its replacement.
+fn check_config(conf: &MiniserveConfig) -> Result<(), ContextualError> {
+    let served = if conf.mounts.is_empty() {
+        vec![conf]
+    } else {
+        conf.mounts.iter().map(|(_, mount)| mount).collect()
+    };
+    for served in served {
+        let path = served.path.canonicalize().map_err(|e| {
+            ContextualError::IoError(
+                format!(
+                    "Failed to resolve path {} to be served",
+                    served.path.display()
+                ),
+                e,
+            )
+        })?;
+        if let Some(index) = &served.index {
+            if !path.join(index).exists() {
+                return Err(ContextualError::MissingIndexFileError(
+                    index.to_string_lossy().to_string(),
+                ));
+            }
+        }
+    }
+
+    for &interface in &conf.interfaces {
+        TcpListener::bind(SocketAddr::new(interface, 0))
+            .map_err(|e| ContextualError::IoError(format!("Failed to bind to {}", interface), e))?;
+    }
+
+    Ok(())
+}
+
+/// Checks whether miniserve is permitted to bind to `port` on all of the given interfaces.
+///
+/// Binding to a privileged port (<1024) usually requires elevated permissions. If we lack those,
+/// we fall back to `fallback_port` (if set) or return an error explaining how to fix it.
+/// Returns the port that should actually be used.
+fn check_bind_capability(
+    interfaces: &[IpAddr],
+    port: u16,
+    fallback_port: Option<u16>,
+) -> Result<u16, ContextualError> {
+    if port >= 1024 {
+        return Ok(port);
+    }
+
+    for &interface in interfaces {
+        // Any other error (e.g. the address being in use) will be reported properly once the
+        // server actually binds.
+        if let Err(e) = TcpListener::bind(SocketAddr::new(interface, port)) {
+            if e.kind() != io::ErrorKind::PermissionDenied {
+                continue;
+            }
+            if let Some(fallback_port) = fallback_port {
+                warn!(
+                    "Not permitted to bind to privileged port {}, falling back to port {}",
+                    port, fallback_port
+                );
+                return Ok(fallback_port);
+            }
+            return Err(ContextualError::PrivilegedPortError(
+                port,
+                privileged_port_hint(),
+            ));
+        }
+    }
+
+    Ok(port)
+}
+
+/// Suggests how to allow miniserve to bind to a privileged port on the current platform
+fn privileged_port_hint() -> String {
+    let alternatives = "choose a port above 1023 or use --fallback-port to fall back to one";
+    if cfg!(target_os = "linux") {
+        let exe = std::env::current_exe()
+            .map(|exe| exe.display().to_string())
+            .unwrap_or_else(|_| "/path/to/miniserve".to_string());
+        format!(
+            "Either run `sudo setcap 'cap_net_bind_service=+ep' {}`, or {}",
+            exe, alternatives
+        )
+    } else {
+        format!(
+            "Either run miniserve with elevated privileges, or {}",
+            alternatives
+        )
+    }
+}
+
 fn configure_header(conf: &MiniserveConfig) -> middleware::DefaultHeaders {
     let headers = conf.clone().header;
 
@@ -402,29 +1312,45 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
     let uses_random_route = conf.random_route.clone().is_some();
     let full_route = format!("/{}", random_route);
 
+    if !conf.mounts.is_empty() {
+        // Each directory is served by its own configuration, below the page listing them
+        app.route(&full_route, web::get().to(mounts::mounts_page));
+        if full_route != "/" {
+            app.route(
+                &format!("{}/", full_route),
+                web::get().to(mounts::mounts_page),
+            );
+        }
+        for (_, mount) in &conf.mounts {
+            let mount = mount.clone();
+            let route = format!("/{}", mount.random_route.clone().unwrap_or_default());
+            app.service(
+                web::scope("")
+                    .guard(guard::fn_guard(move |head| {
+                        let path = head.uri.path();
+                        path == route || path.starts_with(&format!("{}/", route))
+                    }))
+                    .app_data(mount.clone())
+                    .configure(|c| configure_app(c, &mount)),
+            );
+        }
+        return;
+    }
+
     let upload_route;
+    let download_route;
     let serve_path = {
         let path = &conf.path;
-        let no_symlinks = conf.no_symlinks;
-        let show_hidden = conf.show_hidden;
-        let random_route = conf.random_route.clone();
-        let favicon_route = conf.favicon_route.clone();
-        let css_route = conf.css_route.clone();
-        let default_color_scheme = conf.default_color_scheme.clone();
-        let default_color_scheme_dark = conf.default_color_scheme_dark.clone();
-        let show_qrcode = conf.show_qrcode;
-        let file_upload = conf.file_upload;
-        let tar_enabled = conf.tar_enabled;
-        let tar_gz_enabled = conf.tar_gz_enabled;
-        let zip_enabled = conf.zip_enabled;
-        let dirs_first = conf.dirs_first;
-        let hide_version_footer = conf.hide_version_footer;
-        let title = conf.title.clone();
         upload_route = if let Some(random_route) = conf.random_route.clone() {
             format!("/{}/upload", random_route)
         } else {
             "/upload".to_string()
         };
+        download_route = if let Some(random_route) = conf.random_route.clone() {
+            format!("/{}/download", random_route)
+        } else {
+            "/download".to_string()
+        };
         if path.is_file() {
             None
         } else if let Some(index_file) = &conf.index {
@@ -432,9 +1358,8 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
                 actix_files::Files::new(&full_route, path).index_file(index_file.to_string_lossy()),
             )
         } else {
-            let u_r = upload_route.clone();
             let files;
-            if show_hidden {
+            if conf.show_hidden {
                 files = actix_files::Files::new(&full_route, path)
                     .show_files_listing()
                     .use_hidden_files();
@@ -443,28 +1368,7 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
             }
 
             let files = files
-                .files_listing_renderer(move |dir, req| {
-                    listing::directory_listing(
-                        dir,
-                        req,
-                        no_symlinks,
-                        show_hidden,
-                        file_upload,
-                        random_route.clone(),
-                        favicon_route.clone(),
-                        css_route.clone(),
-                        &default_color_scheme,
-                        &default_color_scheme_dark,
-                        show_qrcode,
-                        u_r.clone(),
-                        tar_enabled,
-                        tar_gz_enabled,
-                        zip_enabled,
-                        dirs_first,
-                        hide_version_footer,
-                        title.clone(),
-                    )
-                })
+                .files_listing_renderer(listing::directory_listing)
                 .prefer_utf8(true)
                 .default_handler(web::to(error_404));
             Some(files)
@@ -474,11 +1378,100 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
     let favicon_route = conf.favicon_route.clone();
     let css_route = conf.css_route.clone();
 
-    let default_color_scheme = conf.default_color_scheme.clone();
-    let default_color_scheme_dark = conf.default_color_scheme_dark.clone();
     let hide_version_footer = conf.hide_version_footer;
 
+    if conf.show_qrcode {
+        // Serve QR codes of single files, registered before the files service shadows the route
+        app.route(
+            &format!("{}/__miniserve/qr", full_route.trim_end_matches('/')),
+            web::get().to(listing::file_qr_code),
+        );
+    }
+
+    if conf.publisher.is_some() {
+        app.route(
+            &format!("{}/__miniserve/publish", full_route.trim_end_matches('/')),
+            web::post().to(publish::publish),
+        );
+    }
+
+    if conf.duplicates.is_some() {
+        app.route(
+            &format!(
+                "{}/__miniserve/duplicates",
+                full_route.trim_end_matches('/')
+            ),
+            web::get().to(duplicates::duplicates),
+        );
+    }
+
+    if conf.show_checksums {
+        app.route(
+            &format!("{}/__miniserve/checksum", full_route.trim_end_matches('/')),
+            web::get().to(file_info::file_checksum),
+        );
+    }
+
+    if conf.show_file_info {
+        app.route(
+            &format!("{}/__miniserve/info", full_route.trim_end_matches('/')),
+            web::get().to(file_info::file_info),
+        );
+    }
+
+    if conf.preview {
+        app.route(
+            &format!("{}/__miniserve/preview", full_route.trim_end_matches('/')),
+            web::get().to(file_info::file_preview),
+        );
+    }
+
+    if conf.compressed_downloads {
+        app.route(
+            &format!(
+                "{}/__miniserve/compressed",
+                full_route.trim_end_matches('/')
+            ),
+            web::get().to(file_info::compressed_download),
+        );
+    }
+
+    if conf.file_upload && serve_path.is_some() {
+        app.route(
+            &format!(
+                "{}/__miniserve/create-file",
+                full_route.trim_end_matches('/')
+            ),
+            web::post().to(file_upload::create_file),
+        );
+    }
+
+    if conf.allow_rename {
+        app.route(
+            &format!("{}/__miniserve/rename", full_route.trim_end_matches('/')),
+            web::post().to(rename::rename),
+        );
+    }
+
+    if conf.allow_move {
+        app.route(
+            &format!("{}/__miniserve/transfer", full_route.trim_end_matches('/')),
+            web::post().to(transfer::transfer_selection),
+        );
+    }
+
+    if conf.allow_extract {
+        app.route(
+            &format!("{}/__miniserve/extract", full_route.trim_end_matches('/')),
+            web::post().to(extract::extract),
+        );
+    }
+
     if let Some(serve_path) = serve_path {
+        if conf.tar_enabled || conf.tar_gz_enabled || conf.zip_enabled || conf.seven_zip_enabled {
+            // Allow downloading a selection of entries as archive
+            app.route(&download_route, web::post().to(listing::download_selection));
+        }
         if conf.file_upload {
             // Allow file upload
             app.service(
@@ -489,8 +1482,6 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
                         uses_random_route,
                         favicon_route.clone(),
                         css_route.clone(),
-                        &default_color_scheme,
-                        &default_color_scheme_dark,
                         hide_version_footer,
                     )
                 })),
@@ -514,11 +1505,16 @@ async fn error_404(req: HttpRequest) -> HttpResponse {
     let favicon_route = conf.favicon_route.clone();
     let css_route = conf.css_route.clone();
     let query_params = listing::extract_query_parameters(&req);
+    let suggestion = if conf.suggest_on_404 {
+        suggestions::suggest(&req)
+    } else {
+        None
+    };
 
     errors::log_error_chain(err_404.to_string());
 
     actix_web::HttpResponse::NotFound().body(
-        renderer::render_error(
+        renderer::render_error_with_suggestion(
             &err_404.to_string(),
             StatusCode::NOT_FOUND,
             "/",
@@ -528,9 +1524,12 @@ async fn error_404(req: HttpRequest) -> HttpResponse {
             !uses_random_route,
             &favicon_route,
             &css_route,
-            &conf.default_color_scheme,
-            &conf.default_color_scheme_dark,
+            &conf.reloadable.get().default_color_scheme,
+            &conf.reloadable.get().default_color_scheme_dark,
             conf.hide_version_footer,
+            conf.templates.as_ref(),
+            i18n::Language::negotiate(req.headers(), conf.lang),
+            suggestion.as_ref(),
         )
         .into_string(),
     )
@@ -0,0 +1,108 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, HeaderValue, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::errors::ContextualError;
+use crate::path_policy;
+
+/// A `--mime-map` value, either a single mapping like `wasm=application/wasm` or a file of
+/// such mappings
+#[derive(Clone, Debug)]
+pub enum MimeMapArg {
+    Entry(String, HeaderValue),
+    File(PathBuf),
+}
+
+/// Parses a mapping of an extension to a MIME type, like `wasm=application/wasm` or
+/// `.gcode = text/x-gcode`
+fn parse_entry(src: &str) -> Result<(String, HeaderValue), ContextualError> {
+    let parse_error = |cause: String| ContextualError::ParseError("MIME map".to_string(), cause);
+
+    let (extension, mime) = src
+        .split_once('=')
+        .ok_or_else(|| parse_error(format!("{} is not of the form ext=type", src)))?;
+    let extension = extension.trim().trim_start_matches('.').to_lowercase();
+    if extension.is_empty() {
+        return Err(parse_error(format!("{} has no extension", src)));
+    }
+    let mime = mime
+        .trim()
+        .parse::<mime::Mime>()
+        .map_err(|_| parse_error(format!("{} is not a valid MIME type", mime.trim())))?;
+    let mime = HeaderValue::from_str(mime.as_ref())
+        .map_err(|_| parse_error(format!("{} is not a valid MIME type", mime)))?;
+    Ok((extension, mime))
+}
+
+impl MimeMapArg {
+    /// Parses a mapping if `src` contains `=`, otherwise takes it as the path of a file of
+    /// mappings, read at startup
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        if src.contains('=') {
+            let (extension, mime) = parse_entry(src)?;
+            Ok(MimeMapArg::Entry(extension, mime))
+        } else {
+            Ok(MimeMapArg::File(PathBuf::from(src)))
+        }
+    }
+}
+
+/// Builds the MIME types by extension from the `--mime-map` values, later ones taking
+/// precedence.
+///
+/// Files contain one mapping per line, empty lines and lines starting with `#` are skipped.
+pub fn load(args: &[MimeMapArg]) -> Result<HashMap<String, HeaderValue>, ContextualError> {
+    let mut mime_map = HashMap::new();
+    for arg in args {
+        match arg {
+            MimeMapArg::Entry(extension, mime) => {
+                mime_map.insert(extension.clone(), mime.clone());
+            }
+            MimeMapArg::File(path) => {
+                let content = fs::read_to_string(path).map_err(|e| {
+                    ContextualError::IoError(format!("Failed to read {}", path.display()), e)
+                })?;
+                for line in content.lines().map(str::trim) {
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let (extension, mime) = parse_entry(line)?;
+                    mime_map.insert(extension, mime);
+                }
+            }
+        }
+    }
+    Ok(mime_map)
+}
+
+/// Returns the `Content-Type` for the response to `req`, if it requests a file whose
+/// extension is mapped by `--mime-map`
+pub fn content_type(req: &ServiceRequest) -> Option<HeaderValue> {
+    let conf = req.app_data::<crate::MiniserveConfig>()?;
+    if conf.mime_map.is_empty() {
+        return None;
+    }
+
+    let relative_path = path_policy::requested_file(req)?;
+    let extension = relative_path.extension()?.to_str()?.to_lowercase();
+    conf.mime_map.get(&extension).cloned()
+}
+
+/// Sets the `Content-Type` header `value` on `res`, if it serves the file.
+///
+/// Responses with several ranges keep their multipart type, their parts have the type already.
+pub fn apply<B>(mut res: ServiceResponse<B>, value: Option<HeaderValue>) -> ServiceResponse<B> {
+    let is_multipart = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| content_type.starts_with("multipart/"));
+    let serves_file =
+        matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT) && !is_multipart;
+    if let Some(value) = value.filter(|_| serves_file) {
+        res.headers_mut().insert(header::CONTENT_TYPE, value);
+    }
+    res
+}
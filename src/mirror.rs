@@ -0,0 +1,82 @@
+use actix_web::client::Client;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// Headers which are never mirrored, as they contain credentials
+const REDACTED_HEADERS: &[header::HeaderName] = &[
+    header::AUTHORIZATION,
+    header::PROXY_AUTHORIZATION,
+    header::COOKIE,
+];
+
+/// Metadata of a request sent to the mirror collector
+#[derive(Serialize)]
+struct RequestMetadata {
+    /// Seconds since the UNIX epoch
+    timestamp: u64,
+    method: String,
+    path: String,
+    query: String,
+    version: String,
+    peer_addr: Option<String>,
+
+    /// Values of the headers, joined by ", " if there are several
+    headers: BTreeMap<String, String>,
+}
+
+impl RequestMetadata {
+    fn new(req: &ServiceRequest) -> Self {
+        let mut headers = BTreeMap::new();
+        for (name, value) in req.headers() {
+            let value = if REDACTED_HEADERS.contains(name) {
+                "<redacted>".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            };
+            headers
+                .entry(name.to_string())
+                .and_modify(|values: &mut String| {
+                    values.push_str(", ");
+                    values.push_str(&value);
+                })
+                .or_insert(value);
+        }
+
+        RequestMetadata {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            method: req.method().to_string(),
+            path: req.path().to_string(),
+            query: req.query_string().to_string(),
+            version: format!("{:?}", req.version()),
+            peer_addr: req.peer_addr().map(|addr| addr.to_string()),
+            headers,
+        }
+    }
+}
+
+/// Forwards the metadata of the request, but not its body, to the mirror collector if set
+///
+/// This happens in the background, so neither slow nor failing collectors affect the request.
+pub fn mirror_request(req: &ServiceRequest) {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let collector = match &conf.mirror_requests {
+        Some(collector) => collector.clone(),
+        None => return,
+    };
+    let metadata = RequestMetadata::new(req);
+
+    actix_web::rt::spawn(async move {
+        if let Err(e) = Client::default()
+            .post(&collector)
+            .send_json(&metadata)
+            .await
+        {
+            log::warn!("Failed to mirror request to {}: {}", collector, e);
+        }
+    });
+}
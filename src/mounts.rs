@@ -0,0 +1,76 @@
+use actix_web::{HttpRequest, HttpResponse};
+use std::path::Path;
+
+use crate::errors::ContextualError;
+use crate::i18n::Language;
+use crate::renderer;
+use crate::MiniserveConfig;
+
+/// Returns the name a directory is mounted at when serving several, i.e. its own name.
+///
+/// Names are used as they are in routes, so only unreserved URL characters are allowed.
+fn mount_name(root: &Path) -> Result<String, ContextualError> {
+    let canonical = root.canonicalize().map_err(|e| {
+        ContextualError::IoError(format!("Failed to resolve {}", root.display()), e)
+    })?;
+    canonical
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| {
+            name.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c))
+        })
+        .map(str::to_string)
+        .ok_or_else(|| ContextualError::InvalidMountError(root.display().to_string()))
+}
+
+/// Returns the configuration of each directory when serving several, which is `conf` serving
+/// only that directory under its name.
+///
+/// The name is served like a random route, so that all links of the directory point below it.
+pub fn mount_configs(
+    conf: &MiniserveConfig,
+) -> Result<Vec<(String, MiniserveConfig)>, ContextualError> {
+    if conf.roots.len() < 2 {
+        return Ok(vec![]);
+    }
+
+    let mut mounts: Vec<(String, MiniserveConfig)> = vec![];
+    for root in &conf.roots {
+        let name = mount_name(root)?;
+        if mounts.iter().any(|(other, _)| *other == name) {
+            return Err(ContextualError::DuplicateMountError(name));
+        }
+
+        let mut mount = conf.clone();
+        mount.path = root.clone();
+        mount.roots = vec![];
+        mount.no_archive = conf.no_archive.with_root(root);
+        mount.random_route = Some(match &conf.random_route {
+            Some(random_route) => format!("{}/{}", random_route, name),
+            None => name.clone(),
+        });
+        mounts.push((name, mount));
+    }
+    Ok(mounts)
+}
+
+/// Lists the directories served under their names
+pub async fn mounts_page(req: HttpRequest) -> HttpResponse {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let mounts = conf
+        .mounts
+        .iter()
+        .map(|(name, mount)| {
+            let link = format!("/{}/", mount.random_route.clone().unwrap_or_default());
+            (name.as_str(), link)
+        })
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            renderer::mounts_page(&mounts, conf, Language::negotiate(req.headers(), conf.lang))
+                .into_string(),
+        )
+}
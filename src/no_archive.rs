@@ -0,0 +1,73 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// Name of the marker file which keeps its directory from being downloaded as an archive
+pub const NO_ARCHIVE_FILE_NAME: &str = ".no-archive";
+
+/// Directories which can't be downloaded as archives, even though archives are enabled.
+///
+/// A directory is marked by a `.no-archive` file in it or by matching one of the patterns of
+/// `--no-archive-path`. Marked directories and everything below them can't be archived, and
+/// they are left out of archives of their parents.
+#[derive(Clone)]
+pub struct NoArchive {
+    /// Canonical path of the served directory, which the patterns are relative to
+    root: PathBuf,
+
+    /// Patterns of `--no-archive-path`, if any
+    paths: Option<Gitignore>,
+}
+
+impl NoArchive {
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let paths = if patterns.is_empty() {
+            None
+        } else {
+            let mut builder = GitignoreBuilder::new(&root);
+            for pattern in patterns {
+                if let Err(e) = builder.add_line(None, pattern) {
+                    log::warn!("Invalid no-archive pattern {}: {}", pattern, e);
+                }
+            }
+            match builder.build() {
+                Ok(paths) => Some(paths),
+                Err(e) => {
+                    log::warn!("Failed to build no-archive patterns: {}", e);
+                    None
+                }
+            }
+        };
+
+        NoArchive { root, paths }
+    }
+
+    /// Returns the same patterns for another served directory `root`
+    pub fn with_root(&self, root: &Path) -> Self {
+        NoArchive {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            paths: self.paths.clone(),
+        }
+    }
+
+    /// Returns whether the directory `dir` itself is marked
+    pub fn is_marked(&self, dir: &Path) -> bool {
+        if dir.join(NO_ARCHIVE_FILE_NAME).is_file() {
+            return true;
+        }
+        match (&self.paths, dir.strip_prefix(&self.root)) {
+            (Some(paths), Ok(relative_path)) if relative_path != Path::new("") => {
+                paths.matched(relative_path, true).is_ignore()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether no archive of `dir` may be created, because it or one of its parents
+    /// up to the served directory is marked
+    pub fn is_disabled(&self, dir: &Path) -> bool {
+        dir.ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&self.root))
+            .any(|ancestor| self.is_marked(ancestor))
+    }
+}
@@ -0,0 +1,245 @@
+use actix_web::dev::ServiceRequest;
+use percent_encoding::percent_decode_str;
+use std::path::{Component, Path, PathBuf};
+
+use crate::errors::ContextualError;
+
+/// Resolves `request_path`, a path taken from a request and relative to the served directory
+/// `root`, to the canonical path it refers to.
+///
+/// This is the single place deciding whether a path given by a client may be accessed: it is
+/// rejected if it contains `..` or a Windows prefix, if it doesn't exist, or if it leaves `root`
+/// once symlinks are resolved.
+pub fn resolve_request_path(root: &Path, request_path: &Path) -> Result<PathBuf, ContextualError> {
+    let root = root.canonicalize().map_err(|e| {
+        ContextualError::IoError("Failed to resolve path served by miniserve".to_string(), e)
+    })?;
+    let invalid_path = || {
+        ContextualError::InvalidPathError(format!(
+            "'{}' is not a path inside the served directory",
+            request_path.display()
+        ))
+    };
+
+    let mut relative_path = PathBuf::new();
+    for component in request_path.components() {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::Normal(name) => relative_path.push(name),
+            Component::ParentDir | Component::Prefix(_) => return Err(invalid_path()),
+        }
+    }
+
+    match root.join(relative_path).canonicalize() {
+        Ok(path) if path.starts_with(&root) => Ok(path),
+        _ => Err(invalid_path()),
+    }
+}
+
+/// Returns the path of the file requested by `req`, relative to the served directory, if it
+/// requests an existing file rather than a directory
+pub fn requested_file(req: &ServiceRequest) -> Option<PathBuf> {
+    let conf = req
+        .app_data::<crate::MiniserveConfig>()?
+        .for_path(req.path());
+    let route = format!("/{}", conf.random_route.clone().unwrap_or_default());
+    let relative_path = percent_decode_str(req.path().strip_prefix(&route)?)
+        .decode_utf8()
+        .ok()?;
+    let relative_path = PathBuf::from(relative_path.trim_start_matches('/'));
+    let path = resolve_request_path(&conf.path, &relative_path).ok()?;
+    Some(relative_path).filter(|_| path.is_file())
+}
+
+/// Returns `true` if `name` is the plain name of an entry, i.e. it can't refer to anything
+/// outside of the directory it is joined to
+pub fn is_entry_name(name: &Path) -> bool {
+    let mut components = name.components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Returns `true` if one of the components of the relative path `path` is hidden, i.e. starts
+/// with a dot
+pub fn is_hidden(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+/// Returns the path of a member of an archive relative to the directory it's extracted to,
+/// or `None` if the path is empty or could refer to anything outside of that directory
+pub fn enclosed_path(member: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in member.components() {
+        match component {
+            Component::Normal(name) => path.push(name),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path).filter(|path| path != Path::new(""))
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use assert_fs::fixture::TempDir;
+    use rstest::{fixture, rstest};
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+
+    /// Served directory `root` next to a directory `outside` of it
+    #[fixture]
+    fn tmpdir() -> TempDir {
+        let tmpdir = TempDir::new().expect("Couldn't create a temp dir for tests");
+        tmpdir.child("root/dir/file").touch().unwrap();
+        tmpdir.child("outside/file").touch().unwrap();
+        tmpdir
+    }
+
+    #[rstest(request_path, expected,
+        case("", Some("")),
+        case("/", Some("")),
+        case("dir", Some("dir")),
+        case("/dir/", Some("dir")),
+        case("./dir/./file", Some("dir/file")),
+        case("//dir//file", Some("dir/file")),
+        case("missing", None),
+        case("..", None),
+        case("../outside", None),
+        case("/../root/dir", None),
+        case("dir/..", None),
+        case("dir/../../outside", None),
+    )]
+    fn resolve_traversal(tmpdir: TempDir, request_path: &str, expected: Option<&str>) {
+        let root = tmpdir.path().join("root").canonicalize().unwrap();
+        let resolved = resolve_request_path(&root, Path::new(request_path)).ok();
+        assert_eq!(resolved, expected.map(|path| root.join(path)));
+    }
+
+    #[cfg(unix)]
+    #[rstest(link, target, allowed,
+        case("inside", "root/dir", true),
+        case("outside", "outside", false),
+        case("outside_file", "outside/file", false),
+        case("root", ".", false),
+    )]
+    fn resolve_symlinks(tmpdir: TempDir, link: &str, target: &str, allowed: bool) {
+        let root = tmpdir.path().join("root");
+        std::os::unix::fs::symlink(tmpdir.path().join(target), root.join(link)).unwrap();
+
+        let resolved = resolve_request_path(&root, Path::new(link)).ok();
+        assert_eq!(resolved.is_some(), allowed);
+        if let Some(resolved) = resolved {
+            assert_eq!(resolved, tmpdir.path().join(target).canonicalize().unwrap());
+        }
+    }
+
+    /// Request paths made of components which could lead outside of the served directory,
+    /// through `..` or the symlinks set up by `resolve_generated_paths`
+    fn request_paths() -> impl Strategy<Value = String> {
+        let component = prop_oneof![
+            Just(""),
+            Just("."),
+            Just(".."),
+            Just("dir"),
+            Just("file"),
+            Just("root"),
+            Just("outside"),
+            Just("inside_link"),
+            Just("outside_link"),
+        ];
+        (any::<bool>(), prop::collection::vec(component, 0..8)).prop_map(|(absolute, components)| {
+            let path = components.join("/");
+            if absolute { format!("/{}", path) } else { path }
+        })
+    }
+
+    #[rstest]
+    fn resolve_generated_paths(tmpdir: TempDir) {
+        let root = tmpdir.path().join("root").canonicalize().unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("dir"), root.join("inside_link")).unwrap();
+            std::os::unix::fs::symlink(tmpdir.path().join("outside"), root.join("outside_link")).unwrap();
+        }
+
+        proptest!(|(request_path in request_paths())| {
+            if let Ok(resolved) = resolve_request_path(&root, Path::new(&request_path)) {
+                prop_assert!(resolved.starts_with(&root), "{} resolved to {}", request_path, resolved.display());
+                prop_assert!(resolved.exists());
+            }
+        });
+    }
+
+    proptest! {
+        #[test]
+        fn enclosed_generated_paths(member in "[a-z./]{0,12}") {
+            if let Some(path) = enclosed_path(Path::new(&member)) {
+                prop_assert!(path.components().all(|component| matches!(component, Component::Normal(_))));
+                prop_assert_ne!(path, PathBuf::new());
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    #[rstest(request_path,
+        case("C:\\"),
+        case("C:\\Windows"),
+        case("\\\\server\\share\\file"),
+        case("\\\\?\\C:\\Windows"),
+    )]
+    fn resolve_windows_prefixes(tmpdir: TempDir, request_path: &str) {
+        let root = tmpdir.path().join("root");
+        assert!(resolve_request_path(&root, Path::new(request_path)).is_err());
+    }
+
+    #[rstest(name, expected,
+        case("file", true),
+        case("file.tar.gz", true),
+        case(".hidden", true),
+        case("", false),
+        case(".", false),
+        case("..", false),
+        case("/file", false),
+        case("dir/file", false),
+        case("../file", false),
+    )]
+    fn entry_names(name: &str, expected: bool) {
+        assert_eq!(is_entry_name(Path::new(name)), expected);
+    }
+
+    #[rstest(path, expected,
+        case("", false),
+        case("file", false),
+        case("dir/file.tar.gz", false),
+        case("./dir/file", false),
+        case(".hidden", true),
+        case(".git/config", true),
+        case("/dir/.hidden/file", true),
+    )]
+    fn hidden_paths(path: &str, expected: bool) {
+        assert_eq!(is_hidden(Path::new(path)), expected);
+    }
+
+    #[rstest(member, expected,
+        case("file", Some("file")),
+        case("dir/", Some("dir")),
+        case("./dir/./file", Some("dir/file")),
+        case("", None),
+        case(".", None),
+        case("..", None),
+        case("/etc/passwd", None),
+        case("dir/../file", None),
+        case("../outside", None),
+    )]
+    fn enclosed_paths(member: &str, expected: Option<&str>) {
+        assert_eq!(enclosed_path(Path::new(member)), expected.map(PathBuf::from));
+    }
+}
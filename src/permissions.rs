@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+
+/// Mode bits, owner and group of an entry, as shown by `ls -l`
+#[derive(Clone)]
+pub struct Permissions {
+    /// Type and permission bits, e.g. `drwxr-xr-x`
+    pub mode: String,
+
+    /// Name of the owning user, or its id if it has no name
+    pub owner: String,
+
+    /// Name of the owning group, or its id if it has no name
+    pub group: String,
+}
+
+/// Names of users and groups by id
+#[derive(Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// Reads the names from `/etc/passwd` and `/etc/group`.
+    ///
+    /// Users and groups only known to other sources, like LDAP, are shown by id.
+    pub fn load() -> Self {
+        let read = |path| std::fs::read_to_string(path).unwrap_or_default();
+        OwnerNames {
+            users: parse_names(&read("/etc/passwd")),
+            groups: parse_names(&read("/etc/group")),
+        }
+    }
+
+    /// Returns the permissions of an entry, only available on unix
+    #[cfg(unix)]
+    pub fn permissions(&self, metadata: &Metadata) -> Option<Permissions> {
+        use std::os::unix::fs::MetadataExt;
+
+        let name = |names: &HashMap<u32, String>, id: u32| {
+            names.get(&id).cloned().unwrap_or_else(|| id.to_string())
+        };
+        Some(Permissions {
+            mode: mode_string(metadata.mode()),
+            owner: name(&self.users, metadata.uid()),
+            group: name(&self.groups, metadata.gid()),
+        })
+    }
+
+    /// Returns the permissions of an entry, only available on unix
+    #[cfg(not(unix))]
+    pub fn permissions(&self, _metadata: &Metadata) -> Option<Permissions> {
+        None
+    }
+}
+
+/// Parses the names and ids of a file in the format of `/etc/passwd` or `/etc/group`,
+/// i.e. lines like `name:password:id:...`
+fn parse_names(content: &str) -> HashMap<u32, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// Formats the type and permission bits of `mode` like `ls -l` does, e.g. `-rw-r--r--`
+#[cfg(unix)]
+fn mode_string(mode: u32) -> String {
+    let mut string = String::from(match mode & 0o170000 {
+        0o040000 => "d",
+        0o120000 => "l",
+        _ => "-",
+    });
+    for (i, symbol) in "rwxrwxrwx".chars().enumerate() {
+        let bit = 0o400 >> i;
+        string.push(if mode & bit != 0 { symbol } else { '-' });
+    }
+    string
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(unix)]
+    #[rstest(mode, expected,
+        case(0o644, "-rw-r--r--"),
+        case(0o100755, "-rwxr-xr-x"),
+        case(0o040755, "drwxr-xr-x"),
+        case(0o120777, "lrwxrwxrwx"),
+        case(0o100600, "-rw-------"),
+        case(0o000, "----------"),
+    )]
+    fn mode_strings(mode: u32, expected: &str) {
+        assert_eq!(mode_string(mode), expected);
+    }
+
+    #[test]
+    fn names_by_id() {
+        let names = parse_names("# comment\nroot:x:0:0:root:/root:/bin/sh\nbroken\nwww-data:x:33:\n");
+        assert_eq!(names.get(&0).map(String::as_str), Some("root"));
+        assert_eq!(names.get(&33).map(String::as_str), Some("www-data"));
+        assert_eq!(names.len(), 2);
+    }
+}
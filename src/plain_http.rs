@@ -0,0 +1,96 @@
+use actix_web::http::header;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use std::net::IpAddr;
+
+use crate::acme::Acme;
+use crate::errors::ContextualError;
+
+/// What the plain HTTP server next to the HTTPS one does
+#[derive(Clone)]
+struct PlainHttp {
+    /// Answers the challenges of Let's Encrypt, if set
+    acme: Option<Acme>,
+
+    /// Port to redirect all other requests to over HTTPS, if set
+    https_port: Option<u16>,
+}
+
+/// Starts a plain HTTP server on `port` of `interfaces`, next to the HTTPS one.
+///
+/// It answers the challenges of `acme`, if any, and redirects all other requests to the same
+/// URL over HTTPS on `https_port`, if set. Nothing is ever served over plain HTTP.
+pub fn start(
+    interfaces: &[IpAddr],
+    port: u16,
+    acme: Option<Acme>,
+    https_port: Option<u16>,
+) -> Result<(), ContextualError> {
+    let state = PlainHttp { acme, https_port };
+    let addresses = interfaces
+        .iter()
+        .map(|&interface| (interface, port))
+        .collect::<Vec<_>>();
+
+    // The server keeps running along with the HTTPS one without holding on to its handle
+    HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route(
+                "/.well-known/acme-challenge/{token}",
+                web::get().to(challenge),
+            )
+            .default_service(web::route().to(redirect))
+    })
+    .bind(addresses.as_slice())
+    .map_err(|e| {
+        ContextualError::IoError(format!("Failed to bind to port {} for plain HTTP", port), e)
+    })?
+    .shutdown_timeout(0)
+    .run();
+    Ok(())
+}
+
+/// Answers a challenge of Let's Encrypt with its proof
+async fn challenge(req: HttpRequest, token: web::Path<String>) -> HttpResponse {
+    let state = req.app_data::<PlainHttp>().unwrap();
+    match state.acme.as_ref().and_then(|acme| acme.proof(&token)) {
+        Some(proof) => HttpResponse::Ok().content_type("text/plain").body(proof),
+        None => redirect(req).await,
+    }
+}
+
+/// Redirects to the same URL over HTTPS, keeping the path and query
+async fn redirect(req: HttpRequest) -> HttpResponse {
+    let state = req.app_data::<PlainHttp>().unwrap();
+    let https_port = match state.https_port {
+        Some(https_port) => https_port,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    let connection_info = req.connection_info();
+    let host = host_without_port(connection_info.host());
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let location = if https_port == 443 {
+        format!("https://{}{}", host, path)
+    } else {
+        format!("https://{}:{}{}", host, https_port, path)
+    };
+    HttpResponse::MovedPermanently()
+        .header(header::LOCATION, location)
+        .finish()
+}
+
+/// Strips the port from the value of a `Host` header, keeping the brackets of IPv6 addresses
+fn host_without_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        match host.find(']') {
+            Some(end) => &host[..=end],
+            None => host,
+        }
+    } else {
+        host.rsplit_once(':').map_or(host, |(host, _)| host)
+    }
+}
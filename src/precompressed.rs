@@ -0,0 +1,86 @@
+use actix_files::NamedFile;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::{header, HeaderValue, Method};
+use actix_web::HttpResponse;
+use percent_encoding::percent_decode_str;
+use std::fs::File;
+use std::path::{Component, Path};
+
+use crate::path_policy;
+
+/// Encodings of precompressed files by the suffix of their name, most preferred first
+const ENCODINGS: [(&str, &str); 2] = [("br", ".br"), ("gzip", ".gz")];
+
+/// Returns whether the `Accept-Encoding` header `accept_encoding` allows `encoding`
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut params = item.split(';').map(str::trim);
+        let is_encoding = params.next().map_or(false, |coding| {
+            coding.eq_ignore_ascii_case(encoding) || coding == "*"
+        });
+        let is_refused = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .map_or(false, |q| q == 0.0)
+        });
+        is_encoding && !is_refused
+    })
+}
+
+/// Serves a precompressed sibling of a requested file, like `app.js.br` for `app.js`, if the
+/// client accepts its encoding.
+///
+/// Returns the response to send instead of the file itself, if there is such a sibling.
+pub fn serve(req: &ServiceRequest) -> Option<HttpResponse> {
+    let conf = req
+        .app_data::<crate::MiniserveConfig>()
+        .unwrap()
+        .for_path(req.path());
+    // Encrypted files can't keep their encoding
+    if !conf.serve_precompressed || conf.psk.is_some() {
+        return None;
+    }
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return None;
+    }
+    let accept_encoding = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+
+    let route = format!("/{}", conf.random_route.clone().unwrap_or_default());
+    let relative_path = percent_decode_str(req.path().strip_prefix(&route)?)
+        .decode_utf8()
+        .ok()?;
+    let relative_path = Path::new(relative_path.trim_start_matches('/'));
+    let is_hidden = relative_path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    if is_hidden && !conf.show_hidden {
+        return None;
+    }
+    let path = path_policy::resolve_request_path(&conf.path, relative_path).ok()?;
+    if !path.is_file() {
+        return None;
+    }
+
+    let (encoding, sibling) = ENCODINGS.iter().find_map(|(encoding, suffix)| {
+        if !accepts(accept_encoding, encoding) {
+            return None;
+        }
+        let mut sibling = relative_path.as_os_str().to_owned();
+        sibling.push(suffix);
+        let sibling = path_policy::resolve_request_path(&conf.path, Path::new(&sibling)).ok()?;
+        Some((*encoding, sibling)).filter(|(_, sibling)| sibling.is_file())
+    })?;
+
+    // The type and name of the response are the ones of the requested file
+    let file = File::open(&sibling).ok()?;
+    let mut res = NamedFile::from_file(file, &path)
+        .ok()?
+        .into_response(req.request())
+        .ok()?;
+    let headers = res.headers_mut();
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    Some(res)
+}
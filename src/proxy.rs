@@ -0,0 +1,190 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use crate::errors::ContextualError;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// A network of reverse proxies given by `--trusted-proxy`, like `10.0.0.0/8` or `::1`
+#[derive(Clone, Debug)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl TrustedProxy {
+    /// Parses a network in CIDR notation, or a single address
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        let parse_error = |cause: String| ContextualError::ParseError("proxy".to_string(), cause);
+
+        let (network, prefix_len) = match src.trim().split_once('/') {
+            Some((network, prefix_len)) => (network, Some(prefix_len)),
+            None => (src.trim(), None),
+        };
+        let network = network
+            .parse::<IpAddr>()
+            .map_err(|e| parse_error(e.to_string()))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_len)
+                .ok_or_else(|| {
+                    parse_error(format!("{} is not a valid prefix length", prefix_len))
+                })?,
+            None => max_len,
+        };
+        Ok(TrustedProxy {
+            network,
+            prefix_len,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, unmapped(ip)) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the IPv4 address of an IPv4-mapped IPv6 address, as peers of dual-stack sockets are
+fn unmapped(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => match ipv6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, high, low] => IpAddr::V4(Ipv4Addr::new(
+                (high >> 8) as u8,
+                high as u8,
+                (low >> 8) as u8,
+                low as u8,
+            )),
+            _ => ip,
+        },
+        ip => ip,
+    }
+}
+
+/// Parses an address of `X-Forwarded-For`, which some proxies give with a port
+fn parse_forwarded_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    value
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// Makes the forwarding headers of the request tell the real client and scheme, if
+/// `--trusted-proxy` is given.
+///
+/// Requests from trusted proxies keep `X-Forwarded-Proto` and `X-Forwarded-Host`, and their
+/// `X-Forwarded-For` is reduced to the client in front of the last trusted proxy. The
+/// forwarding headers of all other requests are removed, since anyone could have set them.
+/// The `Forwarded` header is always removed, so that only these headers are considered.
+pub fn sanitize_request(req: &mut ServiceRequest) {
+    let proxies = match req.app_data::<crate::MiniserveConfig>() {
+        Some(conf) if !conf.trusted_proxies.is_empty() => conf.trusted_proxies.clone(),
+        _ => return,
+    };
+    let is_trusted = |ip: IpAddr| proxies.iter().any(|proxy| proxy.contains(ip));
+
+    let peer_is_trusted = req.peer_addr().map_or(false, |addr| is_trusted(addr.ip()));
+    let client = if peer_is_trusted {
+        let forwarded_for = req
+            .headers()
+            .get_all(X_FORWARDED_FOR)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(parse_forwarded_ip)
+            .collect::<Vec<_>>();
+        // Each proxy appends the address it got the request from, so the addresses are
+        // checked from the right until one isn't a trusted proxy
+        let mut client = None;
+        for ip in forwarded_for.into_iter().rev() {
+            client = ip;
+            match ip {
+                Some(ip) if is_trusted(ip) => continue,
+                _ => break,
+            }
+        }
+        client
+    } else {
+        None
+    };
+
+    let headers = req.headers_mut();
+    headers.remove(header::FORWARDED);
+    headers.remove(X_FORWARDED_FOR);
+    if !peer_is_trusted {
+        headers.remove(X_FORWARDED_PROTO);
+        headers.remove(X_FORWARDED_HOST);
+    }
+    if let Some(client) = client {
+        if let Ok(value) = HeaderValue::from_str(&client.to_string()) {
+            headers.insert(HeaderName::from_static(X_FORWARDED_FOR), value);
+        }
+    }
+}
+
+/// Returns the address of the client of the request, which is the one forwarded by trusted
+/// proxies, if any
+pub fn client_ip(req: &ServiceRequest) -> Option<IpAddr> {
+    let forwarded = req
+        .app_data::<crate::MiniserveConfig>()
+        .map_or(false, |conf| !conf.trusted_proxies.is_empty());
+    let forwarded_ip = req
+        .headers()
+        .get(X_FORWARDED_FOR)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_forwarded_ip)
+        .filter(|_| forwarded);
+    forwarded_ip.or_else(|| req.peer_addr().map(|addr| addr.ip()))
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    #[rstest(proxy, ip, expected,
+        case("10.0.0.0/8", "10.1.2.3", true),
+        case("10.0.0.0/8", "11.1.2.3", false),
+        case("10.0.0.0/8", "::ffff:10.1.2.3", true),
+        case("192.168.1.1", "192.168.1.1", true),
+        case("192.168.1.1", "192.168.1.2", false),
+        case("0.0.0.0/0", "8.8.8.8", true),
+        case("fd00::/8", "fd12::1", true),
+        case("fd00::/8", "fe80::1", false),
+        case("::1", "127.0.0.1", false),
+    )]
+    fn trusted_proxy_contains(proxy: &str, ip: &str, expected: bool) {
+        let proxy = TrustedProxy::parse(proxy).unwrap();
+        assert_eq!(proxy.contains(ip.parse().unwrap()), expected);
+    }
+
+    #[rstest(proxy,
+        case("10.0.0.0/33"),
+        case("fd00::/129"),
+        case("10.0.0.0/"),
+        case("proxy.lan"),
+    )]
+    fn trusted_proxy_invalid(proxy: &str) {
+        assert!(TrustedProxy::parse(proxy).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Encryption of file downloads with a pre-shared key.
+//!
+//! Files are encrypted in the [age](https://age-encryption.org) format using the key as
+//! passphrase, so they can be decrypted with any age implementation, like `age -d`.
+use actix_web::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{
+    self, ContentDisposition, DispositionParam, DispositionType, HeaderValue, IntoHeaderValue,
+};
+use actix_web::http::Method;
+use actix_web::web::{self, Bytes};
+use actix_web::HttpResponse;
+use age::stream::StreamWriter;
+use secrecy::Secret;
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Buffer that the encryption stream writes into and the response body is read from
+///
+/// It is shared across threads since the encryption is set up on the blocking thread pool.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Takes all bytes written so far out of the buffer
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock().unwrap()))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Response body encrypting the wrapped body on the fly
+struct EncryptedBody {
+    inner: ResponseBody<Body>,
+    writer: Option<StreamWriter<SharedBuffer>>,
+    buffer: SharedBuffer,
+}
+
+impl MessageBody for EncryptedBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, actix_web::Error>>> {
+        let this = self.get_mut();
+        loop {
+            let writer = match this.writer.as_mut() {
+                Some(writer) => writer,
+                None => return Poll::Ready(None),
+            };
+
+            let written = match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(Some(Ok(chunk))) => writer.write_all(&chunk),
+                // Writing the final chunk also authenticates the end of the stream
+                Poll::Ready(None) => this.writer.take().unwrap().finish().map(|_| ()),
+            };
+            if let Err(e) = written {
+                return Poll::Ready(Some(Err(e.into())));
+            }
+
+            // The encryption works on fixed-size chunks, so we might not have any output yet
+            let encrypted = this.buffer.take();
+            if !encrypted.is_empty() {
+                return Poll::Ready(Some(Ok(encrypted)));
+            }
+        }
+    }
+}
+
+/// Encrypts the body of a file response with `psk`
+///
+/// Responses that don't serve a file (like listings or errors) are returned unchanged.
+///
+/// Deriving the key from the passphrase takes about a second of CPU time by design, so it runs
+/// on the blocking thread pool instead of stalling the worker.
+pub async fn encrypt_response(mut res: ServiceResponse, psk: String) -> ServiceResponse {
+    let serves_file = res.status().is_success()
+        && res.request().method() != Method::HEAD
+        && res.headers().contains_key(header::CONTENT_DISPOSITION);
+    if !serves_file {
+        return res;
+    }
+
+    let buffer = SharedBuffer::default();
+    let output = buffer.clone();
+    let writer = match web::block(move || {
+        age::Encryptor::with_user_passphrase(Secret::new(psk)).wrap_output(output)
+    })
+    .await
+    {
+        Ok(writer) => writer,
+        Err(e) => {
+            log::error!("Failed to set up encryption: {}", e);
+            return res.into_response(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    let file_name = res
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|cd| ContentDisposition::from_raw(cd).ok())
+        .and_then(|cd| cd.get_filename().map(String::from))
+        .unwrap_or_else(|| "download".to_string());
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(format!("{}.age", file_name))],
+    };
+
+    let headers = res.headers_mut();
+    // The size of the encrypted body differs from the file and ranges can't be served anymore
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::ACCEPT_RANGES);
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    if let Ok(disposition) = IntoHeaderValue::try_into(disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+
+    res.map_body(move |_, body| {
+        ResponseBody::Other(Body::from_message(EncryptedBody {
+            inner: body,
+            writer: Some(writer),
+            buffer,
+        }))
+    })
+}
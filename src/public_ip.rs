@@ -0,0 +1,181 @@
+use actix_web::client::Client;
+use actix_web::web;
+use std::convert::TryInto;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::errors::ContextualError;
+
+/// STUN server the public IP address is asked from, unless `--show-public-url` gives another
+/// source
+const DEFAULT_STUN_SERVER: &str = "stun.l.google.com:19302";
+
+/// Longest time to wait for the public IP address, so that startup isn't held up
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Port of STUN servers given without one
+const STUN_PORT: u16 = 3478;
+
+/// Fixed value of every STUN message, see RFC 5389
+const MAGIC_COOKIE: [u8; 4] = [0x21, 0x12, 0xa4, 0x42];
+
+/// STUN attribute with the address the server saw, obfuscated with the magic cookie
+const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// STUN attribute with the address the server saw, from before RFC 5389
+const MAPPED_ADDRESS: u16 = 0x0001;
+
+/// A `--show-public-url` source of the public IP address
+#[derive(Clone, Debug)]
+pub enum PublicIpSource {
+    /// A STUN server like `stun:stun.example.com:3478`
+    Stun(String),
+
+    /// A URL responding with the IP address as plain text, like `https://api.ipify.org`
+    Http(String),
+}
+
+impl Default for PublicIpSource {
+    fn default() -> Self {
+        PublicIpSource::Stun(DEFAULT_STUN_SERVER.to_string())
+    }
+}
+
+impl PublicIpSource {
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        if let Some(server) = src.strip_prefix("stun:") {
+            // IPv6 addresses have to be in brackets, like for URLs
+            let has_port = match server.rsplit_once(':') {
+                Some((host, port)) => {
+                    port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+                }
+                None => false,
+            };
+            return Ok(PublicIpSource::Stun(if has_port {
+                server.to_string()
+            } else {
+                format!("{}:{}", server, STUN_PORT)
+            }));
+        }
+        if src.starts_with("http://") || src.starts_with("https://") {
+            return Ok(PublicIpSource::Http(src.to_string()));
+        }
+        Err(ContextualError::ParseError(
+            "public IP source".to_string(),
+            format!("{} is neither stun:<host>[:<port>] nor an http(s) URL", src),
+        ))
+    }
+}
+
+/// Reads the address of an `XOR-MAPPED-ADDRESS` or `MAPPED-ADDRESS` attribute
+fn parse_address(value: &[u8], xor_key: Option<&[u8; 16]>) -> Option<IpAddr> {
+    let family = *value.get(1)?;
+    let mut address = value.get(4..)?.to_vec();
+    if let Some(xor_key) = xor_key {
+        for (byte, key) in address.iter_mut().zip(xor_key.iter()) {
+            *byte ^= key;
+        }
+    }
+    match family {
+        0x01 => {
+            let octets: [u8; 4] = address.get(..4)?.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        0x02 => {
+            let octets: [u8; 16] = address.get(..16)?.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Asks the STUN server `server` which address our requests come from
+fn stun(server: &str) -> Result<IpAddr, String> {
+    let server = server
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", server, e))?
+        .next()
+        .ok_or_else(|| format!("{} has no address", server))?;
+    let local = match server {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    socket.connect(server).map_err(|e| e.to_string())?;
+
+    // A binding request without attributes
+    let transaction_id = nanoid::rngs::default(12);
+    let mut request = vec![0x00, 0x01, 0x00, 0x00];
+    request.extend_from_slice(&MAGIC_COOKIE);
+    request.extend_from_slice(&transaction_id);
+    socket.send(&request).map_err(|e| e.to_string())?;
+
+    let mut response = [0; 576];
+    let len = socket
+        .recv(&mut response)
+        .map_err(|e| format!("No response from {}: {}", server, e))?;
+    let response = &response[..len];
+    if len < 20 || response[..2] != [0x01, 0x01] || response[8..20] != transaction_id[..] {
+        return Err(format!("Invalid response from {}", server));
+    }
+
+    let mut xor_key = [0; 16];
+    xor_key[..4].copy_from_slice(&MAGIC_COOKIE);
+    xor_key[4..].copy_from_slice(&transaction_id);
+
+    let mut mapped = None;
+    let mut attributes = &response[20..];
+    while attributes.len() >= 4 {
+        let kind = u16::from_be_bytes([attributes[0], attributes[1]]);
+        let len = u16::from_be_bytes([attributes[2], attributes[3]]) as usize;
+        let value = match attributes.get(4..4 + len) {
+            Some(value) => value,
+            None => break,
+        };
+        match kind {
+            XOR_MAPPED_ADDRESS => {
+                return parse_address(value, Some(&xor_key))
+                    .ok_or_else(|| format!("Invalid response from {}", server))
+            }
+            MAPPED_ADDRESS => mapped = parse_address(value, None),
+            _ => (),
+        }
+        // Attributes are padded to multiples of 4 bytes
+        attributes = attributes.get(4 + (len + 3) / 4 * 4..).unwrap_or_default();
+    }
+    mapped.ok_or_else(|| format!("{} didn't tell the address", server))
+}
+
+/// Discovers the IP address other hosts on the internet see miniserve at, which is usually the
+/// one of the router in front of it
+pub async fn discover(source: &PublicIpSource) -> Result<IpAddr, ContextualError> {
+    let public_ip_error = |cause: String| ContextualError::PublicIpError(cause);
+    match source {
+        PublicIpSource::Stun(server) => {
+            let server = server.clone();
+            web::block(move || stun(&server).map_err(public_ip_error))
+                .await
+                .map_err(ContextualError::from)
+        }
+        PublicIpSource::Http(url) => {
+            let mut res = Client::builder()
+                .timeout(TIMEOUT)
+                .finish()
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| public_ip_error(format!("Failed to request {}: {}", url, e)))?;
+            let body = res
+                .body()
+                .await
+                .map_err(|e| public_ip_error(format!("Failed to read {}: {}", url, e)))?;
+            String::from_utf8_lossy(&body)
+                .trim()
+                .parse()
+                .map_err(|_| public_ip_error(format!("{} didn't respond with an address", url)))
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::ignore_files::IgnoreFiles;
+use crate::renderer;
+use crate::slow_ops;
+
+/// Time for which computed stats are shown before the served directory is walked again
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Totals of the served directory shown on the public stats page
+#[derive(Clone, Default)]
+pub struct ShareStats {
+    /// Number of files
+    pub files: u64,
+
+    /// Size in byte of all files
+    pub size: u64,
+
+    /// Latest modification date of any file or directory
+    pub last_update: Option<SystemTime>,
+}
+
+impl ShareStats {
+    fn add(&mut self, other: ShareStats) {
+        self.files += other.files;
+        self.size += other.size;
+        self.add_modified(other.last_update);
+    }
+
+    fn add_modified(&mut self, modified: Option<SystemTime>) {
+        if modified > self.last_update {
+            self.last_update = modified;
+        }
+    }
+}
+
+/// Stats of the served directory, computed on demand and cached for `MAX_AGE`
+#[derive(Clone, Default)]
+pub struct StatsCache {
+    stats: Arc<Mutex<Option<(Instant, ShareStats)>>>,
+}
+
+impl StatsCache {
+    /// Returns the stats of the served directory, walking it on the blocking thread pool if
+    /// the cached ones are outdated
+    async fn get(&self, conf: &crate::MiniserveConfig) -> Result<ShareStats, ContextualError> {
+        if let Some((computed_at, stats)) = &*self.stats.lock().unwrap() {
+            if computed_at.elapsed() <= MAX_AGE {
+                return Ok(stats.clone());
+            }
+        }
+
+        let root = conf.path.canonicalize().map_err(|e| {
+            ContextualError::IoError(format!("Failed to resolve {}", conf.path.display()), e)
+        })?;
+        let show_hidden = conf.show_hidden;
+        let ignore_files = if conf.ignore_files {
+            Some(IgnoreFiles::for_dir(&root, &root))
+        } else {
+            None
+        };
+        let slow_op_threshold = conf.slow_op_threshold;
+
+        let stats = web::block(move || {
+            Ok::<_, ContextualError>(slow_ops::timed(
+                slow_op_threshold,
+                "computing stats of",
+                &root,
+                || share_stats(&root, show_hidden, ignore_files.as_ref()),
+            ))
+        })
+        .await?;
+
+        *self.stats.lock().unwrap() = Some((Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+
+    /// Forgets the stats, e.g. after the served directory has been replaced
+    pub fn clear(&self) {
+        *self.stats.lock().unwrap() = None;
+    }
+}
+
+/// Adds up the files below `dir` which the listings show.
+///
+/// Like for directory sizes, symlinks are not followed so that loops can't keep the walk
+/// running forever, and unreadable entries are skipped.
+fn share_stats(dir: &Path, show_hidden: bool, ignore_files: Option<&IgnoreFiles>) -> ShareStats {
+    let mut stats = ShareStats::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return stats,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if let Some(ignore_files) = ignore_files {
+            if ignore_files.is_ignored(&path, metadata.is_dir()) {
+                continue;
+            }
+        }
+
+        stats.add_modified(metadata.modified().ok());
+        if metadata.is_dir() {
+            let ignore_files = ignore_files.map(|ignore_files| ignore_files.with_dir(&path));
+            stats.add(share_stats(&path, show_hidden, ignore_files.as_ref()));
+        } else if metadata.is_file() {
+            stats.files += 1;
+            stats.size += metadata.len();
+        }
+    }
+
+    stats
+}
+
+/// Route of the public stats page
+fn stats_route(conf: &crate::MiniserveConfig) -> String {
+    let full_route = format!("/{}", conf.random_route.clone().unwrap_or_default());
+    format!("{}/__miniserve/stats", full_route.trim_end_matches('/'))
+}
+
+/// Returns whether `req` asks for the public stats page, which is served in front of the
+/// authentication so that visitors without credentials can see it
+pub fn is_stats_request(req: &ServiceRequest) -> bool {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    conf.public_stats.is_some() && req.method() == Method::GET && req.path() == stats_route(conf)
+}
+
+/// Responds with the public stats page
+pub async fn stats_page(req: ServiceRequest) -> Result<ServiceResponse, actix_web::Error> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap().clone();
+    let lang = Language::negotiate(req.headers(), conf.lang);
+
+    let res = match conf.public_stats.as_ref().unwrap().get(&conf).await {
+        Ok(stats) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(renderer::stats_page(&stats, &conf, lang).into_string()),
+        Err(e) => {
+            errors::log_error_chain(e.to_string());
+            HttpResponse::InternalServerError().finish()
+        }
+    };
+    Ok(req.into_response(res))
+}
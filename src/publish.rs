@@ -0,0 +1,202 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::web;
+use actix_web::{HttpRequest, HttpResponse};
+use sha2::Sha256;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::auth;
+use crate::errors::{self, ContextualError};
+
+/// Swaps a staging directory into the served path on request
+#[derive(Clone)]
+pub struct Publisher {
+    /// Directory holding the next version of the served directory
+    staging: PathBuf,
+
+    /// SHA-256 hash of the token which requests have to present as bearer token
+    token_hash: Vec<u8>,
+
+    /// Held while publishing, so that concurrent requests can't interleave their renames
+    lock: Arc<Mutex<()>>,
+}
+
+impl Publisher {
+    pub fn new(staging: PathBuf, token: &str) -> Self {
+        Publisher {
+            staging,
+            token_hash: auth::get_hash::<Sha256>(token),
+            lock: Arc::default(),
+        }
+    }
+
+    /// Returns whether `req` presents the publish token in its `Authorization` header
+    fn is_authorized(&self, req: &HttpRequest) -> bool {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .map_or(false, |token| {
+                auth::compare_hash::<Sha256>(token.trim(), &self.token_hash)
+            })
+    }
+
+    /// Swaps the staging directory into the place of the served directory `served`.
+    ///
+    /// On Linux both are exchanged in one step, so visitors either see the complete old or the
+    /// complete new tree. Elsewhere, or on filesystems which can't exchange directories, they
+    /// are renamed one after the other, and requests in between may fail. The old tree is kept
+    /// next to `served` with a `.previous` suffix until the next publish, so that it can be
+    /// restored by hand.
+    fn publish(&self, served: &Path) -> Result<(), ContextualError> {
+        let _guard = self.lock.lock().unwrap();
+
+        if !self.staging.is_dir() {
+            return Err(ContextualError::InvalidPathError(format!(
+                "staging directory {} does not exist",
+                self.staging.display()
+            )));
+        }
+        if !served.is_dir() {
+            return Err(ContextualError::InvalidPathError(format!(
+                "served path {} is not a directory",
+                served.display()
+            )));
+        }
+
+        let mut previous = served.as_os_str().to_owned();
+        previous.push(".previous");
+        let previous = PathBuf::from(previous);
+        if previous.exists() {
+            fs::remove_dir_all(&previous).map_err(|e| {
+                ContextualError::IoError(format!("Failed to remove {}", previous.display()), e)
+            })?;
+        }
+
+        match exchange(&self.staging, served) {
+            Ok(()) => {
+                // The staging path holds the old tree now
+                return fs::rename(&self.staging, &previous).map_err(|e| {
+                    ContextualError::IoError(
+                        format!("Failed to move {} aside", self.staging.display()),
+                        e,
+                    )
+                });
+            }
+            Err(e) => log::debug!(
+                "Failed to exchange {} and {}, renaming them one after the other: {}",
+                self.staging.display(),
+                served.display(),
+                e
+            ),
+        }
+
+        fs::rename(served, &previous).map_err(|e| {
+            ContextualError::IoError(format!("Failed to move {} aside", served.display()), e)
+        })?;
+        if let Err(e) = fs::rename(&self.staging, served) {
+            // Put the old tree back so that the served path never stays empty
+            let _ = fs::rename(&previous, served);
+            return Err(ContextualError::IoError(
+                format!(
+                    "Failed to move {} to {}",
+                    self.staging.display(),
+                    served.display()
+                ),
+                e,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Flag of `renameat2` exchanging both paths, from `linux/fs.h`
+#[cfg(target_os = "linux")]
+const RENAME_EXCHANGE: libc::c_uint = 1 << 1;
+
+/// Atomically exchanges the directories `a` and `b`
+#[cfg(target_os = "linux")]
+fn exchange(a: &Path, b: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let a = CString::new(a.as_os_str().as_bytes())?;
+    let b = CString::new(b.as_os_str().as_bytes())?;
+    // glibc only gained a wrapper of renameat2 in 2.28, so the syscall is made directly
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            libc::AT_FDCWD,
+            a.as_ptr(),
+            libc::AT_FDCWD,
+            b.as_ptr(),
+            RENAME_EXCHANGE,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Atomically exchanges the directories `a` and `b`, which isn't supported on this platform
+#[cfg(not(target_os = "linux"))]
+fn exchange(_a: &Path, _b: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "exchanging directories is only supported on Linux",
+    ))
+}
+
+/// Publishes the staging directory, see `Publisher::publish`.
+///
+/// Requests have to present the `--publish-token` as bearer token, regardless of `--auth`.
+pub async fn publish(req: HttpRequest) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let publisher = match &conf.publisher {
+        Some(publisher) => publisher.clone(),
+        None => return HttpResponse::NotFound().finish(),
+    };
+    if !publisher.is_authorized(&req) {
+        log::warn!("Rejected publish request without a valid token");
+        return HttpResponse::Unauthorized()
+            .header(header::WWW_AUTHENTICATE, "Bearer")
+            .body("Missing or invalid publish token\n");
+    }
+    let served = conf.path.clone();
+
+    let result = web::block(move || publisher.publish(&served))
+        .await
+        .map_err(ContextualError::from);
+
+    match result {
+        Ok(()) => {
+            if let Some(dir_sizes) = &conf.dir_sizes {
+                dir_sizes.clear();
+            }
+            if let Some(listing_cache) = &conf.listing_cache {
+                listing_cache.clear();
+            }
+            if let Some(public_stats) = &conf.public_stats {
+                public_stats.clear();
+            }
+            if let Some(duplicates) = &conf.duplicates {
+                duplicates.clear();
+            }
+            log::info!("Published {}", conf.path.display());
+            HttpResponse::Ok().body("Published\n")
+        }
+        Err(e) => {
+            let status = match e {
+                ContextualError::InvalidPathError(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            errors::log_error_chain(e.to_string());
+            HttpResponse::build(status).body(format!("{}\n", e))
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use qrcodegen::{QrCode, QrCodeEcc};
+use yansi::{Color, Paint};
+
+/// Modules of light border around the code, which scanners need to find it
+const QUIET_ZONE: i32 = 2;
+
+/// Renders a QR code of `url` for the terminal, or `None` if it is too long.
+///
+/// Each character shows two rows of modules with half blocks. The code is printed in black and
+/// white regardless of the colors of the terminal, as scanners expect dark modules on light.
+pub fn to_terminal(url: &str) -> Option<String> {
+    let qr = QrCode::encode_text(url, QrCodeEcc::Low).ok()?;
+    let range = -QUIET_ZONE..qr.size() + QUIET_ZONE;
+    let is_light = |x: i32, y: i32| !qr.get_module(x, y);
+
+    let mut lines = vec![];
+    for y in range.clone().step_by(2) {
+        let line = range
+            .clone()
+            .map(|x| match (is_light(x, y), is_light(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            })
+            .collect::<String>();
+        lines.push(
+            Paint::new(line)
+                .fg(Color::White)
+                .bg(Color::Black)
+                .to_string(),
+        );
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn terminal_code_has_two_rows_per_line() {
+        Paint::disable();
+        let code = to_terminal("http://192.168.0.10:8080/c789b6").unwrap();
+        let size = QrCode::encode_text("http://192.168.0.10:8080/c789b6", QrCodeEcc::Low)
+            .unwrap()
+            .size()
+            + 2 * QUIET_ZONE;
+
+        let lines = code.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len() as i32, (size + 1) / 2);
+        assert!(lines.iter().all(|line| line.chars().count() as i32 == size));
+        assert!(lines[0].chars().all(|c| c == '█'));
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::Path;
+
+use crate::errors::ContextualError;
+
+/// Most characters of a random route
+const MAX_LENGTH: usize = 128;
+
+/// Returns whether `c` can be used in a URL without being encoded
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~".contains(c)
+}
+
+/// Parses the length of `--random-route-length`
+pub fn parse_length(src: &str) -> Result<usize, ContextualError> {
+    match src.parse::<usize>() {
+        Ok(length) if (1..=MAX_LENGTH).contains(&length) => Ok(length),
+        _ => Err(ContextualError::ParseError(
+            "random route length".to_string(),
+            format!("{} is not a number from 1 to {}", src, MAX_LENGTH),
+        )),
+    }
+}
+
+/// Parses the characters of `--random-route-alphabet`, which have to be distinct and usable
+/// in URLs as they are
+pub fn parse_alphabet(src: &str) -> Result<String, ContextualError> {
+    let parse_error =
+        |cause: String| ContextualError::ParseError("random route alphabet".to_string(), cause);
+
+    let mut alphabet = Vec::new();
+    for c in src.chars() {
+        if !is_unreserved(c) {
+            return Err(parse_error(format!(
+                "{} can't be used in URLs, only letters, digits, '-', '.', '_' and '~' can",
+                c
+            )));
+        }
+        if alphabet.contains(&c) {
+            return Err(parse_error(format!("{} is given more than once", c)));
+        }
+        alphabet.push(c);
+    }
+    if alphabet.len() < 2 {
+        return Err(parse_error(
+            "at least two characters are needed".to_string(),
+        ));
+    }
+    Ok(src.to_string())
+}
+
+/// Generates a random route of `length` characters of `alphabet`
+pub fn generate(length: usize, alphabet: &str) -> String {
+    let alphabet = alphabet.chars().collect::<Vec<_>>();
+    nanoid::nanoid!(length, &alphabet)
+}
+
+/// Returns the route saved in `file`, so that it stays the same across restarts. If there is
+/// no such file yet, a route is generated and saved there.
+pub fn load_or_create(
+    file: &Path,
+    length: usize,
+    alphabet: &str,
+) -> Result<String, ContextualError> {
+    if file.exists() {
+        let route = fs::read_to_string(file).map_err(|e| {
+            ContextualError::IoError(format!("Failed to read random route {}", file.display()), e)
+        })?;
+        let route = route.trim();
+        if route.is_empty() || !route.chars().all(is_unreserved) {
+            return Err(ContextualError::ParseError(
+                format!("random route of {}", file.display()),
+                "routes can only contain letters, digits, '-', '.', '_' and '~'".to_string(),
+            ));
+        }
+        return Ok(route.to_string());
+    }
+
+    let route = generate(length, alphabet);
+    fs::write(file, format!("{}\n", route)).map_err(|e| {
+        ContextualError::IoError(format!("Failed to save random route {}", file.display()), e)
+    })?;
+    Ok(route)
+}
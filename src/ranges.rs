@@ -0,0 +1,157 @@
+use actix_files::{HttpRange, NamedFile};
+use actix_web::dev::{ServiceRequest, SizedStream};
+use actix_web::http::header::{self, EntityTag, HttpDate, IfRange};
+use actix_web::http::{HeaderValue, Method, StatusCode};
+use actix_web::web::{self, Bytes};
+use actix_web::{Error, HttpMessage, HttpResponse};
+use futures::stream::{self, Stream, StreamExt};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Component;
+
+use crate::mime_map;
+use crate::path_policy;
+
+/// Largest piece of a range read from the file at once
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Most ranges served in one response, requests for more get the whole file
+const MAX_RANGES: usize = 64;
+
+/// Returns whether a response with the tag `etag`, last modified at `last_modified`, is still
+/// the one `If-Range` asks the ranges of.
+///
+/// Only strong tags and exact dates match, as ranges of different versions can't be combined.
+fn if_range_matches(
+    if_range: &IfRange,
+    etag: Option<EntityTag>,
+    last_modified: Option<HttpDate>,
+) -> bool {
+    match if_range {
+        IfRange::EntityTag(tag) => etag.map_or(false, |etag| etag.strong_eq(tag)),
+        IfRange::Date(date) => last_modified.map_or(false, |last_modified| last_modified == *date),
+    }
+}
+
+/// Streams the part of `file` given by `range`
+fn read_range(file: File, range: HttpRange) -> impl Stream<Item = Result<Bytes, Error>> {
+    stream::try_unfold(
+        (file, range.start, range.length),
+        |(file, offset, remaining)| async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+            let len = remaining.min(CHUNK_SIZE);
+            let (file, chunk) = web::block(move || -> io::Result<_> {
+                let mut file = file;
+                let mut chunk = vec![0; len as usize];
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut chunk)?;
+                Ok((file, chunk))
+            })
+            .await?;
+            Ok::<_, Error>(Some((
+                Bytes::from(chunk),
+                (file, offset + len, remaining - len),
+            )))
+        },
+    )
+}
+
+/// Checks `If-Range` and serves requests for several ranges of a file as
+/// `multipart/byteranges`, which the files service doesn't support.
+///
+/// If `If-Range` doesn't match the file, `Range` is removed from the request, so that the
+/// whole file is served. Returns the response to send instead if several ranges are requested.
+pub fn check_request(req: &mut ServiceRequest) -> Option<HttpResponse> {
+    if req.method() != Method::GET || !req.headers().contains_key(header::RANGE) {
+        return None;
+    }
+    let conf = req
+        .app_data::<crate::MiniserveConfig>()?
+        .for_path(req.path());
+    let relative_path = path_policy::requested_file(req)?;
+    let is_hidden = relative_path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    if is_hidden && !conf.show_hidden {
+        return None;
+    }
+    let path = path_policy::resolve_request_path(&conf.path, &relative_path).ok()?;
+
+    // The files service sets the validators, and checks the other preconditions, without
+    // reading the file yet
+    let file_res = NamedFile::open(&path)
+        .ok()?
+        .prefer_utf8(true)
+        .into_response(req.request())
+        .ok()?;
+    let etag = file_res.headers().get(header::ETAG).cloned();
+    let last_modified = file_res.headers().get(header::LAST_MODIFIED).cloned();
+
+    if let Some(if_range) = req.get_header::<IfRange>() {
+        let parse = |value: &Option<HeaderValue>| value.as_ref()?.to_str().ok()?.parse().ok();
+        if !if_range_matches(&if_range, parse(&etag), parse(&last_modified)) {
+            req.headers_mut().remove(header::RANGE);
+            return None;
+        }
+    }
+
+    let size = path.metadata().ok()?.len();
+    let ranges = req
+        .headers()
+        .get(header::RANGE)?
+        .to_str()
+        .ok()
+        .and_then(|range| HttpRange::parse(range, size).ok())?;
+    if ranges.len() > MAX_RANGES {
+        req.headers_mut().remove(header::RANGE);
+        return None;
+    }
+    if ranges.len() < 2 || !matches!(file_res.status(), StatusCode::PARTIAL_CONTENT) {
+        return None;
+    }
+
+    let content_type = mime_map::content_type(req)
+        .or_else(|| file_res.headers().get(header::CONTENT_TYPE).cloned())
+        .and_then(|content_type| content_type.to_str().ok().map(str::to_string))
+        .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM.to_string());
+    let boundary = nanoid::nanoid!();
+    let file = File::open(&path).ok()?;
+
+    let mut parts = vec![];
+    let mut length = 0;
+    for range in ranges {
+        let head = format!(
+            "--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+            boundary,
+            content_type,
+            range.start,
+            range.start + range.length - 1,
+            size
+        );
+        length += head.len() as u64 + range.length + 2;
+        parts.push(
+            stream::once(async move { Ok(Bytes::from(head)) })
+                .chain(read_range(file.try_clone().ok()?, range))
+                .chain(stream::once(async { Ok(Bytes::from_static(b"\r\n")) })),
+        );
+    }
+    let end = format!("--{}--\r\n", boundary);
+    length += end.len() as u64;
+    let body = stream::iter(parts)
+        .flatten()
+        .chain(stream::once(async move { Ok(Bytes::from(end)) }));
+
+    let mut res = HttpResponse::build(StatusCode::PARTIAL_CONTENT);
+    res.header(header::ACCEPT_RANGES, "bytes")
+        .content_type(format!("multipart/byteranges; boundary={}", boundary));
+    if let Some(etag) = etag {
+        res.header(header::ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        res.header(header::LAST_MODIFIED, last_modified);
+    }
+    Some(res.body(SizedStream::new(length, Box::pin(body))))
+}
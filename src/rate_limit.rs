@@ -0,0 +1,167 @@
+use actix_web::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::rt::time::{delay_for, Delay};
+use actix_web::web::Bytes;
+use actix_web::Error;
+use bytesize::ByteSize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::errors::ContextualError;
+use crate::path_policy;
+
+/// Largest piece of a body sent at once, so that even slow rates are kept smoothly
+const PIECE_SIZE: usize = 16 * 1024;
+
+/// Parses a rate like `500K`, `1.5 MiB` or `2MB/s` into bytes per second
+pub fn parse_rate(src: &str) -> Result<u64, ContextualError> {
+    let parse_error = |cause: String| ContextualError::ParseError("rate".to_string(), cause);
+
+    let rate = src.trim().trim_end_matches("/s").trim_end_matches("/S");
+    match rate.parse::<ByteSize>() {
+        Ok(ByteSize(0)) => Err(parse_error("rate must be greater than 0".to_string())),
+        Ok(ByteSize(rate)) => Ok(rate),
+        Err(_) => Err(parse_error(format!(
+            "{} is not a size like 500K or 2MiB",
+            src
+        ))),
+    }
+}
+
+/// When the bytes sent at `rate` bytes per second may go out
+struct Schedule {
+    rate: u64,
+    next: Instant,
+}
+
+impl Schedule {
+    fn new(rate: u64) -> Self {
+        Schedule {
+            rate,
+            next: Instant::now(),
+        }
+    }
+
+    /// Reserves the time to send `len` bytes and returns when they may be sent.
+    ///
+    /// Time in which nothing was sent isn't saved up, so that idle periods don't allow bursts.
+    fn reserve(&mut self, len: usize) -> Instant {
+        let at = self.next.max(Instant::now());
+        self.next = at + Duration::from_secs_f64(len as f64 / self.rate as f64);
+        at
+    }
+}
+
+/// Limit shared by all downloads, set by `--rate-limit-total`
+#[derive(Clone)]
+pub struct TotalRateLimit(Arc<Mutex<Schedule>>);
+
+impl TotalRateLimit {
+    pub fn new(rate: u64) -> Self {
+        TotalRateLimit(Arc::new(Mutex::new(Schedule::new(rate))))
+    }
+}
+
+/// Body passing the pieces of `body` on no faster than its own and the total limit allow
+struct Throttled {
+    body: ResponseBody<Body>,
+
+    /// Limit of this download, if any
+    schedule: Option<Schedule>,
+
+    /// Limit of all downloads, if any
+    total: Option<TotalRateLimit>,
+
+    /// Rest of the last chunk of `body`, which is sent in pieces
+    rest: Option<Bytes>,
+
+    /// Piece waiting for `delay` to be sent
+    waiting: Option<(Bytes, Pin<Box<Delay>>)>,
+}
+
+impl MessageBody for Throttled {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.get_mut();
+
+        if let Some((_, delay)) = &mut this.waiting {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            let (piece, _) = this.waiting.take().unwrap();
+            return Poll::Ready(Some(Ok(piece)));
+        }
+
+        let mut piece = match this.rest.take() {
+            Some(rest) => rest,
+            None => match Pin::new(&mut this.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => chunk,
+                other => return other,
+            },
+        };
+        if piece.len() > PIECE_SIZE {
+            this.rest = Some(piece.split_off(PIECE_SIZE));
+        }
+
+        let own = this
+            .schedule
+            .as_mut()
+            .map(|schedule| schedule.reserve(piece.len()));
+        let total = this
+            .total
+            .as_ref()
+            .map(|total| total.0.lock().unwrap().reserve(piece.len()));
+        let now = Instant::now();
+        let at = own.into_iter().chain(total).max().unwrap_or(now);
+        if at <= now {
+            return Poll::Ready(Some(Ok(piece)));
+        }
+
+        let mut delay = Box::pin(delay_for(at - now));
+        if delay.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Some(Ok(piece)));
+        }
+        this.waiting = Some((piece, delay));
+        Poll::Pending
+    }
+}
+
+/// Returns whether the response to `req` has to be limited, i.e. it serves a file and one of
+/// `--rate-limit` or `--rate-limit-total` is given
+pub fn is_limited(req: &ServiceRequest) -> bool {
+    req.app_data::<crate::MiniserveConfig>()
+        .map_or(false, |conf| {
+            conf.rate_limit.is_some() || conf.rate_limit_total.is_some()
+        })
+        && path_policy::requested_file(req).is_some()
+}
+
+/// Sends the file served by `res` no faster than the configured limits
+pub fn apply(res: ServiceResponse, limited: bool) -> ServiceResponse {
+    if !limited || !matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT) {
+        return res;
+    }
+    let conf = match res.request().app_data::<crate::MiniserveConfig>() {
+        Some(conf) => conf,
+        None => return res,
+    };
+    let schedule = conf.rate_limit.map(Schedule::new);
+    let total = conf.rate_limit_total.clone();
+
+    res.map_body(|_, body| {
+        ResponseBody::Body(Body::from_message(Throttled {
+            body,
+            schedule,
+            total,
+            rest: None,
+            waiting: None,
+        }))
+    })
+}
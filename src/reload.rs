@@ -0,0 +1,99 @@
+use std::env;
+use std::sync::{Arc, RwLock};
+
+use crate::args::CliArgs;
+use crate::auth::RequiredAuth;
+use crate::config_file;
+use crate::errors::{self, ContextualError};
+use crate::listing_cache::ListingCache;
+
+/// Settings which are read from the config file again on SIGHUP, while the server keeps running
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Enable HTTP basic authentication
+    pub auth: Vec<RequiredAuth>,
+
+    /// Default color scheme
+    pub default_color_scheme: String,
+
+    /// Default dark mode color scheme
+    pub default_color_scheme_dark: String,
+}
+
+impl Settings {
+    pub fn from_args(args: &CliArgs) -> Self {
+        Settings {
+            auth: args.auth.clone(),
+            default_color_scheme: args.color_scheme.clone(),
+            default_color_scheme_dark: args.color_scheme_dark.clone(),
+        }
+    }
+}
+
+/// The current `Settings`, shared by all workers and served directories
+#[derive(Clone)]
+pub struct Reloadable(Arc<RwLock<Arc<Settings>>>);
+
+impl Reloadable {
+    pub fn new(settings: Settings) -> Self {
+        Reloadable(Arc::new(RwLock::new(Arc::new(settings))))
+    }
+
+    /// Returns the current settings, which stay the same for the caller even if they are
+    /// reloaded meanwhile
+    pub fn get(&self) -> Arc<Settings> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Parses the command line and the config file again and takes their settings.
+    ///
+    /// Authentication can only be changed, not turned on or off, since that decides how the
+    /// server is set up.
+    fn reload(&self) -> Result<(), ContextualError> {
+        let args = config_file::parse_args(env::args_os().collect())
+            .map_err(|e| ContextualError::ConfigFileError(e.message))?;
+        let settings = Settings::from_args(&args);
+        if settings.auth.is_empty() != self.get().auth.is_empty() {
+            return Err(ContextualError::ConfigFileError(
+                "authentication can't be turned on or off without a restart".to_string(),
+            ));
+        }
+        *self.0.write().unwrap() = Arc::new(settings);
+        Ok(())
+    }
+
+    /// Reloads the settings whenever miniserve receives SIGHUP. Connections are kept, requests
+    /// being handled finish with the settings they started with.
+    ///
+    /// The listings in `listing_cache` are dropped, as they were rendered with the old theme.
+    #[cfg(unix)]
+    pub fn spawn_on_hangup(&self, listing_cache: Option<ListingCache>) {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+
+        let reloadable = self.clone();
+        actix_web::rt::spawn(async move {
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    let e = ContextualError::IoError("Failed to listen for SIGHUP".to_string(), e);
+                    errors::log_error_chain(e.to_string());
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                match reloadable.reload() {
+                    Ok(()) => {
+                        if let Some(listing_cache) = &listing_cache {
+                            listing_cache.clear();
+                        }
+                        log::info!("Reloaded the config file");
+                    }
+                    Err(e) => errors::log_error_chain(e.to_string()),
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    pub fn spawn_on_hangup(&self, _listing_cache: Option<ListingCache>) {}
+}
@@ -0,0 +1,147 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::web::{self, Bytes};
+use actix_web::{HttpRequest, HttpResponse};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::Path;
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::listing;
+use crate::path_policy;
+use crate::renderer;
+
+/// Renames the entry `from` of `dir` to `to`, unless an entry with that name already exists
+fn rename_entry(dir: &Path, from: &OsStr, to: &OsStr) -> Result<(), ContextualError> {
+    let target = dir.join(to);
+    if target.symlink_metadata().is_ok() {
+        return Err(ContextualError::EntryExistsError(
+            to.to_string_lossy().to_string(),
+        ));
+    }
+
+    fs::rename(dir.join(from), &target).map_err(|e| {
+        ContextualError::IoError(
+            format!(
+                "Failed to rename {} to {}",
+                from.to_string_lossy(),
+                to.to_string_lossy()
+            ),
+            e,
+        )
+    })
+}
+
+/// Renames an entry of a directory
+///
+/// Like for uploads, the directory is given by the `path` query parameter. The form data
+/// contains the current name `from` and the new name `to` of the entry, which must both be
+/// plain names of entries the listing would show.
+pub async fn rename(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let return_path = req
+        .headers()
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok())
+        .unwrap_or("/")
+        .to_string();
+    let error_response = |error: ContextualError, status: StatusCode| {
+        errors::log_error_chain(error.to_string());
+        HttpResponse::build(status)
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &error.to_string(),
+                    status,
+                    &return_path,
+                    None,
+                    None,
+                    true,
+                    conf.random_route.is_none(),
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            )
+    };
+
+    let mut from = None;
+    let mut to = None;
+    for (key, value) in form_urlencoded::parse(&body) {
+        match key.as_ref() {
+            "from" => from = Some(OsString::from(value.into_owned())),
+            "to" => to = Some(OsString::from(value.into_owned())),
+            _ => {}
+        }
+    }
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(
+                    "Missing 'from' or 'to' field".to_string(),
+                ),
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+
+    // The directory has to be inside the served directory, like the target of uploads
+    let query_params = listing::extract_query_parameters(&req);
+    let dir_path = query_params.path.unwrap_or_default();
+    let dir = match path_policy::resolve_request_path(&conf.path, &dir_path) {
+        Ok(dir) if dir.is_dir() => dir,
+        _ => {
+            return error_response(
+                ContextualError::InvalidHttpRequestError(
+                    "Invalid value for 'path' parameter".to_string(),
+                ),
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+
+    // Entries hidden from the listing, also by their directory or ignore files, must stay so
+    if !listing::is_listed_entry(&dir, &from, conf) {
+        return error_response(
+            ContextualError::InvalidHttpRequestError(format!(
+                "Invalid entry '{}'",
+                from.to_string_lossy()
+            )),
+            StatusCode::BAD_REQUEST,
+        );
+    }
+    // The renamed entry must stay visible in the listing
+    let is_valid_name = path_policy::is_entry_name(Path::new(&to))
+        && (conf.show_hidden || !to.to_string_lossy().starts_with('.'))
+        && !listing::Annotations::is_reserved_name(&to);
+    if !is_valid_name {
+        return error_response(
+            ContextualError::InvalidPathError(format!(
+                "'{}' is not a valid name",
+                to.to_string_lossy()
+            )),
+            StatusCode::BAD_REQUEST,
+        );
+    }
+
+    log::info!(
+        "Renaming {} to {} in {}",
+        from.to_string_lossy(),
+        to.to_string_lossy(),
+        dir.display()
+    );
+    let result = web::block(move || rename_entry(&dir, &from, &to)).await;
+    match result.map_err(ContextualError::from) {
+        Ok(()) => HttpResponse::SeeOther()
+            .header(header::LOCATION, return_path.as_str())
+            .finish(),
+        Err(e @ ContextualError::EntryExistsError(_)) => error_response(e, StatusCode::CONFLICT),
+        Err(e) => error_response(e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
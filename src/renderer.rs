@@ -1,36 +1,92 @@
 use actix_web::http::StatusCode;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, TimeZone, Utc};
 use chrono_humanize::Humanize;
 use maud::{html, Markup, PreEscaped, DOCTYPE};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::fmt::Display;
 use std::time::SystemTime;
 use structopt::clap::{crate_name, crate_version};
 use strum::IntoEnumIterator;
 
 use crate::archive::ArchiveMethod;
-use crate::listing::{Breadcrumb, Entry, SortingMethod, SortingOrder};
+use crate::duplicates::DuplicateGroup;
+use crate::extract::ExtractFormat;
+use crate::file_info::{FileCompression, FileInfo};
+use crate::i18n::{fill, Language, Strings};
+use crate::listing::{
+    Annotations, Breadcrumb, Entry, ListingView, Pagination, QueryParameters, SortingMethod,
+    SortingOrder, Totals,
+};
+use crate::public_stats::ShareStats;
+use crate::suggestions::Suggestion;
+use crate::templates::{
+    EntryContext, ErrorContext, LinkContext, ListingContext, Templates, TotalsContext,
+};
+use crate::MiniserveConfig;
 
 /// Renders the file listing
 #[allow(clippy::too_many_arguments)]
 pub fn page(
     entries: Vec<Entry>,
     is_root: bool,
-    sort_method: Option<SortingMethod>,
-    sort_order: Option<SortingOrder>,
-    show_qrcode: bool,
-    file_upload: bool,
-    upload_route: &str,
-    favicon_route: &str,
-    css_route: &str,
-    default_color_scheme: &str,
-    default_color_scheme_dark: &str,
-    encoded_dir: &str,
+    query_params: QueryParameters,
+    pagination: Option<Pagination>,
+    totals: Totals,
+    annotations: Annotations,
+    archives_allowed: bool,
     breadcrumbs: Vec<Breadcrumb>,
-    tar_enabled: bool,
-    tar_gz_enabled: bool,
-    zip_enabled: bool,
-    hide_version_footer: bool,
+    encoded_dir: &str,
+    origin: &str,
+    lang: Language,
+    conf: &MiniserveConfig,
 ) -> Markup {
-    let upload_action = build_upload_action(upload_route, encoded_dir, sort_method, sort_order);
+    let strings = lang.strings();
+    let upload_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/upload", random_route),
+        None => "/upload".to_string(),
+    };
+    let (sort_method, sort_order) = (query_params.sort, query_params.order);
+    let upload_action = build_upload_action(&upload_route, encoded_dir, sort_method, sort_order);
+    let download_action = match conf.random_route {
+        Some(ref random_route) => format!("/{}/download?path={}", random_route, encoded_dir),
+        None => format!("/download?path={}", encoded_dir),
+    };
+    let create_file_action = match conf.random_route {
+        Some(ref random_route) => format!(
+            "/{}/__miniserve/create-file?path={}",
+            random_route, encoded_dir
+        ),
+        None => format!("/__miniserve/create-file?path={}", encoded_dir),
+    };
+    let rename_action = match conf.random_route {
+        Some(ref random_route) => {
+            format!("/{}/__miniserve/rename?path={}", random_route, encoded_dir)
+        }
+        None => format!("/__miniserve/rename?path={}", encoded_dir),
+    };
+    let extract_action = match conf.random_route {
+        Some(ref random_route) => {
+            format!("/{}/__miniserve/extract?path={}", random_route, encoded_dir)
+        }
+        None => format!("/__miniserve/extract?path={}", encoded_dir),
+    };
+    let archives_enabled = archives_allowed
+        && (conf.tar_enabled || conf.tar_gz_enabled || conf.zip_enabled || conf.seven_zip_enabled);
+    let transfer_action = match conf.random_route {
+        Some(ref random_route) => format!(
+            "/{}/__miniserve/transfer?path={}",
+            random_route, encoded_dir
+        ),
+        None => format!("/__miniserve/transfer?path={}", encoded_dir),
+    };
+
+    // Links are absolute, so that they work whether or not the URL ends on a slash
+    let parent_link = breadcrumbs
+        .iter()
+        .rev()
+        .nth(1)
+        .map(|el| el.link.clone())
+        .unwrap_or_else(|| "../".to_string());
 
     let title_path = breadcrumbs
         .iter()
@@ -38,17 +94,116 @@ pub fn page(
         .collect::<Vec<_>>()
         .join("/");
 
+    if let Some(templates) = &conf.templates {
+        let context = ListingContext {
+            title: title_path.clone(),
+            breadcrumbs: breadcrumbs
+                .iter()
+                .map(|el| LinkContext {
+                    name: el.name.clone(),
+                    link: parametrized_link(&el.link, sort_method, sort_order),
+                })
+                .collect(),
+            parent_link: if is_root {
+                None
+            } else {
+                Some(parametrized_link(&parent_link, sort_method, sort_order))
+            },
+            entries: entries
+                .iter()
+                .map(|entry| EntryContext {
+                    name: entry.name.clone(),
+                    link: if entry.is_dir() {
+                        parametrized_link(&entry.link, sort_method, sort_order)
+                    } else {
+                        entry.link.clone()
+                    },
+                    is_dir: entry.is_dir(),
+                    is_symlink: entry.is_symlink,
+                    download_name: entry.download_name.clone(),
+                    checksum: entry.checksum.clone(),
+                    mode: entry
+                        .permissions
+                        .as_ref()
+                        .map(|permissions| permissions.mode.clone()),
+                    owner: entry
+                        .permissions
+                        .as_ref()
+                        .map(|permissions| permissions.owner.clone()),
+                    group: entry
+                        .permissions
+                        .as_ref()
+                        .map(|permissions| permissions.group.clone()),
+                    size: entry.size.map(|size| size.to_string()),
+                    size_bytes: entry.size.map(|size| size.as_u64()),
+                    modified: entry
+                        .last_modification_date
+                        .map(|date| DateTime::<Utc>::from(date).timestamp()),
+                })
+                .collect(),
+            totals: TotalsContext {
+                files: totals.files,
+                directories: totals.directories,
+                size: totals.size.to_string(),
+            },
+            upload_action: if conf.file_upload {
+                Some(upload_action.clone())
+            } else {
+                None
+            },
+            archives: ArchiveMethod::iter()
+                .filter(|archive_method| {
+                    archives_allowed
+                        && archive_method.is_enabled(
+                            conf.tar_enabled,
+                            conf.tar_gz_enabled,
+                            conf.zip_enabled,
+                            conf.seven_zip_enabled,
+                        )
+                })
+                .map(|archive_method| LinkContext {
+                    name: archive_method.extension(),
+                    link: archive_link(archive_method, sort_method, sort_order),
+                })
+                .collect(),
+            header_text: conf.header_text.clone(),
+            header: annotations.header.clone(),
+            footer: annotations.footer.clone(),
+            favicon_route: format!("/{}", conf.favicon_route),
+            css_route: format!("/{}", conf.css_route),
+            nonce: conf.nonce_placeholder.clone(),
+            version: version(conf.hide_version_footer),
+        };
+        if let Some(page) = templates.render("listing.html", &context) {
+            return page;
+        }
+    }
+
+    // In the gallery, images are taken out of the table
+    let gallery = query_params.view == Some(ListingView::Gallery);
+    let (images, entries): (Vec<Entry>, Vec<Entry>) = if gallery {
+        entries.into_iter().partition(Entry::is_image)
+    } else {
+        (Vec::new(), entries)
+    };
+
     html! {
         (DOCTYPE)
-        html {
-            (page_header(&title_path, file_upload, favicon_route, css_route))
+        html lang=(lang) {
+            (page_header(
+                &title_path,
+                Some(conf.nonce_placeholder.as_str()).filter(|_| conf.file_upload),
+                &conf.favicon_route,
+                &conf.css_route,
+                Some(link_preview(&title_path, &totals_summary(totals, strings), &format!("{}/{}", origin, conf.favicon_route))),
+            ))
 
             body#drop-container
-                .(format!("default_theme_{}", default_color_scheme))
-                .(format!("default_theme_dark_{}", default_color_scheme_dark)) {
+                .(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
 
-                (PreEscaped(r#"
-                    <script>
+                script nonce=(conf.nonce_placeholder) {
+                    (PreEscaped(r#"
                         // read theme from local storage and apply it to body
                         const body = document.body;
                         var theme = localStorage.getItem('theme');
@@ -68,17 +223,25 @@ pub fn page(
 
                             localStorage.setItem('theme', name);
                         }
-                    </script>
+
+                        document.addEventListener('click', function(e) {
+                            const link = e.target.closest('a[data-theme]');
+                            if (link != null) {
+                                e.preventDefault();
+                                updateColorScheme(link.dataset.theme);
+                            }
+                        });
                     "#))
+                }
 
-                @if file_upload {
+                @if conf.file_upload {
                     div.drag-form {
                         div.drag-title {
-                            h1 { "Drop your file here to upload it" }
+                            h1 { (strings.drop_to_upload) }
                         }
                     }
                 }
-                (color_scheme_selector(show_qrcode))
+                (color_scheme_selector(conf.show_qrcode, strings))
                 div.container {
                     span#top { }
                     h1.title dir="ltr" {
@@ -94,54 +257,137 @@ pub fn page(
                             "/"
                         }
                     }
+                    @if let Some(header_text) = &conf.header_text {
+                        div.annotation.header-text { (PreEscaped(header_text)) }
+                    }
                     div.toolbar {
-                        @if tar_enabled || tar_gz_enabled || zip_enabled {
+                        div.filter {
+                            input#filter type="search" placeholder=(strings.filter_entries) aria-label=(strings.filter_entries) autocomplete="off";
+                        }
+                        div.view {
+                            @if gallery {
+                                a.view-toggle href=(view_link(false, sort_method, sort_order)) { (strings.table_view) }
+                            } @else {
+                                a.view-toggle href=(view_link(true, sort_method, sort_order)) { (strings.gallery_view) }
+                            }
+                        }
+                        @if archives_enabled || conf.allow_move {
                             div.download {
-                                @for archive_method in ArchiveMethod::iter() {
-                                    @if archive_method.is_enabled(tar_enabled, tar_gz_enabled, zip_enabled) {
-                                        (archive_button(archive_method, sort_method, sort_order))
+                                @if archives_enabled {
+                                    @for archive_method in ArchiveMethod::iter() {
+                                        @if archive_method.is_enabled(conf.tar_enabled, conf.tar_gz_enabled, conf.zip_enabled, conf.seven_zip_enabled) {
+                                            (archive_button(archive_method, sort_method, sort_order, strings))
+                                        }
+                                    }
+                                }
+                                // The selected entries are submitted to the route of the clicked button
+                                form#download-selected action=(if archives_enabled { &download_action } else { &transfer_action }) method="POST" {
+                                    @if archives_enabled {
+                                        select name="method" aria-label=(strings.archive_format) {
+                                            @for archive_method in ArchiveMethod::iter() {
+                                                @if archive_method.is_enabled(conf.tar_enabled, conf.tar_gz_enabled, conf.zip_enabled, conf.seven_zip_enabled) {
+                                                    option value=(archive_method) { "." (archive_method.extension()) }
+                                                }
+                                            }
+                                        }
+                                        button type="submit" formaction=(download_action) { (strings.download_selected) }
+                                    }
+                                    @if conf.allow_move {
+                                        input.destination type="text" name="destination" placeholder=(strings.destination) aria-label=(strings.destination);
+                                        button type="submit" name="operation" value="move" formaction=(transfer_action) { (strings.move_selected) }
+                                        button type="submit" name="operation" value="copy" formaction=(transfer_action) { (strings.copy_selected) }
                                     }
                                 }
                             }
                         }
-                        @if file_upload {
+                        @if conf.file_upload {
                             div.upload {
                                 form id="file_submit" action=(upload_action) method="POST" enctype="multipart/form-data" {
-                                    p { "Select a file to upload or drag it anywhere into the window" }
+                                    p { (strings.select_to_upload) }
                                     div {
                                         input#file-input type="file" name="file_to_upload" required="" multiple {}
-                                        button type="submit" { "Upload file" }
+                                        button type="submit" { (strings.upload_file) }
                                     }
                                 }
+                                form#create-file action=(create_file_action) method="POST" {
+                                    input type="text" name="name" required="" placeholder=(strings.file_name) aria-label=(strings.file_name);
+                                    button type="submit" { (strings.create_file) }
+                                }
                             }
                         }
                     }
+                    @if let Some(header) = &annotations.header {
+                        div.annotation.header { (PreEscaped(header)) }
+                    }
+                    @if !images.is_empty() {
+                        (gallery_grid(&images))
+                    }
                     table {
                         thead {
-                            th.name { (build_link("name", "Name", sort_method, sort_order)) }
-                            th.size { (build_link("size", "Size", sort_method, sort_order)) }
-                            th.date { (build_link("date", "Last modification", sort_method, sort_order)) }
+                            th.name {
+                                (build_link("name", strings.name, sort_method, sort_order, strings))
+                                (build_link("extension", strings.extension, sort_method, sort_order, strings))
+                            }
+                            th.size { (build_link("size", strings.size, sort_method, sort_order, strings)) }
+                            th.date { (build_link("date", strings.last_modification, sort_method, sort_order, strings)) }
+                            @if conf.show_checksums {
+                                th.checksum { (strings.checksum) }
+                            }
+                            @if conf.show_permissions {
+                                th.permissions { (strings.permissions) }
+                            }
                         }
                         tbody {
                             @if !is_root {
                                 tr {
-                                    td colspan="3" {
+                                    td colspan=(3 + conf.show_permissions as u8 + conf.show_checksums as u8) {
                                         span.root-chevron { (chevron_left()) }
-                                        a.root href=(parametrized_link("../", sort_method, sort_order)) {
-                                            "Parent directory"
+                                        a.root href=(parametrized_link(&parent_link, sort_method, sort_order)) {
+                                            (strings.parent_directory)
                                         }
                                     }
                                 }
                             }
                             @for entry in entries {
-                                (entry_row(entry, sort_method, sort_order))
+                                (entry_row(entry, sort_method, sort_order, archives_enabled || conf.allow_move, conf, strings))
                             }
                         }
                     }
+                    @if let Some(pagination) = pagination {
+                        (pagination_nav(pagination, &query_params, strings, &conf.nonce_placeholder))
+                    }
+                    @if let Some(footer) = &annotations.footer {
+                        div.annotation.footer { (PreEscaped(footer)) }
+                    }
+                    (totals_footer(totals, strings))
+                    @if conf.tree_view {
+                        (tree_view_script(&conf.nonce_placeholder))
+                    }
+                    (copy_link_script(&conf.nonce_placeholder))
+                    (filter_script(&conf.nonce_placeholder))
+                    @if gallery {
+                        (gallery_script(&conf.nonce_placeholder))
+                    }
+                    @if conf.show_checksums {
+                        (checksum_script(&conf.nonce_placeholder))
+                    }
+                    @if conf.show_qrcode {
+                        (qr_code_script(&conf.nonce_placeholder))
+                    }
+                    @if conf.allow_rename {
+                        form#rename-entry action=(rename_action) method="POST" {
+                            input type="hidden" name="from";
+                            input type="hidden" name="to";
+                        }
+                        (rename_script(&conf.nonce_placeholder))
+                    }
+                    @if conf.allow_extract {
+                        form#extract-entry action=(extract_action) method="POST" {}
+                    }
                     a.back href="#top" {
                         (arrow_up())
                     }
-                    @if !hide_version_footer {
+                    @if !conf.hide_version_footer {
                         (version_footer())
                     }
                 }
@@ -150,6 +396,59 @@ pub fn page(
     }
 }
 
+// Partial: number and size of all entries in the directory
+fn totals_footer(totals: Totals, strings: &Strings) -> Markup {
+    html! {
+        p.totals {
+            (totals_summary(totals, strings))
+        }
+    }
+}
+
+/// Describes the number and size of all entries, like "3 files, 1 directory, 2.1 KB total"
+fn totals_summary(totals: Totals, strings: &Strings) -> String {
+    let count = |count: usize, singular: &str, plural: &str| {
+        format!("{} {}", count, if count == 1 { singular } else { plural })
+    };
+    format!(
+        "{}, {}, {}",
+        count(totals.files, strings.file, strings.files),
+        count(totals.directories, strings.directory, strings.directories),
+        fill(strings.total, &[&totals.size.to_string()])
+    )
+}
+
+/// Partial: meta tags for previews of links to the page, as shown by chat apps
+///
+/// `image` has to be an absolute URL.
+fn link_preview(title: &str, description: &str, image: &str) -> Markup {
+    html! {
+        meta name="description" content=(description);
+        meta property="og:type" content="website";
+        meta property="og:site_name" content=(crate_name!());
+        meta property="og:title" content=(title);
+        meta property="og:description" content=(description);
+        meta property="og:image" content=(image);
+        meta name="twitter:card" content="summary";
+    }
+}
+
+/// Renders the Markdown of `--header-text` to HTML, keeping HTML in it as is
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(markdown));
+    html
+}
+
+/// Name and version of miniserve, unless the version footer is hidden
+fn version(hide_version_footer: bool) -> Option<String> {
+    if hide_version_footer {
+        None
+    } else {
+        Some(format!("{}/{}", crate_name!(), crate_version!()))
+    }
+}
+
 // Partial: version footer
 fn version_footer() -> Markup {
     html! {
@@ -159,6 +458,358 @@ fn version_footer() -> Markup {
     }
 }
 
+/// Partial: navigation between the pages of a paginated listing
+fn pagination_nav(
+    pagination: Pagination,
+    query_params: &QueryParameters,
+    strings: &Strings,
+    nonce: &str,
+) -> Markup {
+    let page_info = fill(
+        strings.page_of,
+        &[
+            &pagination.page.to_string(),
+            &pagination.page_count.to_string(),
+        ],
+    );
+
+    html! {
+        div.pagination {
+            @if pagination.page > 1 {
+                a.previous-page href=(page_link(pagination.page - 1, query_params)) {
+                    (strings.previous_page)
+                }
+            }
+            span.page-info {
+                (page_info)
+            }
+            @if pagination.page < pagination.page_count {
+                a.load-more href=(page_link(pagination.page + 1, query_params)) {
+                    (strings.load_more)
+                }
+                a.next-page href=(page_link(pagination.page + 1, query_params)) {
+                    (strings.next_page)
+                }
+            }
+        }
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+                // Append the rows of the next page to the current listing instead of navigating
+                document.addEventListener('click', function(e) {
+                    const link = e.target.closest('.pagination a.load-more');
+                    if (link == null) {
+                        return;
+                    }
+                    e.preventDefault();
+                    fetch(link.href)
+                        .then(response => response.text())
+                        .then(html => {
+                            const next = new DOMParser().parseFromString(html, 'text/html');
+                            const tbody = document.querySelector('table tbody');
+                            next.querySelectorAll('table tbody tr.entry').forEach(row => tbody.appendChild(row));
+                            document.querySelector('.pagination').replaceWith(next.querySelector('.pagination'));
+                        });
+                });
+            "#))
+        }
+    }
+}
+
+/// Partial: script to expand directories in place, without leaving the current listing
+fn tree_view_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            // Insert the entries of a directory right below its row, or remove them again
+            function toggleDirectory(button) {
+                const row = button.closest('tr');
+                const link = new URL(row.querySelector('a.directory').getAttribute('href'), window.location.href);
+                const depth = parseInt(row.dataset.depth || '0') + 1;
+
+                if (!row.classList.toggle('expanded')) {
+                    document.querySelectorAll('tr.entry').forEach(child => {
+                        if (child.dataset.parent && child.dataset.parent.startsWith(link.pathname)) {
+                            child.remove();
+                        }
+                    });
+                    return;
+                }
+
+                fetch(link)
+                    .then(response => response.text())
+                    .then(html => {
+                        const listing = new DOMParser().parseFromString(html, 'text/html');
+                        let previous = row;
+                        listing.querySelectorAll('table tbody tr.entry').forEach(child => {
+                            // Only entries of the listed directory itself can be downloaded together
+                            child.querySelectorAll('input.select-entry').forEach(input => input.remove());
+                            child.dataset.parent = link.pathname;
+                            child.dataset.depth = depth;
+                            child.querySelector('td').style.paddingLeft = (depth * 1.5) + 'rem';
+                            previous.after(child);
+                            previous = child;
+                        });
+                    });
+            }
+
+            document.addEventListener('click', function(e) {
+                const button = e.target.closest('button.tree-toggle');
+                if (button != null) {
+                    toggleDirectory(button);
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Partial: script to copy the absolute URL of a file to the clipboard
+fn copy_link_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            // Links are resolved against the current page, so the URL contains the random route
+            function copyLink(button) {
+                const link = button.closest('tr').querySelector('a.file');
+                navigator.clipboard.writeText(link.href).then(() => {
+                    button.classList.add('copied');
+                    setTimeout(() => button.classList.remove('copied'), 1000);
+                });
+            }
+
+            document.addEventListener('click', function(e) {
+                const button = e.target.closest('button.copy-link');
+                if (button != null) {
+                    copyLink(button);
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Partial: grid of images, opening in a full-screen lightbox
+fn gallery_grid(images: &[Entry]) -> Markup {
+    html! {
+        div.gallery {
+            @for image in images {
+                a.gallery-item href=(image.link) title=(image.name) {
+                    img src=(image.link) alt=(image.name) loading="lazy";
+                    span.gallery-name { (image.name) }
+                }
+            }
+        }
+        div#lightbox hidden {
+            img alt="";
+            span.lightbox-name { }
+        }
+    }
+}
+
+/// Partial: script to browse the images of the gallery in the lightbox with the keyboard
+fn gallery_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            let lightboxIndex = -1;
+
+            function showImage(index) {
+                // Hidden items are skipped, so that the lightbox follows the filter
+                const items = Array.from(document.querySelectorAll('.gallery-item:not([hidden])'));
+                if (items.length === 0) {
+                    return;
+                }
+                lightboxIndex = (index + items.length) % items.length;
+                const item = items[lightboxIndex];
+                const lightbox = document.querySelector('#lightbox');
+                lightbox.querySelector('img').src = item.href;
+                lightbox.querySelector('.lightbox-name').textContent = item.title;
+                lightbox.hidden = false;
+            }
+
+            function openLightbox(event, item) {
+                event.preventDefault();
+                const items = Array.from(document.querySelectorAll('.gallery-item:not([hidden])'));
+                showImage(items.indexOf(item));
+            }
+
+            function closeLightbox() {
+                document.querySelector('#lightbox').hidden = true;
+                lightboxIndex = -1;
+            }
+
+            document.addEventListener('keydown', event => {
+                if (lightboxIndex < 0) {
+                    return;
+                }
+                switch (event.key) {
+                    case 'ArrowLeft': showImage(lightboxIndex - 1); break;
+                    case 'ArrowRight': showImage(lightboxIndex + 1); break;
+                    case 'Escape': closeLightbox(); break;
+                    default: return;
+                }
+                event.preventDefault();
+            });
+
+            document.addEventListener('click', event => {
+                const item = event.target.closest('.gallery-item');
+                if (item != null) {
+                    openLightbox(event, item);
+                } else if (event.target.closest('#lightbox') != null) {
+                    closeLightbox();
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Build a link switching between the table and the gallery, keeping the sorting parameters
+fn view_link(
+    gallery: bool,
+    sort_method: Option<SortingMethod>,
+    sort_order: Option<SortingOrder>,
+) -> String {
+    let mut params = Vec::new();
+    if let (Some(sort_method), Some(sort_order)) = (sort_method, sort_order) {
+        params.push(format!("sort={}&order={}", sort_method, sort_order));
+    }
+    if gallery {
+        params.push("view=gallery".to_string());
+    }
+    format!("?{}", params.join("&"))
+}
+
+/// Partial: script to compute the checksum of a file when its button is clicked
+fn checksum_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            function computeChecksum(button) {
+                button.disabled = true;
+                fetch(button.dataset.src)
+                    .then(response => response.ok ? response.text() : Promise.reject())
+                    .then(checksum => {
+                        const code = document.createElement('code');
+                        code.title = checksum;
+                        code.textContent = checksum;
+                        button.replaceWith(code);
+                    })
+                    .catch(() => button.disabled = false);
+            }
+
+            document.addEventListener('click', function(e) {
+                const button = e.target.closest('button.compute-checksum');
+                if (button != null) {
+                    computeChecksum(button);
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Partial: script asking for the new name of an entry when its rename button is clicked
+fn rename_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            function renameEntry(button) {
+                const name = button.dataset.name;
+                const newName = prompt(button.title, name);
+                if (newName === null || newName === '' || newName === name) {
+                    return;
+                }
+                const form = document.getElementById('rename-entry');
+                form.elements.from.value = name;
+                form.elements.to.value = newName;
+                form.submit();
+            }
+
+            document.addEventListener('click', function(e) {
+                const button = e.target.closest('button.rename');
+                if (button != null) {
+                    renameEntry(button);
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Partial: script to hide the entries not matching the filter input
+fn filter_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            // Only the names are matched, case insensitively
+            function filterEntries(filter) {
+                const needle = filter.trim().toLowerCase();
+                document.querySelectorAll('table tbody tr.entry').forEach(row => {
+                    const name = row.querySelector('a.file, a.directory').textContent.toLowerCase();
+                    row.hidden = !name.includes(needle);
+                });
+                document.querySelectorAll('.gallery-item').forEach(item => {
+                    item.hidden = !item.title.toLowerCase().includes(needle);
+                });
+            }
+
+            document.querySelector('#filter').addEventListener('input', function(e) {
+                filterEntries(e.target.value);
+            });
+            "#))
+        }
+    }
+}
+
+/// Partial: script to load the QR codes of the page and of files when they are hovered
+fn qr_code_script(nonce: &str) -> Markup {
+    html! {
+        script nonce=(nonce) {
+            (PreEscaped(r#"
+            document.addEventListener('mouseover', function(e) {
+                if (e.target.closest('nav .qrcode-toggle') != null) {
+                    document.querySelector('#qrcode').src = `?qrcode=${encodeURIComponent(window.location.href)}`;
+                    return;
+                }
+                const fileQrCode = e.target.closest('.file-qrcode');
+                if (fileQrCode != null) {
+                    const img = fileQrCode.querySelector('img');
+                    img.src = img.dataset.src;
+                }
+            });
+            "#))
+        }
+    }
+}
+
+/// Build a link to the given page of a paginated listing, keeping the sorting, filtering and
+/// view parameters
+fn page_link(page: usize, query_params: &QueryParameters) -> String {
+    let mut link = format!("?page={}", page);
+    if let Some(method) = query_params.sort {
+        link = format!("{}&sort={}", link, method);
+    }
+    if let Some(order) = query_params.order {
+        link = format!("{}&order={}", link, order);
+    }
+    if let Some(entry_type) = query_params.entry_type {
+        link = format!("{}&type={}", link, entry_type);
+    }
+    if let Some(modified_since) = &query_params.modified_since {
+        link = format!(
+            "{}&modified-since={}",
+            link,
+            utf8_percent_encode(modified_since, NON_ALPHANUMERIC)
+        );
+    }
+    if query_params.view == Some(ListingView::Gallery) {
+        link = format!("{}&view=gallery", link);
+    }
+
+    link
+}
+
 /// Build the action of the upload form
 fn build_upload_action(
     upload_route: &str,
@@ -188,27 +839,27 @@ const THEME_PICKER_CHOICES: &[(&str, &str)] = &[
 pub const THEME_SLUGS: &[&str] = &["squirrel", "archlinux", "zenburn", "monokai"];
 
 /// Partial: color scheme selector
-fn color_scheme_selector(show_qrcode: bool) -> Markup {
+fn color_scheme_selector(show_qrcode: bool, strings: &Strings) -> Markup {
     html! {
         nav {
             @if show_qrcode {
                 div {
-                    p onmouseover="document.querySelector('#qrcode').src = `?qrcode=${encodeURIComponent(window.location.href)}`" {
-                        "QR code"
+                    p.qrcode-toggle {
+                        (strings.qr_code)
                     }
                     div.qrcode {
-                        img#qrcode alt="QR code" title="QR code of this page";
+                        img#qrcode alt=(strings.qr_code) title=(strings.qr_code_title);
                     }
                 }
             }
             div {
                 p {
-                    "Change theme..."
+                    (strings.change_theme)
                 }
                 ul.theme {
                     @for color_scheme in THEME_PICKER_CHOICES {
                         li.(format!("theme_{}", color_scheme.1)) {
-                            (color_scheme_link(color_scheme))
+                            (color_scheme_link(color_scheme, strings))
                         }
                     }
                 }
@@ -218,11 +869,11 @@ fn color_scheme_selector(show_qrcode: bool) -> Markup {
 }
 
 // /// Partial: color scheme link
-fn color_scheme_link(color_scheme: &(&str, &str)) -> Markup {
-    let title = format!("Switch to {} theme", color_scheme.0);
+fn color_scheme_link(color_scheme: &(&str, &str), strings: &Strings) -> Markup {
+    let title = fill(strings.switch_theme, &[color_scheme.0]);
 
     html! {
-        a href=(format!("javascript:updateColorScheme(\"{}\")", color_scheme.1)) title=(title) {
+        a href="#" data-theme=(color_scheme.1) title=(title) {
             (color_scheme.0)
         }
     }
@@ -233,8 +884,25 @@ fn archive_button(
     archive_method: ArchiveMethod,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
+    strings: &Strings,
 ) -> Markup {
-    let link = if sort_method.is_none() && sort_order.is_none() {
+    let link = archive_link(archive_method, sort_method, sort_order);
+    let text = fill(strings.download_archive, &[&archive_method.extension()]);
+
+    html! {
+        a href=(link) {
+            (text)
+        }
+    }
+}
+
+/// Build the link to download the current directory as an archive
+fn archive_link(
+    archive_method: ArchiveMethod,
+    sort_method: Option<SortingMethod>,
+    sort_order: Option<SortingOrder>,
+) -> String {
+    if sort_method.is_none() && sort_order.is_none() {
         format!("?download={}", archive_method)
     } else {
         format!(
@@ -242,14 +910,6 @@ fn archive_button(
             parametrized_link("", sort_method, sort_order,),
             archive_method
         )
-    };
-
-    let text = format!("Download .{}", archive_method.extension());
-
-    html! {
-        a href=(link) {
-            (text)
-        }
     }
 }
 
@@ -290,9 +950,10 @@ fn build_link(
     title: &str,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
+    strings: &Strings,
 ) -> Markup {
     let mut link = format!("?sort={}&order=asc", name);
-    let mut help = format!("Sort by {} in ascending order", name);
+    let mut help = fill(strings.sort_ascending, &[title]);
     let mut chevron = chevron_up();
     let mut class = "";
 
@@ -302,7 +963,7 @@ fn build_link(
             if let Some(order) = sort_order {
                 if order.to_string() == "asc" {
                     link = format!("?sort={}&order=desc", name);
-                    help = format!("Sort by {} in descending order", name);
+                    help = fill(strings.sort_descending, &[title]);
                     chevron = chevron_down();
                 }
             }
@@ -322,13 +983,25 @@ fn entry_row(
     entry: Entry,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
+    selectable: bool,
+    conf: &MiniserveConfig,
+    strings: &Strings,
 ) -> Markup {
     html! {
-        tr {
+        tr.entry {
             td {
                 p {
+                    @if selectable {
+                        input.select-entry type="checkbox" name="entry" value=(entry.name) form="download-selected" aria-label=(strings.select_entry);
+                    }
                     @if entry.is_dir() {
+                        @if conf.tree_view {
+                            button.tree-toggle type="button" title=(strings.expand_directory) {
+                                (chevron_right())
+                            }
+                        }
                         a.directory href=(parametrized_link(&entry.link, sort_method, sort_order)) {
+                            (entry_icon(&entry))
                             (entry.name) "/"
                             @if entry.is_symlink {
                                 span.symlink-symbol { }
@@ -336,10 +1009,41 @@ fn entry_row(
                         }
                     } @else if entry.is_file() {
                         div.file-entry {
-                            a.file href=(&entry.link) {
-                                (entry.name)
-                                @if entry.is_symlink {
-                                    span.symlink-symbol { }
+                            @if let Some(download_name) = &entry.download_name {
+                                a.file href=(&entry.link) download=(download_name) {
+                                    (file_link_content(&entry))
+                                }
+                            } @else {
+                                a.file href=(&entry.link) {
+                                    (file_link_content(&entry))
+                                }
+                            }
+                            button.copy-link type="button" title=(strings.copy_link) {
+                                (copy_symbol())
+                            }
+                            @if conf.show_qrcode {
+                                (file_qr_code(&entry, conf, strings))
+                            }
+                            @if conf.show_file_info {
+                                a.file-info href=(file_info_link(&entry.link, false, conf)) title=(strings.file_info) {
+                                    (info_symbol())
+                                }
+                            }
+                            @if conf.preview && entry.extension() == "pdf" {
+                                a.preview href=(preview_link(&entry.link, conf)) title=(strings.preview) {
+                                    (preview_symbol())
+                                }
+                            }
+                            @if conf.compressed_downloads {
+                                @for format in FileCompression::iter() {
+                                    a.compressed-download href=(compressed_download_link(&entry.link, format, conf)) title=(fill(strings.download_archive, &[format.extension()])) {
+                                        "." (format.extension())
+                                    }
+                                }
+                            }
+                            @if conf.allow_extract && ExtractFormat::from_name(&entry.name).is_some() {
+                                button.extract type="submit" form="extract-entry" name="name" value=(entry.name) title=(strings.extract) {
+                                    (extract_symbol())
                                 }
                             }
                             @if let Some(size) = entry.size {
@@ -349,6 +1053,11 @@ fn entry_row(
                             }
                         }
                     }
+                    @if conf.allow_rename {
+                        button.rename type="button" title=(strings.rename) data-name=(entry.name) {
+                            (rename_symbol())
+                        }
+                    }
                 }
             }
             td.size-cell {
@@ -357,11 +1066,13 @@ fn entry_row(
                 }
             }
             td.date-cell {
-                @if let Some(modification_date) = convert_to_utc(entry.last_modification_date) {
+                @if let Some((date, time)) = format_date(entry.last_modification_date, conf.time_format.as_deref(), conf.local_time) {
                     span {
-                        (modification_date.0) " "
-                        span.at { " at " }
-                        (modification_date.1) " "
+                        (date) " "
+                        @if let Some(time) = time {
+                            span.at { " " (strings.at) " " }
+                            (time) " "
+                        }
                     }
                 }
                 @if let Some(modification_timer) = humanize_systemtime(entry.last_modification_date) {
@@ -370,20 +1081,193 @@ fn entry_row(
                     }
                 }
             }
+            @if conf.show_checksums {
+                td.checksum-cell {
+                    @if let Some(checksum) = &entry.checksum {
+                        code title=(checksum) { (checksum) }
+                    } @else if entry.is_file() {
+                        button.compute-checksum type="button" data-src=(checksum_link(&entry.link, conf)) {
+                            (strings.compute_checksum)
+                        }
+                    }
+                }
+            }
+            @if conf.show_permissions {
+                td.permissions-cell {
+                    @if let Some(permissions) = &entry.permissions {
+                        code.mode { (permissions.mode) }
+                        span.owner { (permissions.owner) }
+                        span.group { (permissions.group) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Partial: icon and name of a file
+fn file_link_content(entry: &Entry) -> Markup {
+    html! {
+        (entry_icon(entry))
+        (entry.name)
+        @if entry.is_symlink {
+            span.symlink-symbol { }
+        }
+    }
+}
+
+/// Partial: QR code of a single file, only fetched once hovered
+fn file_qr_code(entry: &Entry, conf: &MiniserveConfig, strings: &Strings) -> Markup {
+    let qr_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/__miniserve/qr", random_route),
+        None => "/__miniserve/qr".to_string(),
+    };
+    let qr_link = format!(
+        "{}?path={}",
+        qr_route,
+        utf8_percent_encode(&entry.link, NON_ALPHANUMERIC)
+    );
+
+    html! {
+        span.file-qrcode {
+            (qr_symbol())
+            img alt=(strings.qr_code) title=(strings.file_qr_code_title) data-src=(qr_link);
         }
     }
 }
 
+/// Builds the link to the info page of the file linked as `link`
+fn file_info_link(link: &str, checksum: bool, conf: &MiniserveConfig) -> String {
+    let info_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/__miniserve/info", random_route),
+        None => "/__miniserve/info".to_string(),
+    };
+    format!(
+        "{}?path={}{}",
+        info_route,
+        utf8_percent_encode(link, NON_ALPHANUMERIC),
+        if checksum { "&checksum=true" } else { "" }
+    )
+}
+
+/// Builds the link to the preview page of the PDF linked as `link`
+fn preview_link(link: &str, conf: &MiniserveConfig) -> String {
+    let preview_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/__miniserve/preview", random_route),
+        None => "/__miniserve/preview".to_string(),
+    };
+    format!(
+        "{}?path={}",
+        preview_route,
+        utf8_percent_encode(link, NON_ALPHANUMERIC)
+    )
+}
+
+/// Builds the link to download the file linked as `link` compressed in `format`
+fn compressed_download_link(link: &str, format: FileCompression, conf: &MiniserveConfig) -> String {
+    let compressed_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/__miniserve/compressed", random_route),
+        None => "/__miniserve/compressed".to_string(),
+    };
+    format!(
+        "{}?path={}&format={}",
+        compressed_route,
+        utf8_percent_encode(link, NON_ALPHANUMERIC),
+        format
+    )
+}
+
+/// Builds the link to the checksum of the file linked as `link`
+fn checksum_link(link: &str, conf: &MiniserveConfig) -> String {
+    let checksum_route = match conf.random_route {
+        Some(ref random_route) => format!("/{}/__miniserve/checksum", random_route),
+        None => "/__miniserve/checksum".to_string(),
+    };
+    format!(
+        "{}?path={}",
+        checksum_route,
+        utf8_percent_encode(link, NON_ALPHANUMERIC)
+    )
+}
+
+/// Partial: icon matching the type of an entry
+fn entry_icon(entry: &Entry) -> Markup {
+    let icon = if entry.is_dir() {
+        include_str!("../data/icons/folder.svg")
+    } else {
+        match entry.extension().as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tif" | "tiff"
+            | "heic" | "avif" => include_str!("../data/icons/image.svg"),
+            "mp4" | "mkv" | "webm" | "avi" | "mov" | "wmv" | "flv" | "m4v" | "mpg" | "mpeg" => {
+                include_str!("../data/icons/media.svg")
+            }
+            "mp3" | "flac" | "ogg" | "opus" | "wav" | "m4a" | "aac" | "wma" => {
+                include_str!("../data/icons/audio.svg")
+            }
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "iso" | "deb"
+            | "rpm" => include_str!("../data/icons/archive.svg"),
+            "rs" | "c" | "h" | "cpp" | "hpp" | "py" | "js" | "ts" | "go" | "java" | "rb" | "sh"
+            | "html" | "css" | "scss" | "json" | "toml" | "yaml" | "yml" | "xml" | "php"
+            | "lua" => {
+                include_str!("../data/icons/code.svg")
+            }
+            "txt" | "md" | "rst" | "log" | "csv" | "pdf" | "doc" | "docx" | "odt" | "rtf" => {
+                include_str!("../data/icons/text.svg")
+            }
+            _ => include_str!("../data/icons/file.svg"),
+        }
+    };
+
+    html! {
+        span.icon { (PreEscaped(icon)) }
+    }
+}
+
 /// Partial: up arrow
 fn arrow_up() -> Markup {
     PreEscaped("⇪".to_string())
 }
 
+/// Partial: copy symbol
+fn copy_symbol() -> Markup {
+    PreEscaped("⧉".to_string())
+}
+
+/// Partial: QR code symbol
+fn qr_symbol() -> Markup {
+    PreEscaped("▦".to_string())
+}
+
+/// Partial: info symbol
+fn info_symbol() -> Markup {
+    PreEscaped("ⓘ".to_string())
+}
+
+/// Partial: rename symbol
+fn rename_symbol() -> Markup {
+    PreEscaped("✎".to_string())
+}
+
+/// Partial: extract symbol
+fn extract_symbol() -> Markup {
+    PreEscaped("⇲".to_string())
+}
+
+/// Partial: preview symbol
+fn preview_symbol() -> Markup {
+    PreEscaped("◳".to_string())
+}
+
 /// Partial: chevron left
 fn chevron_left() -> Markup {
     PreEscaped("◂".to_string())
 }
 
+/// Partial: chevron right
+fn chevron_right() -> Markup {
+    PreEscaped("▸".to_string())
+}
+
 /// Partial: chevron up
 fn chevron_up() -> Markup {
     PreEscaped("▴".to_string())
@@ -395,21 +1279,30 @@ fn chevron_down() -> Markup {
 }
 
 /// Partial: page header
-fn page_header(title: &str, file_upload: bool, favicon_route: &str, css_route: &str) -> Markup {
+fn page_header(
+    title: &str,
+    upload_script_nonce: Option<&str>,
+    favicon_route: &str,
+    css_route: &str,
+    link_preview: Option<Markup>,
+) -> Markup {
     html! {
         head {
             meta charset="utf-8";
             meta http-equiv="X-UA-Compatible" content="IE=edge";
             meta name="viewport" content="width=device-width, initial-scale=1";
+            @if let Some(link_preview) = link_preview {
+                (link_preview)
+            }
 
             link rel="icon" type="image/svg+xml" href={ "/" (favicon_route) };
             link rel="stylesheet" href={ "/" (css_route) };
 
             title { (title) }
 
-            @if file_upload {
-                (PreEscaped(r#"
-                <script>
+            @if let Some(nonce) = upload_script_nonce {
+                script nonce=(nonce) {
+                    (PreEscaped(r#"
                     window.onload = function() {
                         const dropContainer = document.querySelector('#drop-container');
                         const dragForm = document.querySelector('.drag-form');
@@ -443,31 +1336,309 @@ fn page_header(title: &str, file_upload: bool, favicon_route: &str, css_route: &
                             dragForm.style.display = 'none';
                         };
                     }
-                </script>
-                "#))
+                    "#))
+                }
             }
         }
     }
 }
 
-/// Converts a SystemTime object to a strings tuple (date, time)
-/// Date is formatted as %e %b, e.g. Jul 12
-/// Time is formatted as %R, e.g. 22:34
-fn convert_to_utc(src_time: Option<SystemTime>) -> Option<(String, String)> {
-    src_time.map(DateTime::<Utc>::from).map(|date_time| {
-        (
-            date_time.format("%b %e").to_string(),
-            date_time.format("%R").to_string(),
-        )
+/// Converts a SystemTime object to a strings tuple (date, time), in local time or UTC
+/// Without custom format, date is formatted as %e %b, e.g. Jul 12
+/// and time is formatted as %R, e.g. 22:34
+/// With a custom format, the whole date is formatted at once and there is no time
+fn format_date(
+    src_time: Option<SystemTime>,
+    time_format: Option<&str>,
+    local_time: bool,
+) -> Option<(String, Option<String>)> {
+    src_time.map(|src_time| {
+        if local_time {
+            format_date_time(DateTime::<Local>::from(src_time), time_format)
+        } else {
+            format_date_time(DateTime::<Utc>::from(src_time), time_format)
+        }
     })
 }
 
+/// Formats `date_time` in its timezone, see `format_date`
+fn format_date_time<Tz>(
+    date_time: DateTime<Tz>,
+    time_format: Option<&str>,
+) -> (String, Option<String>)
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    match time_format {
+        Some(time_format) => (date_time.format(time_format).to_string(), None),
+        None => (
+            date_time.format("%b %e").to_string(),
+            Some(date_time.format("%R").to_string()),
+        ),
+    }
+}
+
 /// Converts a SystemTime to a string readable by a human,
 /// and gives a rough approximation of the elapsed time since
 fn humanize_systemtime(time: Option<SystemTime>) -> Option<String> {
     time.map(|time| time.humanize())
 }
 
+/// Renders the page showing the metadata of a single file
+pub fn file_info_page(info: &FileInfo, conf: &MiniserveConfig, lang: Language) -> Markup {
+    let strings = lang.strings();
+
+    html! {
+        (DOCTYPE)
+        html lang=(lang) {
+            (page_header(&info.name, None, &conf.favicon_route, &conf.css_route, None))
+
+            body.(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
+
+                div.container {
+                    h1.title dir="ltr" {
+                        bdi { (info.name) }
+                    }
+                    table.file-info {
+                        tbody {
+                            tr {
+                                th { (strings.size) }
+                                td title=(format!("{} B", info.size)) { (bytesize::ByteSize::b(info.size)) }
+                            }
+                            @if let Some((date, time)) = format_date(info.last_modification_date, conf.time_format.as_deref(), conf.local_time) {
+                                tr {
+                                    th { (strings.last_modification) }
+                                    td {
+                                        (date)
+                                        @if let Some(time) = time {
+                                            " " (strings.at) " " (time)
+                                        }
+                                    }
+                                }
+                            }
+                            tr {
+                                th { (strings.mime_type) }
+                                td { (info.mime_type) }
+                            }
+                            @if let Some(permissions) = &info.permissions {
+                                tr {
+                                    th { (strings.permissions) }
+                                    td { code { (permissions.mode) } }
+                                }
+                                tr {
+                                    th { (strings.owner) }
+                                    td { (permissions.owner) ":" (permissions.group) }
+                                }
+                            }
+                            tr {
+                                th { (strings.checksum) }
+                                td.checksum {
+                                    @if let Some(checksum) = &info.checksum {
+                                        code { (checksum) }
+                                    } @else {
+                                        a href=(file_info_link(&info.link, true, conf)) { (strings.compute_checksum) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div.file-info-links {
+                        a.raw href=(info.link) { (strings.open_raw) }
+                        @if conf.preview && info.mime_type == "application/pdf" {
+                            a.preview href=(preview_link(&info.link, conf)) { (strings.preview) }
+                        }
+                        a.download href=(info.link) download=(info.name) { (strings.download) }
+                        @if conf.compressed_downloads {
+                            @for format in FileCompression::iter() {
+                                a.compressed-download href=(compressed_download_link(&info.link, format, conf)) {
+                                    (fill(strings.download_archive, &[format.extension()]))
+                                }
+                            }
+                        }
+                        a.root href=(info.parent_link) { (strings.go_back) }
+                    }
+                    @if !conf.hide_version_footer {
+                        (version_footer())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renders the preview page of a single PDF, embedding the browser's viewer
+pub fn preview_page(info: &FileInfo, conf: &MiniserveConfig, lang: Language) -> Markup {
+    let strings = lang.strings();
+
+    html! {
+        (DOCTYPE)
+        html lang=(lang) {
+            (page_header(&info.name, None, &conf.favicon_route, &conf.css_route, None))
+
+            body.(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
+
+                div.container {
+                    h1.title dir="ltr" {
+                        bdi { (info.name) }
+                    }
+                    iframe.pdf-preview src=(info.link) title=(info.name) {}
+                    div.file-info-links {
+                        a.raw href=(info.link) { (strings.open_raw) }
+                        a.download href=(info.link) download=(info.name) { (strings.download) }
+                        a.root href=(info.parent_link) { (strings.go_back) }
+                    }
+                    @if !conf.hide_version_footer {
+                        (version_footer())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renders the public stats page of the served directory
+pub fn stats_page(stats: &ShareStats, conf: &MiniserveConfig, lang: Language) -> Markup {
+    let strings = lang.strings();
+
+    html! {
+        (DOCTYPE)
+        html lang=(lang) {
+            (page_header(strings.share_stats, None, &conf.favicon_route, &conf.css_route, None))
+
+            body.(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
+
+                div.container {
+                    h1.title { (strings.share_stats) }
+                    table.file-info {
+                        tbody {
+                            tr {
+                                th { (strings.file_count) }
+                                td { (stats.files) }
+                            }
+                            tr {
+                                th { (strings.total_size) }
+                                td title=(format!("{} B", stats.size)) { (bytesize::ByteSize::b(stats.size)) }
+                            }
+                            @if let Some((date, time)) = format_date(stats.last_update, conf.time_format.as_deref(), conf.local_time) {
+                                tr {
+                                    th { (strings.last_update) }
+                                    td {
+                                        (date)
+                                        @if let Some(time) = time {
+                                            " " (strings.at) " " (time)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    @if !conf.hide_version_footer {
+                        (version_footer())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renders the page listing files with the same content
+pub fn duplicates_page(
+    groups: &[DuplicateGroup],
+    conf: &MiniserveConfig,
+    lang: Language,
+) -> Markup {
+    let strings = lang.strings();
+    let reclaimable = groups.iter().map(DuplicateGroup::reclaimable).sum::<u64>();
+
+    html! {
+        (DOCTYPE)
+        html lang=(lang) {
+            (page_header(strings.duplicates, None, &conf.favicon_route, &conf.css_route, None))
+
+            body.(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
+
+                div.container {
+                    h1.title { (strings.duplicates) }
+                    table.file-info {
+                        tbody {
+                            tr {
+                                th { (strings.duplicate_groups) }
+                                td { (groups.len()) }
+                            }
+                            tr {
+                                th { (strings.reclaimable_size) }
+                                td title=(format!("{} B", reclaimable)) { (bytesize::ByteSize::b(reclaimable)) }
+                            }
+                        }
+                    }
+                    @for group in groups {
+                        table.duplicates {
+                            thead {
+                                tr {
+                                    th { (fill(strings.duplicate_group, &[&group.paths.len().to_string(), &bytesize::ByteSize::b(group.size).to_string()])) }
+                                }
+                            }
+                            tbody {
+                                @for path in &group.paths {
+                                    tr {
+                                        td { (path) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    @if !conf.hide_version_footer {
+                        (version_footer())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renders the top-level page listing the directories served under their names, given with
+/// their links
+pub fn mounts_page(mounts: &[(&str, String)], conf: &MiniserveConfig, lang: Language) -> Markup {
+    let strings = lang.strings();
+    let title = conf.title.as_deref().unwrap_or(strings.mounts);
+
+    html! {
+        (DOCTYPE)
+        html lang=(lang) {
+            (page_header(title, None, &conf.favicon_route, &conf.css_route, None))
+
+            body.(format!("default_theme_{}", conf.reloadable.get().default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.reloadable.get().default_color_scheme_dark)) {
+
+                div.container {
+                    h1.title dir="ltr" { bdi { (title) } }
+                    table.mounts {
+                        tbody {
+                            @for (name, link) in mounts {
+                                tr.entry {
+                                    td {
+                                        p {
+                                            a.directory href=(link) { (name) "/" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    @if !conf.hide_version_footer {
+                        (version_footer())
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Renders an error on the webpage
 #[allow(clippy::too_many_arguments)]
 pub fn render_error(
@@ -483,6 +1654,46 @@ pub fn render_error(
     default_color_scheme: &str,
     default_color_scheme_dark: &str,
     hide_version_footer: bool,
+    templates: Option<&Templates>,
+    lang: Language,
+) -> Markup {
+    render_error_with_suggestion(
+        error_description,
+        error_code,
+        return_address,
+        sort_method,
+        sort_order,
+        has_referer,
+        display_back_link,
+        favicon_route,
+        css_route,
+        default_color_scheme,
+        default_color_scheme_dark,
+        hide_version_footer,
+        templates,
+        lang,
+        None,
+    )
+}
+
+/// Renders an error on the webpage, suggesting a link to what was likely meant
+#[allow(clippy::too_many_arguments)]
+pub fn render_error_with_suggestion(
+    error_description: &str,
+    error_code: StatusCode,
+    return_address: &str,
+    sort_method: Option<SortingMethod>,
+    sort_order: Option<SortingOrder>,
+    has_referer: bool,
+    display_back_link: bool,
+    favicon_route: &str,
+    css_route: &str,
+    default_color_scheme: &str,
+    default_color_scheme_dark: &str,
+    hide_version_footer: bool,
+    templates: Option<&Templates>,
+    lang: Language,
+    suggestion: Option<&Suggestion>,
 ) -> Markup {
     let link = if has_referer {
         return_address.to_string()
@@ -490,10 +1701,36 @@ pub fn render_error(
         parametrized_link(return_address, sort_method, sort_order)
     };
 
+    if let Some(templates) = templates {
+        let context = ErrorContext {
+            status: error_code.as_u16(),
+            reason: error_code
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            message: error_description.to_string(),
+            suggestion: suggestion.map(|suggestion| LinkContext {
+                name: suggestion.name.clone(),
+                link: suggestion.link.clone(),
+            }),
+            back_link: if display_back_link {
+                Some(link.clone())
+            } else {
+                None
+            },
+            favicon_route: format!("/{}", favicon_route),
+            css_route: format!("/{}", css_route),
+            version: version(hide_version_footer),
+        };
+        if let Some(page) = templates.render("error.html", &context) {
+            return page;
+        }
+    }
+
     html! {
         (DOCTYPE)
-        html {
-            (page_header(&error_code.to_string(), false, favicon_route, css_route))
+        html lang=(lang) {
+            (page_header(&error_code.to_string(), None, favicon_route, css_route, None))
 
             body.(format!("default_theme_{}", default_color_scheme))
                 .(format!("default_theme_dark_{}", default_color_scheme_dark)) {
@@ -503,10 +1740,18 @@ pub fn render_error(
                     @for error in error_description.lines() {
                         p { (error) }
                     }
+                    @if let Some(suggestion) = suggestion {
+                        @let (before, after) = lang.strings().did_you_mean.split_once("{}").unwrap_or_default();
+                        p.suggestion {
+                            (before)
+                            a href=(suggestion.link) { (suggestion.name) }
+                            (after)
+                        }
+                    }
                     @if display_back_link {
                         div.error-nav {
                             a.error-back href=(link) {
-                                "Go back to file listing"
+                                (lang.strings().go_back)
                             }
                         }
                     }
@@ -0,0 +1,103 @@
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+
+use crate::errors::ContextualError;
+
+/// The headers sent by `--security-headers`, by the name used to configure them, with their
+/// default value.
+///
+/// Frames are allowed from the same origin, since the preview of PDF files embeds them.
+const DEFAULTS: [(&str, HeaderName, &str); 4] = [
+    (
+        "hsts",
+        header::STRICT_TRANSPORT_SECURITY,
+        "max-age=31536000; includeSubDomains",
+    ),
+    (
+        "content-type-options",
+        header::X_CONTENT_TYPE_OPTIONS,
+        "nosniff",
+    ),
+    ("referrer-policy", header::REFERRER_POLICY, "no-referrer"),
+    ("frame-options", header::X_FRAME_OPTIONS, "SAMEORIGIN"),
+];
+
+/// A `--security-headers` value, which replaces the value of one of the headers, or leaves
+/// it out if the value is `off`, e.g. `referrer-policy=same-origin`
+#[derive(Clone, Debug)]
+pub struct SecurityHeaderArg {
+    name: HeaderName,
+    value: Option<HeaderValue>,
+}
+
+impl SecurityHeaderArg {
+    pub fn parse(src: &str) -> Result<Self, ContextualError> {
+        let parse_error =
+            |cause: String| ContextualError::ParseError("security header".to_string(), cause);
+
+        let (key, value) = src
+            .split_once('=')
+            .ok_or_else(|| parse_error(format!("{} is not of the form header=value", src)))?;
+        let name = DEFAULTS
+            .iter()
+            .find(|(default_key, _, _)| default_key.eq_ignore_ascii_case(key.trim()))
+            .map(|(_, name, _)| name.clone())
+            .ok_or_else(|| {
+                parse_error(format!(
+                    "{} is not one of hsts, content-type-options, referrer-policy and frame-options",
+                    key.trim()
+                ))
+            })?;
+        let value = match value.trim() {
+            "off" => None,
+            value => Some(
+                HeaderValue::from_str(value)
+                    .map_err(|_| parse_error(format!("{} is not a valid header value", value)))?,
+            ),
+        };
+        Ok(SecurityHeaderArg { name, value })
+    }
+}
+
+/// Returns the headers sent by `--security-headers`, given the values replacing their defaults
+pub fn headers(args: &[SecurityHeaderArg]) -> Vec<(HeaderName, HeaderValue)> {
+    DEFAULTS
+        .iter()
+        .filter_map(|(_, name, default)| {
+            match args.iter().rev().find(|arg| arg.name == *name) {
+                Some(arg) => arg.value.clone(),
+                None => Some(HeaderValue::from_static(default)),
+            }
+            .map(|value| (name.clone(), value))
+        })
+        .collect()
+}
+
+/// Returns the security headers to send with the response to `req`.
+///
+/// HSTS is only sent over HTTPS, where browsers take it into account.
+pub fn for_request(req: &ServiceRequest) -> Vec<(HeaderName, HeaderValue)> {
+    let conf = match req.app_data::<crate::MiniserveConfig>() {
+        Some(conf) => conf,
+        None => return vec![],
+    };
+    let https = req.connection_info().scheme() == "https";
+    conf.security_headers
+        .iter()
+        .filter(|(name, _)| https || *name != header::STRICT_TRANSPORT_SECURITY)
+        .cloned()
+        .collect()
+}
+
+/// Adds the security `headers` to `res`, unless it already has them, e.g. from `--header`
+pub fn apply<B>(
+    mut res: ServiceResponse<B>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+) -> ServiceResponse<B> {
+    for (name, value) in headers {
+        if !res.headers().contains_key(&name) {
+            res.headers_mut().insert(name, value);
+        }
+    }
+    res
+}
@@ -0,0 +1,203 @@
+use actix_web::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_web::dev::{Server, ServiceRequest, ServiceResponse};
+use actix_web::http::{Method, StatusCode};
+use actix_web::rt::time::delay_for;
+use actix_web::web::Bytes;
+use actix_web::Error;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::errors::ContextualError;
+use crate::path_policy;
+
+/// Seconds responses being sent get to finish once miniserve shuts down on its own, so that
+/// the last download isn't cut off
+pub const GRACE_PERIOD: u64 = 5;
+
+/// Parses a duration like `90`, `30s`, `10m`, `2h` or `1h30m`, plain numbers being seconds
+pub fn parse_duration(src: &str) -> Result<Duration, ContextualError> {
+    let parse_error = || {
+        ContextualError::ParseError(
+            "duration".to_string(),
+            format!("{} is not a duration like 90s, 10m or 1h30m", src),
+        )
+    };
+
+    let mut seconds = 0u64;
+    let mut rest = src.trim();
+    if rest.is_empty() {
+        return Err(parse_error());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number = rest[..digits].parse::<u64>().map_err(|_| parse_error())?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit] {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(parse_error()),
+        };
+        rest = &rest[unit..];
+        seconds = number
+            .checked_mul(factor)
+            .and_then(|part| seconds.checked_add(part))
+            .ok_or_else(parse_error)?;
+    }
+    if seconds == 0 {
+        return Err(ContextualError::ParseError(
+            "duration".to_string(),
+            "duration must be greater than 0".to_string(),
+        ));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Checks wether a number of downloads is valid, i.e. a positive number
+pub fn parse_download_count(src: &str) -> Result<usize, ContextualError> {
+    match src.parse::<usize>() {
+        Ok(0) => Err(ContextualError::ParseError(
+            "number of downloads".to_string(),
+            "number of downloads must be greater than 0".to_string(),
+        )),
+        Ok(count) => Ok(count),
+        Err(e) => Err(ContextualError::ParseError(
+            "number of downloads".to_string(),
+            e.to_string(),
+        )),
+    }
+}
+
+/// Downloads left until miniserve shuts down, as set by `--shutdown-after-downloads`, shared by
+/// all workers
+#[derive(Clone)]
+pub struct DownloadLimit {
+    remaining: Arc<AtomicUsize>,
+
+    /// The running server, once it is started
+    server: Arc<Mutex<Option<Server>>>,
+}
+
+impl DownloadLimit {
+    pub fn new(count: usize) -> Self {
+        DownloadLimit {
+            remaining: Arc::new(AtomicUsize::new(count)),
+            server: Arc::default(),
+        }
+    }
+
+    /// Counts a completed download and stops the server after the last one
+    fn count(&self) {
+        let previous =
+            self.remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| {
+                    remaining.checked_sub(1)
+                });
+        if previous == Ok(1) {
+            log::info!("Shutting down after the last download");
+            if let Some(server) = self.server.lock().unwrap().take() {
+                actix_web::rt::spawn(server.stop(true));
+            }
+        }
+    }
+}
+
+/// Stops `server` once `--shutdown-after` has passed or the downloads of
+/// `--shutdown-after-downloads` are done
+pub fn schedule(server: &Server, conf: &crate::MiniserveConfig) {
+    if let Some(limit) = &conf.download_limit {
+        *limit.server.lock().unwrap() = Some(server.clone());
+    }
+    if let Some(duration) = conf.shutdown_after {
+        let server = server.clone();
+        actix_web::rt::spawn(async move {
+            delay_for(duration).await;
+            log::info!("Shutting down after {}s", duration.as_secs());
+            server.stop(true).await;
+        });
+    }
+}
+
+/// Body counting as a download once it is sent completely
+struct CountedBody {
+    body: ResponseBody<Body>,
+    limit: Option<DownloadLimit>,
+}
+
+impl MessageBody for CountedBody {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.body).poll_next(cx);
+        if let Poll::Ready(None) = poll {
+            if let Some(limit) = this.limit.take() {
+                limit.count();
+            }
+        }
+        poll
+    }
+}
+
+/// Returns whether the response to `req` may count as a download, i.e. it gets a file while
+/// `--shutdown-after-downloads` is given
+pub fn is_download(req: &ServiceRequest) -> bool {
+    req.app_data::<crate::MiniserveConfig>()
+        .map_or(false, |conf| conf.download_limit.is_some())
+        && req.method() == Method::GET
+        && path_policy::requested_file(req).is_some()
+}
+
+/// Counts the file served by `res` as a download once it is sent completely. Partial content
+/// doesn't count, as the file isn't complete yet.
+pub fn count(res: ServiceResponse, download: bool) -> ServiceResponse {
+    if !download || res.status() != StatusCode::OK {
+        return res;
+    }
+    let limit = match res.request().app_data::<crate::MiniserveConfig>() {
+        Some(conf) => conf.download_limit.clone(),
+        None => return res,
+    };
+
+    res.map_body(|_, body| ResponseBody::Body(Body::from_message(CountedBody { body, limit })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        duration,
+        expected,
+        case("90", Some(90)),
+        case("30s", Some(30)),
+        case("10m", Some(600)),
+        case("1h30m", Some(5400)),
+        case("2d", Some(172_800)),
+        case("0", None),
+        case("", None),
+        case("m", None),
+        case("10x", None),
+        case("-5s", None)
+    )]
+    fn parse_duration_values(duration: &str, expected: Option<u64>) {
+        assert_eq!(
+            parse_duration(duration)
+                .ok()
+                .map(|duration| duration.as_secs()),
+            expected
+        );
+    }
+}
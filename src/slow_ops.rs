@@ -0,0 +1,29 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Logs `operation` on `path` if it took longer than `threshold`
+pub fn check(threshold: Option<Duration>, operation: &str, path: &Path, duration: Duration) {
+    if let Some(threshold) = threshold {
+        if duration > threshold {
+            log::warn!(
+                "Slow filesystem operation: {} {} took {:.3?}",
+                operation,
+                path.display(),
+                duration
+            );
+        }
+    }
+}
+
+/// Runs `f`, logging `operation` on `path` if it takes longer than `threshold`
+pub fn timed<T>(
+    threshold: Option<Duration>,
+    operation: &str,
+    path: &Path,
+    f: impl FnOnce() -> T,
+) -> T {
+    let start = Instant::now();
+    let result = f();
+    check(threshold, operation, path, start.elapsed());
+    result
+}
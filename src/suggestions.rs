@@ -0,0 +1,128 @@
+use actix_web::HttpRequest;
+use percent_encoding::{percent_decode_str, utf8_percent_encode};
+use std::path::Path;
+
+use crate::ignore_files::IgnoreFiles;
+use crate::listing::percent_encode_sets::PATH_SEGMENT;
+use crate::path_policy;
+
+/// Maximum number of edits between a missing name and a suggested one
+const MAX_DISTANCE: usize = 2;
+
+/// Link to an entry which was likely meant by a request for a missing path
+pub struct Suggestion {
+    pub name: String,
+    pub link: String,
+}
+
+/// Looks for an entry next to the missing path of `req` whose name only differs in case or
+/// by a typo, so that the 404 page can suggest it
+pub fn suggest(req: &HttpRequest) -> Option<Suggestion> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let route = match conf.random_route {
+        Some(ref random_route) => format!("/{}", random_route),
+        None => String::new(),
+    };
+
+    // The link keeps the encoding of the request, only the name is replaced
+    let (parent_link, missing_name) = req
+        .path()
+        .strip_prefix(&route)?
+        .trim_end_matches('/')
+        .rsplit_once('/')?;
+    let missing_name = percent_decode_str(missing_name)
+        .decode_utf8()
+        .ok()?
+        .to_lowercase();
+    let parent_path = percent_decode_str(parent_link).decode_utf8().ok()?;
+    let parent_path = Path::new(&*parent_path);
+    if path_policy::is_hidden(parent_path) && !conf.show_hidden {
+        return None;
+    }
+    let parent_dir = path_policy::resolve_request_path(&conf.path, parent_path).ok()?;
+
+    let ignore_files = if conf.ignore_files {
+        Some(IgnoreFiles::for_dir(&conf.path, &parent_dir))
+    } else {
+        None
+    };
+
+    let (_, name, is_dir) = parent_dir
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_str()?.to_string();
+            let file_type = entry.file_type().ok()?;
+            if (name.starts_with('.') && !conf.show_hidden)
+                || (file_type.is_symlink() && conf.no_symlinks)
+            {
+                return None;
+            }
+
+            let is_dir = entry.path().is_dir();
+            if let Some(ignore_files) = &ignore_files {
+                if ignore_files.is_ignored(&entry.path(), is_dir) {
+                    return None;
+                }
+            }
+
+            let distance = edit_distance(&missing_name, &name.to_lowercase());
+            if distance > MAX_DISTANCE || distance >= missing_name.chars().count() {
+                return None;
+            }
+            Some((distance, name, is_dir))
+        })
+        .min()?;
+
+    let link = format!(
+        "{}{}/{}{}",
+        route,
+        parent_link,
+        utf8_percent_encode(&name, PATH_SEGMENT),
+        if is_dir { "/" } else { "" }
+    );
+    Some(Suggestion { name, link })
+}
+
+/// Levenshtein distance between `a` and `b`, i.e. the number of characters which have to be
+/// inserted, removed or replaced to turn one into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + if a_char == *b_char { 0 } else { 1 };
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+
+    distances[b.len()]
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use pretty_assertions::assert_eq;
+
+    #[rstest(a, b, expected,
+        case("", "", 0),
+        case("file.txt", "file.txt", 0),
+        case("", "abc", 3),
+        case("file.txt", "fiel.txt", 2),
+        case("file.txt", "file.tx", 1),
+        case("file.txt", "files.txt", 1),
+        case("kitten", "sitting", 3),
+        case("😀.data", "😁.data", 1),
+    )]
+    fn edit_distances(a: &str, b: &str, expected: usize) {
+        assert_eq!(edit_distance(a, b), expected);
+        assert_eq!(edit_distance(b, a), expected);
+    }
+}
@@ -0,0 +1,166 @@
+use maud::{Markup, PreEscaped};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use tera::{Context, Tera};
+
+use crate::errors::ContextualError;
+
+/// Custom Tera templates replacing the built-in pages
+///
+/// `listing.html` replaces the directory listing and `error.html` the error page.
+/// Pages without a matching template keep the built-in markup.
+#[derive(Clone)]
+pub struct Templates {
+    tera: Arc<Tera>,
+}
+
+impl Templates {
+    /// Loads all `*.html` templates from `dir`
+    pub fn load(dir: &Path) -> Result<Self, ContextualError> {
+        let glob = dir.join("*.html");
+        let tera = Tera::new(&glob.to_string_lossy()).map_err(|e| {
+            ContextualError::TemplateError(dir.to_string_lossy().to_string(), e.to_string())
+        })?;
+        Ok(Templates {
+            tera: Arc::new(tera),
+        })
+    }
+
+    /// Renders the template `name` with the given context.
+    ///
+    /// Returns `None` if there is no such template or rendering fails, in which case the
+    /// built-in markup should be used.
+    pub fn render<T: Serialize>(&self, name: &str, context: &T) -> Option<Markup> {
+        if !self.tera.get_template_names().any(|n| n == name) {
+            return None;
+        }
+
+        let rendered =
+            Context::from_serialize(context).and_then(|context| self.tera.render(name, &context));
+        match rendered {
+            Ok(html) => Some(PreEscaped(html)),
+            Err(e) => {
+                log::error!("Failed to render template {}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+/// Variables available in `listing.html`
+#[derive(Serialize)]
+pub struct ListingContext {
+    /// Page title, i.e. the path of the listed directory
+    pub title: String,
+
+    /// Path to the listed directory, starting with the root
+    pub breadcrumbs: Vec<LinkContext>,
+
+    /// Link to the parent directory, unset for the root
+    pub parent_link: Option<String>,
+
+    pub entries: Vec<EntryContext>,
+
+    pub totals: TotalsContext,
+
+    /// Target of the upload form, unset if uploads are disabled
+    pub upload_action: Option<String>,
+
+    /// Links to download the directory as an archive, one per enabled format
+    pub archives: Vec<LinkContext>,
+
+    /// HTML of `--header-text`, to be inserted unescaped
+    pub header_text: Option<String>,
+
+    /// Content of `HEADER.html` in the listed directory, to be inserted unescaped
+    pub header: Option<String>,
+
+    /// Content of `FOOTER.html` in the listed directory, to be inserted unescaped
+    pub footer: Option<String>,
+
+    pub favicon_route: String,
+
+    pub css_route: String,
+
+    /// Nonce allowing inline scripts by the Content-Security-Policy, e.g.
+    /// `<script nonce="{{ nonce }}">`
+    pub nonce: String,
+
+    /// Name and version of miniserve, unset if the version footer is hidden
+    pub version: Option<String>,
+}
+
+/// Variables of a link
+#[derive(Serialize)]
+pub struct LinkContext {
+    pub name: String,
+    pub link: String,
+}
+
+/// Variables of an entry of the listing
+#[derive(Serialize)]
+pub struct EntryContext {
+    pub name: String,
+    pub link: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+
+    /// Name to save the file as, set if it only differs in case from another one
+    pub download_name: Option<String>,
+
+    /// SHA-256 checksum, only set when the column is enabled and it has been computed before
+    pub checksum: Option<String>,
+
+    /// Mode bits like "drwxr-xr-x", only set on unix when permissions are shown
+    pub mode: Option<String>,
+
+    /// Name of the owning user, only set on unix when permissions are shown
+    pub owner: Option<String>,
+
+    /// Name of the owning group, only set on unix when permissions are shown
+    pub group: Option<String>,
+
+    /// Human readable size, like "1.2 MB"
+    pub size: Option<String>,
+
+    /// Size in bytes
+    pub size_bytes: Option<u64>,
+
+    /// Last modification as UNIX timestamp, suitable for Tera's `date` filter
+    pub modified: Option<i64>,
+}
+
+/// Variables of the summary of all entries
+#[derive(Serialize)]
+pub struct TotalsContext {
+    pub files: usize,
+    pub directories: usize,
+    pub size: String,
+}
+
+/// Variables available in `error.html`
+#[derive(Serialize)]
+pub struct ErrorContext {
+    /// HTTP status code
+    pub status: u16,
+
+    /// Reason phrase of the status code, like "Not Found"
+    pub reason: String,
+
+    /// Description of the error, possibly spanning multiple lines
+    pub message: String,
+
+    /// Link to what was likely meant, only set for missing files with suggestions enabled
+    pub suggestion: Option<LinkContext>,
+
+    /// Link back to the listing, unset if it shouldn't be displayed
+    pub back_link: Option<String>,
+
+    pub favicon_route: String,
+
+    pub css_route: String,
+
+    /// Name and version of miniserve, unset if the version footer is hidden
+    pub version: Option<String>,
+}
@@ -0,0 +1,94 @@
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::sign::{self, CertifiedKey};
+use rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::errors::ContextualError;
+
+/// Parses the PEM encoded certificate chain `pem`
+fn parse_certs(mut pem: &[u8]) -> Result<Vec<Certificate>, &'static str> {
+    match certs(&mut pem) {
+        Ok(chain) if !chain.is_empty() => Ok(chain),
+        Ok(_) => Err("the file contains no certificate"),
+        Err(()) => Err("the file is not a PEM encoded certificate"),
+    }
+}
+
+/// Parses the first PEM encoded PKCS#8 or RSA (PKCS#1) private key of `pem`
+fn parse_key(pem: &[u8]) -> Result<PrivateKey, &'static str> {
+    let keys = match pkcs8_private_keys(&mut &pem[..]) {
+        Ok(keys) if keys.is_empty() => rsa_private_keys(&mut &pem[..]),
+        keys => keys,
+    };
+    match keys {
+        Ok(mut keys) if !keys.is_empty() => Ok(keys.remove(0)),
+        Ok(_) => Err("the file contains no PKCS#8 or RSA private key"),
+        Err(()) => Err("the file is not a PEM encoded private key"),
+    }
+}
+
+/// Loads the certificate chain at `cert` and the private key at `key`, both PEM encoded, into
+/// the TLS configuration of the server.
+///
+/// The key may be a PKCS#8 or an RSA (PKCS#1) key, only the first key in the file is used.
+pub fn load_config(cert: &Path, key: &Path) -> Result<ServerConfig, ContextualError> {
+    let tls_error = |path: &Path, cause: &str| {
+        ContextualError::TlsConfigError(path.display().to_string(), cause.to_string())
+    };
+    let read = |path: &Path| {
+        fs::read(path)
+            .map_err(|e| ContextualError::IoError(format!("Failed to read {}", path.display()), e))
+    };
+
+    let cert_chain = parse_certs(&read(cert)?).map_err(|cause| tls_error(cert, cause))?;
+    let private_key = parse_key(&read(key)?).map_err(|cause| tls_error(key, cause))?;
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(cert_chain, private_key)
+        .map_err(|e| tls_error(cert, &e.to_string()))?;
+    Ok(config)
+}
+
+/// Parses the PEM encoded certificate chain `cert` and its private key `key` into a key
+/// rustls can sign with
+pub fn certified_key(cert: &[u8], key: &[u8]) -> Result<CertifiedKey, &'static str> {
+    let cert_chain = parse_certs(cert)?;
+    let signing_key = sign::any_supported_type(&parse_key(key)?)
+        .map_err(|()| "the private key is of an unsupported type")?;
+    Ok(CertifiedKey::new(cert_chain, Arc::new(signing_key)))
+}
+
+/// Generates a self-signed certificate for localhost, valid until miniserve stops, and returns
+/// the TLS configuration serving it along with its SHA-256 fingerprint.
+///
+/// Browsers warn about the certificate, so visitors have to compare the fingerprint with the
+/// one miniserve prints to know that they are talking to it.
+pub fn self_signed_config() -> Result<(ServerConfig, String), ContextualError> {
+    let tls_error = |cause: String| {
+        ContextualError::TlsConfigError("a self-signed certificate".to_string(), cause)
+    };
+
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+        .map_err(|e| tls_error(e.to_string()))?;
+    let cert_der = certificate
+        .serialize_der()
+        .map_err(|e| tls_error(e.to_string()))?;
+    let fingerprint = Sha256::digest(&cert_der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(
+            vec![Certificate(cert_der)],
+            PrivateKey(certificate.serialize_private_key_der()),
+        )
+        .map_err(|e| tls_error(e.to_string()))?;
+    Ok((config, fingerprint))
+}
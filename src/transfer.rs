@@ -0,0 +1,284 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::web::{self, Bytes};
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+use strum_macros::{Display, EnumString};
+
+use crate::errors::{self, ContextualError};
+use crate::i18n::Language;
+use crate::listing;
+use crate::path_policy;
+use crate::renderer;
+
+/// Ways to transfer entries to another directory
+#[derive(Clone, Copy, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Operation {
+    Move,
+    Copy,
+}
+
+/// Moves or copies the entry at `source` to `target`, unless an entry already exists there
+fn transfer_entry(
+    operation: Operation,
+    source: &Path,
+    target: &Path,
+) -> Result<(), ContextualError> {
+    if target.symlink_metadata().is_ok() {
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        return Err(ContextualError::EntryExistsError(name.to_string()));
+    }
+
+    let result = match operation {
+        Operation::Move => fs::rename(source, target),
+        Operation::Copy => copy_recursively(source, target),
+    };
+    result.map_err(|e| {
+        ContextualError::IoError(
+            format!(
+                "Failed to {} {} to {}",
+                operation,
+                source.display(),
+                target.display()
+            ),
+            e,
+        )
+    })
+}
+
+/// Copies a file, or a directory with everything below it
+fn copy_recursively(source: &Path, target: &Path) -> io::Result<()> {
+    let metadata = source.symlink_metadata()?;
+    if metadata.file_type().is_symlink() {
+        copy_symlink(source, target)
+    } else if metadata.is_dir() {
+        fs::create_dir(target)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &target.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(source, target).map(|_| ())
+    }
+}
+
+/// Copies a symlink as a symlink, so that loops can't make copies grow forever
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+/// Creating symlinks requires special privileges on other platforms, so they are left out
+#[cfg(not(unix))]
+fn copy_symlink(_source: &Path, _target: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Outcome of transferring one entry
+struct TransferResult {
+    name: String,
+    result: Result<(), ContextualError>,
+}
+
+/// Outcome of all entries as reported in JSON
+#[derive(Serialize)]
+struct TransferReport<'a> {
+    entries: Vec<EntryReport<'a>>,
+}
+
+/// Outcome of one entry as reported in JSON
+#[derive(Serialize)]
+struct EntryReport<'a> {
+    name: &'a str,
+    done: bool,
+    error: Option<String>,
+}
+
+/// Responds with the outcome of each entry as JSON
+fn json_report(results: &[TransferResult], status: Option<StatusCode>) -> HttpResponse {
+    let entries = results
+        .iter()
+        .map(|entry| EntryReport {
+            name: &entry.name,
+            done: entry.result.is_ok(),
+            error: entry.result.as_ref().err().map(ToString::to_string),
+        })
+        .collect::<Vec<_>>();
+    for error in entries.iter().filter_map(|entry| entry.error.as_ref()) {
+        errors::log_error_chain(error.to_string());
+    }
+
+    HttpResponse::build(status.unwrap_or(StatusCode::OK)).json(TransferReport { entries })
+}
+
+/// Describes the outcome of each entry, one per line, for the error page
+fn summary(operation: Operation, results: &[TransferResult]) -> String {
+    let failed = results.iter().filter(|entry| entry.result.is_err()).count();
+    let mut summary = format!(
+        "{} of {} entries could not be {}",
+        failed,
+        results.len(),
+        match operation {
+            Operation::Move => "moved",
+            Operation::Copy => "copied",
+        }
+    );
+    for entry in results {
+        let outcome = match &entry.result {
+            Ok(()) => "done".to_string(),
+            Err(e) => e.to_string().replace('\n', ", "),
+        };
+        summary.push_str(&format!("\n{}: {}", entry.name, outcome));
+    }
+    summary
+}
+
+/// Moves or copies the entries selected in the listing to another directory
+///
+/// Like for uploads, the directory of the entries is given by the `path` query parameter. The
+/// form data contains the `operation`, the `destination` directory relative to the served
+/// directory, and one `entry` field per selected entry.
+///
+/// Each entry is transferred on its own, so that failures don't keep the remaining entries
+/// from being transferred. Clients accepting JSON get the outcome of each entry as JSON.
+pub async fn transfer_selection(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let wants_json = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map_or(false, |accept| accept.contains("application/json"));
+    let return_path = req
+        .headers()
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok())
+        .unwrap_or("/")
+        .to_string();
+    let error_response = |description: String, status: StatusCode| {
+        errors::log_error_chain(description.clone());
+        HttpResponse::build(status)
+            .content_type("text/html; charset=utf-8")
+            .body(
+                renderer::render_error(
+                    &description,
+                    status,
+                    &return_path,
+                    None,
+                    None,
+                    true,
+                    conf.random_route.is_none(),
+                    &conf.favicon_route,
+                    &conf.css_route,
+                    &conf.reloadable.get().default_color_scheme,
+                    &conf.reloadable.get().default_color_scheme_dark,
+                    conf.hide_version_footer,
+                    conf.templates.as_ref(),
+                    Language::negotiate(req.headers(), conf.lang),
+                )
+                .into_string(),
+            )
+    };
+    let invalid_request = |message: String| {
+        error_response(
+            ContextualError::InvalidHttpRequestError(message).to_string(),
+            StatusCode::BAD_REQUEST,
+        )
+    };
+
+    let mut operation = None;
+    let mut destination = None;
+    let mut selection = Vec::new();
+    for (key, value) in form_urlencoded::parse(&body) {
+        match key.as_ref() {
+            "operation" => operation = value.parse::<Operation>().ok(),
+            "destination" => destination = Some(value.into_owned()),
+            "entry" => selection.push(OsString::from(value.into_owned())),
+            _ => {}
+        }
+    }
+    let operation = match operation {
+        Some(operation) => operation,
+        None => return invalid_request("Missing or invalid operation".to_string()),
+    };
+    if selection.is_empty() {
+        return invalid_request("No entries selected".to_string());
+    }
+
+    // Both directories have to be inside the served directory, like the target of uploads
+    let query_params = listing::extract_query_parameters(&req);
+    let dir_path = query_params.path.unwrap_or_default();
+    let dir = match path_policy::resolve_request_path(&conf.path, &dir_path) {
+        Ok(dir) if dir.is_dir() => dir,
+        _ => return invalid_request("Invalid value for 'path' parameter".to_string()),
+    };
+    let destination_path = Path::new(destination.as_deref().unwrap_or_default());
+    let destination = match path_policy::resolve_request_path(&conf.path, destination_path) {
+        Ok(destination) if destination.is_dir() && listing::is_listed_path(&destination, conf) => {
+            destination
+        }
+        _ => return invalid_request("Invalid destination directory".to_string()),
+    };
+
+    // Only entries which are listed may be selected, and directories can't contain themselves.
+    // Annotation files mustn't end up in the destination either.
+    for name in &selection {
+        let is_valid = listing::is_listed_entry(&dir, name, conf)
+            && !listing::Annotations::is_reserved_name(name)
+            && !dir
+                .join(name)
+                .canonicalize()
+                .map_or(false, |source| destination.starts_with(source));
+        if !is_valid {
+            return invalid_request(format!("Invalid entry '{}'", name.to_string_lossy()));
+        }
+    }
+
+    log::info!(
+        "{} {} entries of {} to {}",
+        match operation {
+            Operation::Move => "Moving",
+            Operation::Copy => "Copying",
+        },
+        selection.len(),
+        dir.display(),
+        destination.display()
+    );
+    let results = web::block(move || {
+        Ok::<_, ContextualError>(
+            selection
+                .iter()
+                .map(|name| TransferResult {
+                    name: name.to_string_lossy().to_string(),
+                    result: transfer_entry(operation, &dir.join(name), &destination.join(name)),
+                })
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await;
+
+    match results.map_err(ContextualError::from) {
+        Ok(results) => {
+            // The first failure decides the status, later ones are only reported
+            let status = results
+                .iter()
+                .find_map(|entry| entry.result.as_ref().err())
+                .map(|error| match error {
+                    ContextualError::EntryExistsError(_) => StatusCode::CONFLICT,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                });
+            match status {
+                _ if wants_json => json_report(&results, status),
+                None => HttpResponse::SeeOther()
+                    .header(header::LOCATION, return_path.as_str())
+                    .finish(),
+                Some(status) => error_response(summary(operation, &results), status),
+            }
+        }
+        Err(e) => error_response(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,73 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::header::HOST;
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    host,
+    expected,
+    case("example.com", StatusCode::OK),
+    case("files.lan:8080", StatusCode::OK),
+    case("localhost", StatusCode::OK),
+    case("192.168.0.1", StatusCode::OK),
+    case("attacker.example", StatusCode::BAD_REQUEST)
+)]
+/// Only requests addressed to allowed or local hosts are answered.
+fn requests_are_checked_against_allowed_hosts(
+    tmpdir: TempDir,
+    port: u16,
+    host: &str,
+    expected: StatusCode,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allowed-hosts")
+        .arg("example.com,files.lan")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let resp = reqwest::blocking::Client::new()
+        .get(format!("http://localhost:{}", port).as_str())
+        .header(HOST, host)
+        .send()?;
+    assert_eq!(resp.status(), expected);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Without allowed hosts, any host is answered.
+fn any_host_is_allowed_by_default(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let resp = reqwest::blocking::Client::new()
+        .get(format!("http://localhost:{}", port).as_str())
+        .header(HOST, "attacker.example")
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-﻿mod fixtures;
+mod fixtures;
 
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
@@ -6,10 +6,13 @@ use fixtures::{port, tmpdir, Error};
 use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
-use select::predicate::Text;
+use select::predicate::{Attr, Text};
+use std::convert::TryInto;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[rstest]
 fn archives_are_disabled(tmpdir: TempDir, port: u16) -> Result<(), Error> {
@@ -92,3 +95,440 @@ fn test_tar_archives(tmpdir: TempDir, port: u16) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[rstest]
+/// Zip archives are streamed and contain the whole directory.
+fn test_zip_archives(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-z")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut body = Vec::new();
+    reqwest::blocking::get(format!("http://localhost:{}/dira/?download=zip", port).as_str())?
+        .error_for_status()?
+        .read_to_end(&mut body)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(body))?;
+
+    let mut content = String::new();
+    archive
+        .by_name("dira/test.txt")?
+        .read_to_string(&mut content)?;
+    assert_eq!(content, "This is dira/test.txt");
+    assert!(archive.by_name("dira/").map_or(false, |dir| dir.is_dir()));
+    assert_eq!(
+        archive.len(),
+        archive
+            .file_names()
+            .filter(|name| name.starts_with("dira/"))
+            .count()
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    query,
+    compressed,
+    case("", false),
+    case("&compression=store", false),
+    case("&compression=0", false),
+    case("&compression=9", true)
+)]
+/// Clients choose whether files in zip archives are compressed.
+fn zip_archives_with_compression(
+    tmpdir: TempDir,
+    port: u16,
+    query: &str,
+    compressed: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-z")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut body = Vec::new();
+    reqwest::blocking::get(
+        format!("http://localhost:{}/dira/?download=zip{}", port, query).as_str(),
+    )?
+    .error_for_status()?
+    .read_to_end(&mut body)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(body))?;
+
+    let mut file = archive.by_name("dira/test.txt")?;
+    let expected = if compressed {
+        zip::CompressionMethod::Deflated
+    } else {
+        zip::CompressionMethod::Stored
+    };
+    assert_eq!(file.compression(), expected);
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    assert_eq!(content, "This is dira/test.txt");
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(query, compressed, case("", false), case("&compression=9", true))]
+/// 7z archives contain the whole directory, with the files deflated if the client asks for it.
+fn test_7z_archives(
+    tmpdir: TempDir,
+    port: u16,
+    query: &str,
+    compressed: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-7z")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut body = Vec::new();
+    reqwest::blocking::get(
+        format!("http://localhost:{}/dira/?download=7z{}", port, query).as_str(),
+    )?
+    .error_for_status()?
+    .read_to_end(&mut body)?;
+    let (packed, header) = read_7z(&body);
+
+    let content = if compressed {
+        let mut content = Vec::new();
+        flate2::read::DeflateDecoder::new(packed).read_to_end(&mut content)?;
+        content
+    } else {
+        packed.to_vec()
+    };
+    assert!(contains(&content, b"This is dira/test.txt"));
+    for name in &["dira", "dira/test.txt"] {
+        let name = name
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert!(contains(header, &name));
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+/// Returns the packed files and the header of the 7z archive `archive`, checking the start
+/// header which points to the header at its end
+fn read_7z(archive: &[u8]) -> (&[u8], &[u8]) {
+    assert_eq!(&archive[..8], b"7z\xbc\xaf\x27\x1c\x00\x04");
+    let start_header = &archive[12..32];
+    assert_eq!(
+        u32::from_le_bytes(archive[8..12].try_into().unwrap()),
+        crc32fast::hash(start_header)
+    );
+
+    let offset = u64::from_le_bytes(start_header[..8].try_into().unwrap()) as usize + 32;
+    let size = u64::from_le_bytes(start_header[8..16].try_into().unwrap()) as usize;
+    assert_eq!(offset + size, archive.len());
+    let header = &archive[offset..];
+    assert_eq!(
+        u32::from_le_bytes(start_header[16..].try_into().unwrap()),
+        crc32fast::hash(header)
+    );
+
+    (&archive[32..offset], header)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[rstest]
+/// Archives of unchanged directories are served from the cache.
+fn archives_are_cached(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let cache_dir = TempDir::new()?;
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .arg("--archive-cache-dir")
+        .arg(cache_dir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let download = || -> Result<Vec<u8>, Error> {
+        let mut body = Vec::new();
+        reqwest::blocking::get(format!("http://localhost:{}/dira/?download=tar", port).as_str())?
+            .error_for_status()?
+            .read_to_end(&mut body)?;
+        Ok(body)
+    };
+    let cached_archives = || -> Result<usize, Error> { Ok(cache_dir.path().read_dir()?.count()) };
+
+    let archive = download()?;
+    assert_eq!(cached_archives()?, 1);
+    assert_eq!(download()?, archive);
+    assert_eq!(cached_archives()?, 1);
+
+    // Changing the directory leads to a new archive
+    sleep(Duration::from_millis(10));
+    std::fs::write(tmpdir.path().join("dira").join("new.txt"), "new")?;
+    assert_ne!(download()?, archive);
+    assert_eq!(cached_archives()?, 2);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Entries matching the exclude patterns are left out of archives, but still listed.
+fn archives_leave_out_excluded_entries(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::create_dir_all(tmpdir.path().join("dira").join("node_modules").join("pkg"))?;
+    std::fs::write(
+        tmpdir
+            .path()
+            .join("dira")
+            .join("node_modules")
+            .join("pkg")
+            .join("index.js"),
+        "",
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .arg("--archive-exclude")
+        .arg("node_modules")
+        .arg("--archive-exclude")
+        .arg("*.mkv")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Text).any(|x| x.text() == "test.mkv"));
+
+    let body =
+        reqwest::blocking::get(format!("http://localhost:{}/dira/?download=tar", port).as_str())?
+            .error_for_status()?;
+    let mut archive = tar::Archive::new(body);
+    let paths = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    assert!(paths.contains(&"dira/test.txt".to_string()));
+    assert!(paths.iter().all(|path| !path.contains("node_modules")));
+    assert!(paths.iter().all(|path| !path.ends_with(".mkv")));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Directories marked by a `.no-archive` file or `--no-archive-path` can't be archived and are
+/// left out of archives of their parents.
+fn archives_of_marked_directories_are_refused(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("dira").join(".no-archive"), "")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .arg("--no-archive-path")
+        .arg("dirb")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    for dir in &["dira", "dirb"] {
+        let body = reqwest::blocking::get(format!("http://localhost:{}/{}/", port, dir).as_str())?
+            .error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        assert!(parsed.find(Text).all(|x| x.text() != "Download .tar"));
+
+        assert_eq!(
+            reqwest::blocking::get(
+                format!("http://localhost:{}/{}/?download=tar", port, dir).as_str()
+            )?
+            .status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/?download=tar", port).as_str())?
+        .error_for_status()?;
+    let mut archive = tar::Archive::new(body);
+    let paths = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    assert!(paths.iter().any(|path| path.contains("dirc/")));
+    assert!(paths
+        .iter()
+        .all(|path| !path.contains("dira") && !path.contains("dirb")));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Archives are sent no faster than `--archive-rate-limit` allows.
+fn archives_are_rate_limited(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .arg("--archive-rate-limit")
+        .arg("4")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let start = Instant::now();
+    let mut body = Vec::new();
+    reqwest::blocking::get(format!("http://localhost:{}/?download=tar", port).as_str())?
+        .error_for_status()?
+        .read_to_end(&mut body)?;
+    let elapsed = start.elapsed();
+
+    // All but the first 4 KiB are held back
+    let expected = Duration::from_secs_f64((body.len() - 4096) as f64 / 4096.0);
+    assert!(body.len() > 8192);
+    assert!(elapsed >= expected);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Archives above the maximum size are refused, others announce their size.
+fn archives_are_limited_in_size(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    std::fs::create_dir_all(tmpdir.path().join("small"))?;
+    std::fs::write(tmpdir.path().join("small").join("a.txt"), "1234")?;
+    std::fs::create_dir_all(tmpdir.path().join("large"))?;
+    std::fs::write(
+        tmpdir.path().join("large").join("a.bin"),
+        vec![0; 2 * 1024 * 1024],
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .arg("--max-archive-size")
+        .arg("1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let resp =
+        reqwest::blocking::get(format!("http://localhost:{}/small/?download=tar", port).as_str())?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["x-estimated-size"], "4");
+
+    let resp =
+        reqwest::blocking::get(format!("http://localhost:{}/large/?download=tar", port).as_str())?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Only the selected entries end up in archives of a selection.
+fn selected_entries_are_archived(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--enable-tar")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Every entry can be selected
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Attr("id", "download-selected"))
+        .next()
+        .is_some());
+    assert!(parsed
+        .find(Attr("name", "entry"))
+        .any(|input| input.attr("value") == Some("test.txt")));
+
+    let client = reqwest::blocking::Client::new();
+    let body = client
+        .post(format!("http://localhost:{}/download?path=/", port).as_str())
+        .form(&[("method", "tar"), ("entry", "test.txt"), ("entry", "dira")])
+        .send()?
+        .error_for_status()?;
+    let mut archive = tar::Archive::new(body);
+
+    // Paths relative to the top-level directory of the archive
+    let paths = archive
+        .entries()?
+        .map(|entry| {
+            let path = entry?.path()?.iter().skip(1).collect::<PathBuf>();
+            Ok(path.to_string_lossy().to_string())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    assert!(paths.contains(&"test.txt".to_string()));
+    assert!(paths.iter().any(|path| path.starts_with("dira/")));
+    assert!(!paths.contains(&"test.html".to_string()));
+    assert!(paths.iter().all(|path| !path.starts_with("dirb")));
+
+    // Entries outside of the directory can't be selected
+    let status = client
+        .post(format!("http://localhost:{}/download?path=/dira", port).as_str())
+        .form(&[("method", "tar"), ("entry", "../test.txt")])
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -2,13 +2,14 @@ mod fixtures;
 
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
 use fixtures::{port, tmpdir, Error, FILES};
 use pretty_assertions::assert_eq;
 use reqwest::blocking::Client;
 use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
-use select::predicate::Text;
+use select::predicate::{Name, Text};
 use std::process::{Command, Stdio};
 use std::thread::sleep;
 use std::time::Duration;
@@ -259,3 +260,43 @@ fn auth_multiple_accounts_wrong_password(
 
     Ok(())
 }
+
+#[rstest]
+/// The public stats page is shown without credentials, unlike the files it sums up.
+fn public_stats_without_auth(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    tmpdir.child("a.txt").write_str("1234")?;
+    tmpdir.child("dir/b.txt").write_str("123456")?;
+    tmpdir.child(".hidden").write_str("12345678")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-a")
+        .arg("testuser:testpassword")
+        .arg("--public-stats")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let status = reqwest::blocking::get(base_url.as_str())?.status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let response = reqwest::blocking::get(format!("{}/__miniserve/stats", base_url).as_str())?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let parsed = Document::from_read(response)?;
+    let values = parsed
+        .find(Name("td"))
+        .map(|td| td.text())
+        .collect::<Vec<_>>();
+    assert_eq!(values[0], "2");
+    assert_eq!(values[1], "10 B");
+
+    child.kill()?;
+
+    Ok(())
+}
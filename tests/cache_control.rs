@@ -0,0 +1,57 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::CACHE_CONTROL;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    path,
+    expected,
+    case("test.txt", Some("no-cache")),
+    case("style.css", Some("max-age=31536000,immutable")),
+    case("dira/nested.css", Some("max-age=31536000,immutable")),
+    case("", None)
+)]
+fn cache_control_is_set_on_files(
+    tmpdir: TempDir,
+    port: u16,
+    path: &str,
+    expected: Option<&str>,
+) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("style.css"), "body {}")?;
+    std::fs::write(tmpdir.path().join("dira").join("nested.css"), "body {}")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--cache-control")
+        .arg("no-cache")
+        .arg("--cache-control")
+        .arg("*.css=max-age=31536000,immutable")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/{}", port, path).as_str())
+        .send()?
+        .error_for_status()?;
+    assert_eq!(
+        res.headers()
+            .get(CACHE_CONTROL)
+            .map(|value| value.to_str().unwrap()),
+        expected
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
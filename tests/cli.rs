@@ -2,6 +2,7 @@ mod fixtures;
 
 use assert_cmd::prelude::*;
 use fixtures::Error;
+use rstest::rstest;
 use std::process::Command;
 use structopt::clap::{crate_name, crate_version, Shell};
 
@@ -53,3 +54,203 @@ fn print_completions_invalid_shell() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+/// Invalid time formats are rejected.
+fn time_format_invalid() -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--time-format")
+        .arg("%Y-%Q")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(value, case("*.css="), case("*.css=no\ncache"), case(""))]
+/// Cache-Control values need a valid value, also for globs.
+fn cache_control_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--cache-control")
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(value, case("200=page.html"), case("404"), case("abc=page.html"))]
+/// Custom error pages are only for error statuses.
+fn error_page_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--error-page")
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(value, case("=text/plain"), case("txt=not a type"))]
+/// MIME maps need an extension and a valid MIME type.
+fn mime_map_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--mime-map")
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(value, case("0"), case("fast"), case("-1K"))]
+/// Rates have to be positive sizes.
+fn rate_limit_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--rate-limit")
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+/// Certificates can't be obtained without a contact address, nor be given twice.
+fn acme_options_invalid() -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--acme-domain")
+        .arg("example.com")
+        .assert()
+        .failure();
+
+    Command::cargo_bin("miniserve")?
+        .arg("--acme-domain")
+        .arg("example.com")
+        .arg("--acme-email")
+        .arg("admin@example.com")
+        .arg("--tls-cert")
+        .arg("cert.pem")
+        .arg("--tls-key")
+        .arg("key.pem")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(
+    value,
+    case("--security-headers=csp=none"),
+    case("--security-headers=hsts"),
+    case("--security-headers=frame-options=\n")
+)]
+/// Security headers have to be known and get valid values.
+fn security_headers_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(
+    option,
+    value,
+    case("--client-body-buffer-size", "0"),
+    case("--client-body-buffer-size", "big"),
+    case("--request-timeout", "-1"),
+    case("--keep-alive", "forever")
+)]
+/// Timeouts have to be numbers of seconds and buffer sizes positive sizes.
+fn connection_settings_invalid(option: &str, value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(option)
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(value, case("miniserve-does-not-exist0"), case("eth 0"), case(""))]
+/// Interfaces have to be addresses or the names of existing network interfaces.
+fn interface_invalid(value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("-i")
+        .arg(value)
+        .arg(".")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+/// A valid configuration passes the check without starting the server.
+fn check_accepts_valid_config() -> Result<(), Error> {
+    let output = Command::cargo_bin("miniserve")?
+        .arg("--check")
+        .arg("--tls-cert")
+        .arg("tests/data/cert.pem")
+        .arg("--tls-key")
+        .arg("tests/data/key.pem")
+        .arg("-i")
+        .arg("127.0.0.1")
+        .arg("tests")
+        .output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains("The configuration is valid"));
+
+    Ok(())
+}
+
+#[rstest(
+    args,
+    case(&["tests/does-not-exist"]),
+    case(&["--index", "does-not-exist.html", "tests"]),
+    case(&["--tls-cert", "tests/data/cert.pem", "--tls-key", "tests/data/missing.pem", "tests"]),
+    case(&["-i", "192.0.2.1", "tests"])
+)]
+/// Missing paths and files, and addresses which can't be bound fail the check.
+fn check_rejects_invalid_config(args: &[&str]) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--check")
+        .args(args)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(
+    option,
+    value,
+    case("--random-route-length", "0"),
+    case("--random-route-length", "1000"),
+    case("--random-route-alphabet", "a"),
+    case("--random-route-alphabet", "abca"),
+    case("--random-route-alphabet", "ab/c")
+)]
+/// Random routes need a sensible length and distinct characters usable in URLs.
+fn random_route_options_invalid(option: &str, value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(option)
+        .arg(value)
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest(source, case("ftp://example.com"), case("example.com"), case("stun"))]
+/// Public IP addresses can only be asked from STUN servers and http(s) URLs.
+fn show_public_url_source_invalid(source: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(format!("--show-public-url={}", source))
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(path, case("/"), case("/dira/"), case("/test.txt"))]
+fn unchanged_responses_are_not_sent_again(
+    tmpdir: TempDir,
+    port: u16,
+    path: &str,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::new();
+    let url = format!("http://localhost:{}{}", port, path);
+    let res = client.get(url.as_str()).send()?.error_for_status()?;
+    let etag = res.headers()[ETAG].clone();
+    let last_modified = res.headers()[LAST_MODIFIED].clone();
+    assert!(!etag.to_str()?.starts_with("W/"));
+
+    let res = client
+        .get(url.as_str())
+        .header(IF_NONE_MATCH, etag.clone())
+        .send()?;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()[ETAG], etag);
+
+    let res = client
+        .get(url.as_str())
+        .header(IF_MODIFIED_SINCE, last_modified)
+        .send()?;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+    let res = client
+        .get(url.as_str())
+        .header(IF_NONE_MATCH, "\"outdated\"")
+        .send()?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn changed_listings_are_sent_again(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::new();
+    let url = format!("http://localhost:{}/dira/", port);
+    let res = client.get(url.as_str()).send()?.error_for_status()?;
+    let etag = res.headers()[ETAG].clone();
+
+    std::fs::write(tmpdir.path().join("dira").join("new.txt"), "new")?;
+
+    let res = client
+        .get(url.as_str())
+        .header(IF_NONE_MATCH, etag.clone())
+        .send()?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_ne!(res.headers()[ETAG], etag);
+
+    child.kill()?;
+
+    Ok(())
+}
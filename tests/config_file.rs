@@ -0,0 +1,217 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, FILES};
+use pretty_assertions::assert_eq;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Name, Text};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+/// Options of the config file are applied.
+fn config_file_options_apply(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let config = tmpdir.path().join("miniserve.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "path = '{}'\nport = {}\nauth = [\"joe:123\", \"bob:456\"]\nupload-files = true\n",
+            tmpdir.path().display(),
+            port
+        ),
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let url = format!("http://localhost:{}", port);
+    let status = Client::new().get(&url).send()?.status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let body = Client::new()
+        .get(&url)
+        .basic_auth("bob", Some("456"))
+        .send()?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("id", "file_submit")).next().is_some());
+    for &file in FILES {
+        assert!(parsed.find(Text).any(|x| x.text() == file));
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Options and paths on the command line take precedence over the config file.
+fn command_line_overrides_config_file(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let config = tmpdir.path().join("miniserve.toml");
+    std::fs::write(
+        &config,
+        "path = '/does/not/exist'\nport = 1\ntitle = 'From the file'\n",
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config)
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--title")
+        .arg("From the command line")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert!(title.contains("From the command line"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[rstest]
+/// Credentials and theming are reloaded from the config file on SIGHUP.
+fn config_file_reloads_on_hangup(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let config = tmpdir.path().join("miniserve.toml");
+    std::fs::write(&config, "auth = 'joe:123'\ncolor-scheme = 'zenburn'\n")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config)
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let url = format!("http://localhost:{}", port);
+    let status = |user: &str, password: &str| -> Result<StatusCode, Error> {
+        Ok(Client::new()
+            .get(&url)
+            .basic_auth(user, Some(password))
+            .send()?
+            .status())
+    };
+    assert_eq!(status("joe", "123")?, StatusCode::OK);
+    assert_eq!(status("bob", "456")?, StatusCode::UNAUTHORIZED);
+
+    std::fs::write(&config, "auth = 'bob:456'\ncolor-scheme = 'monokai'\n")?;
+    Command::new("kill")
+        .arg("-HUP")
+        .arg(child.id().to_string())
+        .assert()
+        .success();
+
+    sleep(Duration::from_secs(1));
+
+    assert_eq!(status("joe", "123")?, StatusCode::UNAUTHORIZED);
+    assert_eq!(status("bob", "456")?, StatusCode::OK);
+    let body = Client::new()
+        .get(&url)
+        .basic_auth("bob", Some("456"))
+        .send()?
+        .error_for_status()?
+        .text()?;
+    assert!(body.contains("default_theme_monokai"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    format,
+    expected,
+    case("toml", &["port = 9000", "auth = \"<redacted>\"", "psk = \"<redacted>\"", "title = \"Shared\"", "color-scheme = \"squirrel\""]),
+    case("json", &["\"port\": 9000", "\"auth\": \"<redacted>\"", "\"psk\": \"<redacted>\"", "\"title\": \"Shared\"", "\"color-scheme\": \"squirrel\""])
+)]
+/// The options in effect, including defaults, are printed in the format asked for, without
+/// the credentials.
+fn print_config_shows_effective_options(
+    tmpdir: TempDir,
+    format: &str,
+    expected: &[&str],
+) -> Result<(), Error> {
+    let config = tmpdir.path().join("miniserve.toml");
+    std::fs::write(
+        &config,
+        "port = 1\nauth = 'joe:123'\npsk = 'hunter2'\ntitle = 'Shared'\n",
+    )?;
+
+    let output = Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config)
+        .arg("-p")
+        .arg("9000")
+        .arg("--print-config")
+        .arg(format)
+        .output()?;
+    assert!(output.status.success());
+
+    let printed = String::from_utf8(output.stdout)?;
+    for line in expected {
+        assert!(printed.contains(line), "{} is missing in {}", line, printed);
+    }
+    assert!(!printed.contains("print-config"));
+    assert!(!printed.contains("joe:123"));
+    assert!(!printed.contains("hunter2"));
+
+    Ok(())
+}
+
+#[rstest(
+    config,
+    case("port = "),
+    case("auth = [[\"joe:123\"]]"),
+    case("[tls]\ncert = 'cert.pem'"),
+    case("config = 'other.toml'"),
+    case("no-such-option = true")
+)]
+/// Config files have to be valid TOML of known options with scalar values.
+fn config_file_invalid(tmpdir: TempDir, config: &str) -> Result<(), Error> {
+    let config_file = tmpdir.path().join("miniserve.toml");
+    std::fs::write(&config_file, config)?;
+
+    Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config_file)
+        .arg(tmpdir.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[rstest]
+/// A missing config file is an error.
+fn config_file_missing(tmpdir: TempDir) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(tmpdir.path().join("missing.toml"))
+        .arg(tmpdir.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
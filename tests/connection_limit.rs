@@ -0,0 +1,58 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+#[rstest(
+    option,
+    expected,
+    case("--max-connections", StatusCode::SERVICE_UNAVAILABLE),
+    case("--max-connections-per-ip", StatusCode::TOO_MANY_REQUESTS)
+)]
+fn connection_limits_reject_requests(
+    tmpdir: TempDir,
+    port: u16,
+    option: &str,
+    expected: StatusCode,
+) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("big.bin"), vec![b'x'; 48 * 1024])?;
+
+    // The rate limit keeps the download going while the other requests are made
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(option)
+        .arg("1")
+        .arg("--rate-limit")
+        .arg("16KiB")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let download = thread::spawn(move || {
+        reqwest::blocking::get(format!("http://localhost:{}/big.bin", port).as_str())
+            .and_then(|res| res.bytes())
+            .map(|body| body.len())
+    });
+    sleep(Duration::from_millis(500));
+
+    let res = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?;
+    assert_eq!(res.status(), expected);
+
+    assert_eq!(download.join().unwrap()?, 48 * 1024);
+
+    // Finished downloads don't count anymore
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use reqwest::{Method, StatusCode};
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    origin,
+    expected,
+    case("https://app.example.com", Some("https://app.example.com")),
+    case("https://other.example.com", None)
+)]
+fn cors_headers_are_set_for_allowed_origins(
+    tmpdir: TempDir,
+    port: u16,
+    origin: &str,
+    expected: Option<&str>,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--cors-allow-origin")
+        .arg("https://app.example.com/")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/test.txt", port).as_str())
+        .header(ORIGIN, origin)
+        .send()?
+        .error_for_status()?;
+    assert_eq!(
+        res.headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|value| value.to_str().unwrap()),
+        expected
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    method,
+    expected,
+    allowed_origin,
+    expected_origin,
+    case("PUT", StatusCode::NO_CONTENT, "*", "*"),
+    case("DELETE", StatusCode::FORBIDDEN, "*", "*"),
+    case(
+        "PUT",
+        StatusCode::NO_CONTENT,
+        "https://app.example.com",
+        "https://app.example.com"
+    )
+)]
+/// Preflights are answered before authentication, for the allowed methods only.
+fn cors_preflight_is_answered(
+    tmpdir: TempDir,
+    port: u16,
+    method: &str,
+    expected: StatusCode,
+    allowed_origin: &str,
+    expected_origin: &str,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--auth")
+        .arg("testuser:testpassword")
+        .arg("--cors-allow-origin")
+        .arg(allowed_origin)
+        .arg("--cors-allow-methods")
+        .arg("get,put")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .request(
+            Method::OPTIONS,
+            format!("http://localhost:{}/upload", port).as_str(),
+        )
+        .header(ORIGIN, "https://app.example.com")
+        .header(ACCESS_CONTROL_REQUEST_METHOD, method)
+        .send()?;
+    assert_eq!(res.status(), expected);
+    if expected == StatusCode::NO_CONTENT {
+        // Credentials are only allowed for origins which were given explicitly
+        assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], expected_origin);
+        assert_eq!(
+            res.headers()
+                .get(ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .map(|value| value.to_str().unwrap()),
+            Some("true").filter(|_| expected_origin != "*")
+        );
+        assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_METHODS], "GET, PUT");
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
+use fixtures::{port, tmpdir, Error};
+use reqwest::header::CONTENT_SECURITY_POLICY;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Name;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Returns the policy and the page of the listing at `url`
+fn get_listing(url: &str) -> Result<(String, Document), Error> {
+    let res = reqwest::blocking::get(url)?.error_for_status()?;
+    let policy = res.headers()[CONTENT_SECURITY_POLICY].to_str()?.to_string();
+    Ok((policy, Document::from(res.text()?.as_str())))
+}
+
+#[rstest]
+fn listing_scripts_have_the_nonce_of_the_policy(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--upload-files")
+        .arg("--qrcode")
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut nonces = vec![];
+    for _ in 0..2 {
+        let (policy, parsed) = get_listing(format!("http://localhost:{}", port).as_str())?;
+        let nonce = policy
+            .split("'nonce-")
+            .nth(1)
+            .and_then(|rest| rest.split('\'').next())
+            .unwrap()
+            .to_string();
+
+        let scripts = parsed.find(Name("script")).collect::<Vec<_>>();
+        assert!(!scripts.is_empty());
+        for script in scripts {
+            assert_eq!(script.attr("nonce"), Some(nonce.as_str()));
+        }
+        nonces.push(nonce);
+    }
+    assert_ne!(nonces[0], nonces[1]);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn csp_can_be_overridden(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--csp")
+        .arg("script-src 'nonce-{nonce}' https://cdn.example.com")
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let (policy, parsed) = get_listing(format!("http://localhost:{}", port).as_str())?;
+    let nonce = parsed
+        .find(Name("script"))
+        .next()
+        .unwrap()
+        .attr("nonce")
+        .unwrap();
+    assert_eq!(
+        policy,
+        format!("script-src 'nonce-{}' https://cdn.example.com", nonce)
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn served_files_have_no_policy(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    tmpdir
+        .child("page.html")
+        .write_str("<script>alert(1)</script>")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = reqwest::blocking::get(format!("http://localhost:{}/page.html", port).as_str())?
+        .error_for_status()?;
+    assert!(!res.headers().contains_key(CONTENT_SECURITY_POLICY));
+
+    let res = reqwest::blocking::get(format!("http://localhost:{}/missing.html", port).as_str())?;
+    assert!(res.headers().contains_key(CONTENT_SECURITY_POLICY));
+
+    child.kill()?;
+
+    Ok(())
+}
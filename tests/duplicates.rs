@@ -0,0 +1,82 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, FILES};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Class, Descendant, Name};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn lists_files_with_the_same_content(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--show-duplicates")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/__miniserve/duplicates", port).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    // Only the files at the root have the same content, the ones in the directories have
+    // the same size but differ
+    assert_eq!(parsed.find(Class("duplicates")).count(), 1);
+    let paths = parsed
+        .find(Descendant(Class("duplicates"), Name("td")))
+        .map(|td| td.text())
+        .collect::<Vec<_>>();
+    assert_eq!(paths.len(), FILES.len());
+    assert!(paths.contains(&"test.txt".to_string()));
+    assert!(!paths.iter().any(|path| path.starts_with(".hidden")));
+
+    let reclaimable = "Test Hello Yes".len() * (FILES.len() - 1);
+    assert!(parsed
+        .find(Name("td"))
+        .any(|td| td.attr("title") == Some(format!("{} B", reclaimable).as_str())));
+
+    // The result is cached instead of scanning again on every request
+    std::fs::write(tmpdir.path().join("copy.txt"), "Test Hello Yes")?;
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/__miniserve/duplicates", port).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(!parsed.find(Name("td")).any(|td| td.text() == "copy.txt"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn duplicates_are_hidden_by_default(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let status = reqwest::blocking::get(
+        format!("http://localhost:{}/__miniserve/duplicates", port).as_str(),
+    )?
+    .status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::WWW_AUTHENTICATE;
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn custom_error_pages_are_shown(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let not_found = tmpdir.path().join("not_found.html");
+    let unauthorized = tmpdir.path().join("unauthorized.html");
+    std::fs::write(&not_found, "<h1>Lost?</h1>")?;
+    std::fs::write(&unauthorized, "<h1>Who are you?</h1>")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--auth")
+        .arg("testuser:testpassword")
+        .arg("--error-page-404")
+        .arg(&not_found)
+        .arg("--error-page")
+        .arg(format!("401={}", unauthorized.display()))
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::new();
+    let res = client
+        .get(format!("http://localhost:{}/missing.txt", port).as_str())
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(res.text()?, "<h1>Lost?</h1>");
+
+    // The challenge of the authentication is kept
+    let res = client
+        .get(format!("http://localhost:{}", port).as_str())
+        .send()?;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert!(res.headers().contains_key(WWW_AUTHENTICATE));
+    assert_eq!(res.text()?, "<h1>Who are you?</h1>");
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Attr;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Writes a zip archive with a file in a directory
+fn write_zip(path: &Path) -> Result<(), Error> {
+    let mut zip = zip::ZipWriter::new(File::create(path)?);
+    zip.add_directory("docs/", Default::default())?;
+    zip.start_file("docs/notes.txt", Default::default())?;
+    zip.write_all(b"zipped notes")?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Writes a gzip-compressed tar archive with a member for each of `members`
+fn write_tar_gz(path: &Path, members: &[&str]) -> Result<(), Error> {
+    let mut tar = tar::Builder::new(GzEncoder::new(File::create(path)?, Compression::default()));
+    for member in members {
+        let content = b"packed notes";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        // `Header::set_path` refuses `..`, so the name is written directly
+        header.as_old_mut().name[..member.len()].copy_from_slice(member.as_bytes());
+        header.set_cksum();
+        tar.append(&header, &content[..])?;
+    }
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[rstest]
+fn extracts_archives_next_to_them(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    write_zip(&tmpdir.path().join("zipped.zip"))?;
+    write_tar_gz(&tmpdir.path().join("packed.tar.gz"), &["notes.txt"])?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-extract")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Only archives can be extracted
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let extractable = parsed
+        .find(Attr("form", "extract-entry"))
+        .filter_map(|button| button.attr("value"))
+        .collect::<Vec<_>>();
+    assert_eq!(extractable.len(), 2);
+    assert!(extractable.contains(&"zipped.zip"));
+    assert!(extractable.contains(&"packed.tar.gz"));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let extract = |name: &str| -> Result<StatusCode, Error> {
+        Ok(client
+            .post(format!("http://localhost:{}/__miniserve/extract?path=/", port).as_str())
+            .form(&[("name", name)])
+            .send()?
+            .status())
+    };
+
+    assert_eq!(extract("zipped.zip")?, StatusCode::SEE_OTHER);
+    assert_eq!(
+        std::fs::read_to_string(tmpdir.path().join("zipped/docs/notes.txt"))?,
+        "zipped notes"
+    );
+    assert_eq!(extract("packed.tar.gz")?, StatusCode::SEE_OTHER);
+    assert_eq!(
+        std::fs::read_to_string(tmpdir.path().join("packed/notes.txt"))?,
+        "packed notes"
+    );
+
+    // Existing directories are never extracted into
+    assert_eq!(extract("zipped.zip")?, StatusCode::CONFLICT);
+    assert_eq!(extract("test.txt")?, StatusCode::BAD_REQUEST);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn archives_leaving_their_directory_are_refused(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    write_tar_gz(
+        &tmpdir.path().join("evil.tar.gz"),
+        &["harmless.txt", "../escaped.txt"],
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-extract")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let res = client
+        .post(format!("http://localhost:{}/__miniserve/extract?path=/", port).as_str())
+        .form(&[("name", "evil.tar.gz")])
+        .send()?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Nothing is left behind, neither inside nor outside of the served directory
+    assert!(!tmpdir.path().join("evil").exists());
+    assert!(!tmpdir.path().join("escaped.txt").exists());
+    assert!(!tmpdir.path().join("..").join("escaped.txt").exists());
+    assert_eq!(
+        std::fs::read_dir(tmpdir.path())?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".extracting"))
+            .count(),
+        0
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Hidden or ignored archives can't be extracted, and members which the listing wouldn't show
+/// are left out.
+fn unlisted_archives_and_members_are_not_extracted(
+    tmpdir: TempDir,
+    port: u16,
+) -> Result<(), Error> {
+    std::fs::write(
+        tmpdir.path().join(".miniserveignore"),
+        "secret.tar.gz\n*.key\n",
+    )?;
+    write_tar_gz(&tmpdir.path().join("secret.tar.gz"), &["notes.txt"])?;
+    write_tar_gz(
+        &tmpdir.path().join(".hidden_dir1/hidden.tar.gz"),
+        &["notes.txt"],
+    )?;
+    write_tar_gz(
+        &tmpdir.path().join("packed.tar.gz"),
+        &["notes.txt", ".env", "server.key"],
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-extract")
+        .arg("--ignore-files")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let extract = |path: &str, name: &str| -> Result<StatusCode, Error> {
+        Ok(client
+            .post(
+                format!(
+                    "http://localhost:{}/__miniserve/extract?path={}",
+                    port, path
+                )
+                .as_str(),
+            )
+            .form(&[("name", name)])
+            .send()?
+            .status())
+    };
+
+    assert_eq!(extract("/", "secret.tar.gz")?, StatusCode::BAD_REQUEST);
+    assert!(!tmpdir.path().join("secret").exists());
+    assert_eq!(
+        extract("/.hidden_dir1", "hidden.tar.gz")?,
+        StatusCode::BAD_REQUEST
+    );
+    assert!(!tmpdir.path().join(".hidden_dir1/hidden").exists());
+
+    assert_eq!(extract("/", "packed.tar.gz")?, StatusCode::SEE_OTHER);
+    assert!(tmpdir.path().join("packed/notes.txt").exists());
+    assert!(!tmpdir.path().join("packed/.env").exists());
+    assert!(!tmpdir.path().join("packed/server.key").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
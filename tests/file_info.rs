@@ -0,0 +1,291 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
+use fixtures::{port, tmpdir, Error};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Class, Name};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn hide_file_info_links(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Class("file-info")).next().is_none());
+
+    let resp = reqwest::blocking::get(
+        format!(
+            "http://localhost:{}/__miniserve/info?path=%2Ftest.txt",
+            port
+        )
+        .as_str(),
+    )?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn show_file_info(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--file-info")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let body =
+        reqwest::blocking::get(format!("{}/dira/", base_url).as_str())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let info_link = parsed
+        .find(Class("file-entry"))
+        .find(|entry| {
+            entry
+                .find(Class("file"))
+                .next()
+                .map_or(false, |link| link.attr("href") == Some("/dira/test.txt"))
+        })
+        .and_then(|entry| entry.find(Class("file-info")).next())
+        .and_then(|link| link.attr("href"))
+        .unwrap()
+        .to_string();
+    assert_eq!(info_link, "/__miniserve/info?path=%2Fdira%2Ftest%2Etxt");
+
+    let body = reqwest::blocking::get(format!("{}{}", base_url, info_link).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let rows = parsed
+        .find(Name("tr"))
+        .map(|row| row.find(Name("td")).next().unwrap().text())
+        .collect::<Vec<_>>();
+    assert!(rows.contains(&"21 B".to_string()));
+    assert!(rows.contains(&"text/plain".to_string()));
+    let checksum_link = parsed
+        .find(Class("checksum"))
+        .next()
+        .and_then(|cell| cell.find(Name("a")).next())
+        .and_then(|link| link.attr("href"))
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        parsed.find(Class("raw")).next().unwrap().attr("href"),
+        Some("/dira/test.txt")
+    );
+
+    let body = reqwest::blocking::get(format!("{}{}", base_url, checksum_link).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let checksum = parsed.find(Class("checksum")).next().unwrap().text();
+    assert_eq!(
+        checksum,
+        "782640fc051faf8fa55bb56a6f19a86e711e3cb58ce2ab2330388d8813d742d1"
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    path,
+    case("%2Fdira"),
+    case("%2Fmissing.txt"),
+    case("%2F..%2Ftest.txt"),
+    case("%2F.hidden_file1"),
+    case("%2F.hidden_dir1%2Ftest.txt")
+)]
+fn file_info_rejects_invalid_paths(tmpdir: TempDir, port: u16, path: &str) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--file-info")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let resp = reqwest::blocking::get(
+        format!("http://localhost:{}/__miniserve/info?path={}", port, path).as_str(),
+    )?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn checksum_column_is_filled_on_demand(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--show-checksums")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let checksum_cell = || -> Result<(Option<String>, Option<String>), Error> {
+        let body =
+            reqwest::blocking::get(format!("{}/dira/", base_url).as_str())?.error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        let row = parsed
+            .find(Class("entry"))
+            .find(|row| {
+                row.find(Class("file"))
+                    .next()
+                    .map_or(false, |link| link.attr("href") == Some("/dira/test.txt"))
+            })
+            .unwrap();
+        let cell = row.find(Class("checksum-cell")).next().unwrap();
+        Ok((
+            cell.find(Name("code")).next().map(|code| code.text()),
+            cell.find(Class("compute-checksum"))
+                .next()
+                .and_then(|button| button.attr("data-src"))
+                .map(str::to_string),
+        ))
+    };
+
+    // Nothing is computed up front
+    let (checksum, checksum_link) = checksum_cell()?;
+    assert_eq!(checksum, None);
+    let checksum_link = checksum_link.unwrap();
+    assert_eq!(
+        checksum_link,
+        "/__miniserve/checksum?path=%2Fdira%2Ftest%2Etxt"
+    );
+
+    let checksum = reqwest::blocking::get(format!("{}{}", base_url, checksum_link).as_str())?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(
+        checksum,
+        "782640fc051faf8fa55bb56a6f19a86e711e3cb58ce2ab2330388d8813d742d1"
+    );
+
+    // Computed checksums are shown from then on, until the file changes
+    assert_eq!(checksum_cell()?, (Some(checksum), None));
+    std::fs::write(tmpdir.path().join("dira").join("test.txt"), "changed")?;
+    assert_eq!(checksum_cell()?, (None, Some(checksum_link)));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// PDFs are served inline and get a preview page embedding them, other files don't.
+fn preview_pdf(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    tmpdir.child("scan.pdf").write_str("%PDF-1.4")?;
+    tmpdir.child("notes.txt").write_str("notes")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--preview")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let resp = reqwest::blocking::get(format!("{}/scan.pdf", base_url).as_str())?;
+    let disposition = resp.headers()["content-disposition"].to_str()?;
+    assert!(disposition.starts_with("inline"));
+    assert!(disposition.contains("scan.pdf"));
+
+    let body = reqwest::blocking::get(base_url.as_str())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let preview_links = parsed
+        .find(Class("preview"))
+        .filter_map(|link| link.attr("href").map(String::from))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        preview_links,
+        vec!["/__miniserve/preview?path=%2Fscan%2Epdf".to_string()]
+    );
+
+    let body = reqwest::blocking::get(format!("{}{}", base_url, preview_links[0]).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let frame = parsed.find(Name("iframe")).next().unwrap();
+    assert_eq!(frame.attr("src"), Some("/scan.pdf"));
+
+    let resp = reqwest::blocking::get(
+        format!("{}/__miniserve/preview?path=%2Fnotes%2Etxt", base_url).as_str(),
+    )?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Files are compressed on the fly when downloaded through their compressed download links.
+fn compressed_downloads(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--compressed-downloads")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let body = reqwest::blocking::get(base_url.as_str())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let gz_link = parsed
+        .find(Class("compressed-download"))
+        .filter_map(|link| link.attr("href"))
+        .find(|href| href.contains("test%2Etxt") && href.ends_with("format=gz"))
+        .map(String::from)
+        .unwrap();
+
+    let resp =
+        reqwest::blocking::get(format!("{}{}", base_url, gz_link).as_str())?.error_for_status()?;
+    let disposition = resp.headers()["content-disposition"].to_str()?;
+    assert!(disposition.contains("test.txt.gz"));
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(resp).read_to_string(&mut content)?;
+    assert_eq!(content, "Test Hello Yes");
+
+    let resp = reqwest::blocking::get(
+        format!("{}/__miniserve/compressed?path=%2Fdira&format=gz", base_url).as_str(),
+    )?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_DISPOSITION, X_CONTENT_TYPE_OPTIONS};
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    option,
+    file,
+    forced,
+    case("--force-download", "test.html", true),
+    case("--force-download", "test.txt", true),
+    case("--force-download=*.html,*.svg", "test.html", true),
+    case("--force-download=*.html,*.svg", "test.txt", false)
+)]
+fn force_download_serves_attachments(
+    tmpdir: TempDir,
+    port: u16,
+    option: &str,
+    file: &str,
+    forced: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(option)
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/{}", port, file).as_str())
+        .send()?
+        .error_for_status()?;
+    let disposition = res.headers()[CONTENT_DISPOSITION].to_str()?;
+    assert_eq!(disposition.starts_with("attachment"), forced);
+    assert!(disposition.contains(file));
+    assert_eq!(res.headers().contains_key(X_CONTENT_TYPE_OPTIONS), forced);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+mod fixtures;
+mod utils;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Name;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+use utils::get_link_from_text;
+
+#[rstest(args, accept_language, lang, parent_link_text,
+    case(vec![], None, "en", "Parent directory"),
+    case(vec![], Some("de-DE,de;q=0.9,en;q=0.8"), "de", "Übergeordnetes Verzeichnis"),
+    case(vec!["--lang", "fr"], Some("de"), "fr", "Dossier parent"),
+)]
+/// The language of the interface is negotiated with the client, unless it's forced.
+fn interface_is_translated(
+    tmpdir: TempDir,
+    port: u16,
+    args: Vec<&str>,
+    accept_language: Option<&str>,
+    lang: &str,
+    parent_link_text: &str,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(args)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut request = Client::new().get(format!("http://localhost:{}/dira/", port).as_str());
+    if let Some(accept_language) = accept_language {
+        request = request.header("Accept-Language", accept_language);
+    }
+    let body = request.send()?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    assert_eq!(
+        parsed.find(Name("html")).next().unwrap().attr("lang"),
+        Some(lang)
+    );
+    assert!(get_link_from_text(&parsed, parent_link_text).is_some());
+
+    child.kill()?;
+
+    Ok(())
+}
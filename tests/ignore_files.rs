@@ -0,0 +1,95 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Text;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(ignore_file, case(".miniserveignore"), case(".gitignore"))]
+/// Entries matched by ignore files are neither listed nor served.
+fn ignored_entries_are_hidden(tmpdir: TempDir, port: u16, ignore_file: &str) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join(ignore_file), "test.txt\ndira/\n")?;
+    std::fs::write(tmpdir.path().join("dirb").join(ignore_file), "*.mkv\n")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--ignore-files")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Text).all(|x| x.text() != "test.txt"));
+    assert!(parsed.find(Text).all(|x| x.text() != "dira/"));
+    assert!(parsed.find(Text).any(|x| x.text() == "test.mkv"));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dirb/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Text).all(|x| x.text() != "test.mkv"));
+    assert!(parsed.find(Text).all(|x| x.text() != "test.txt"));
+    assert!(parsed.find(Text).any(|x| x.text() == "test.html"));
+
+    for path in &["test.txt", "dira/", "dira/test.html", "dirb/test.mkv"] {
+        assert_eq!(
+            reqwest::blocking::get(format!("http://localhost:{}/{}", port, path).as_str())?
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Entries matched by ignore files are left out of archives.
+fn ignored_entries_are_not_archived(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join(".miniserveignore"), "/test.txt\ndira/\n")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--ignore-files")
+        .arg("--enable-tar")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/?download=tar", port).as_str())?
+        .error_for_status()?;
+    let mut archive = tar::Archive::new(body);
+
+    // Paths relative to the top-level directory of the archive
+    let paths = archive
+        .entries()?
+        .map(|entry| {
+            let path = entry?.path()?.iter().skip(1).collect::<PathBuf>();
+            Ok(path.to_string_lossy().to_string())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    assert!(paths.contains(&"test.html".to_string()));
+    assert!(paths.contains(&"dirb/test.txt".to_string()));
+    assert!(!paths.contains(&"test.txt".to_string()));
+    assert!(paths.iter().all(|path| !path.starts_with("dira")));
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    file,
+    expected,
+    case("model.GCODE", "text/x-gcode"),
+    case("module.mjs", "text/javascript"),
+    case("app.wasm", "application/wasm")
+)]
+fn mime_map_overrides_content_type(
+    tmpdir: TempDir,
+    port: u16,
+    file: &str,
+    expected: &str,
+) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join(file), "content")?;
+    let mime_types = tmpdir.path().join("mime.types");
+    std::fs::write(
+        &mime_types,
+        "# Types of modules\nmjs=text/javascript\n\nwasm = application/wasm\n",
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--mime-map")
+        .arg(".gcode=text/x-gcode")
+        .arg("--mime-map")
+        .arg(&mime_types)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/{}", port, file).as_str())
+        .send()?
+        .error_for_status()?;
+    assert_eq!(res.headers()[CONTENT_TYPE], expected);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use rstest::rstest;
+use std::io::Read;
+use std::net::TcpListener;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+#[rstest]
+/// The metadata of requests is forwarded to the collector, without credentials.
+fn requests_are_mirrored(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let collector = TcpListener::bind("127.0.0.1:0")?;
+    let collector_url = format!("http://{}/collect", collector.local_addr()?);
+
+    // Receive a single mirrored request in the background
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let (mut stream, _) = collector.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let mut request = Vec::new();
+        let _ = stream.read_to_end(&mut request);
+        tx.send(String::from_utf8_lossy(&request).to_string())
+            .unwrap();
+    });
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--mirror-requests")
+        .arg(&collector_url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    reqwest::blocking::Client::new()
+        .get(format!("http://localhost:{}/dira/?sort=size", port).as_str())
+        .header("Cookie", "secret=hunter2")
+        .send()?
+        .error_for_status()?;
+
+    let mirrored = rx.recv_timeout(Duration::from_secs(5))?;
+    assert!(mirrored.starts_with("POST /collect"));
+    assert!(mirrored.contains(r#""path":"/dira/""#));
+    assert!(mirrored.contains(r#""query":"sort=size""#));
+    assert!(!mirrored.contains("hunter2"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[test]
+/// Collectors have to be HTTP URLs.
+fn mirror_url_invalid() -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("--mirror-requests")
+        .arg("ftp://example.com")
+        .assert()
+        .failure();
+
+    Ok(())
+}
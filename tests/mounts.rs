@@ -0,0 +1,63 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, DIRECTORIES};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Attr;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn lists_and_serves_several_directories(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path().join("dira"))
+        .arg(tmpdir.path().join("dirb"))
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    for directory in &DIRECTORIES[..2] {
+        let name = directory.trim_end_matches('/');
+        assert!(parsed
+            .find(Attr("href", format!("/{}/", name).as_str()))
+            .next()
+            .is_some());
+    }
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dirb/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("href", "/dirb/test.txt")).next().is_some());
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/test.txt", port).as_str())?
+        .error_for_status()?;
+    assert_eq!(body.text()?, "This is dira/test.txt");
+
+    let status =
+        reqwest::blocking::get(format!("http://localhost:{}/dirc/", port).as_str())?.status();
+    assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn rejects_directories_with_the_same_name(tmpdir: TempDir) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path().join("dira"))
+        .arg(tmpdir.path().join("very/../dira"))
+        .assert()
+        .failure();
+
+    Ok(())
+}
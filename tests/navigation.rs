@@ -3,10 +3,11 @@ mod utils;
 
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
-use fixtures::{port, tmpdir, Error, DEEPLY_NESTED_FILE, DIRECTORIES};
+use fixtures::{port, tmpdir, Error, DEEPLY_NESTED_FILE, DIRECTORIES, FILES};
 use pretty_assertions::{assert_eq, assert_ne};
 use rstest::rstest;
 use select::document::Document;
+use select::predicate::{Class, Name, Predicate};
 use std::process::{Command, Stdio};
 use std::thread::sleep;
 use std::time::Duration;
@@ -221,3 +222,484 @@ fn can_navigate_using_breadcrumbs(
 
     Ok(())
 }
+
+#[rstest]
+/// Listings are split into pages when a page size is set.
+fn listing_is_paginated(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--page-size")
+        .arg("5")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // The root contains all test files, all test directories and the nested directory.
+    let entry_count = FILES.len() + DIRECTORIES.len() + 1;
+    let page_count = (entry_count + 4) / 5;
+
+    let base_url = Url::parse(&format!("http://localhost:{}/", port))?;
+    let mut seen_entries = 0;
+    for page in 1..=page_count {
+        let body = reqwest::blocking::get(base_url.join(&format!("?page={}", page))?)?
+            .error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        let rows = parsed.find(Name("tr").and(Class("entry"))).count();
+        assert!(rows <= 5);
+        seen_entries += rows;
+
+        let next_link = get_link_from_text(&parsed, "Next page");
+        assert_eq!(next_link.is_some(), page < page_count);
+    }
+    assert_eq!(seen_entries, entry_count);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// The pages of a filtered listing, also the ones appended by "Load more", stay filtered.
+fn filtered_listing_is_paginated(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--page-size")
+        .arg("5")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let mut url = Url::parse(&format!("http://localhost:{}/?type=file", port))?;
+    let mut seen_files = 0;
+    loop {
+        let body = reqwest::blocking::get(url.clone())?.error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        assert_eq!(parsed.find(Class("directory")).count(), 0);
+        seen_files += parsed.find(Class("file")).count();
+
+        let load_more = parsed
+            .find(Class("load-more"))
+            .next()
+            .and_then(|link| link.attr("href").map(str::to_string));
+        match get_link_from_text(&parsed, "Next page") {
+            Some(next_link) => {
+                assert_eq!(load_more.as_deref(), Some(next_link.as_str()));
+                assert!(next_link.contains("type=file"));
+                url = url.join(&next_link)?;
+            }
+            None => break,
+        }
+    }
+    assert_eq!(seen_files, FILES.len());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(tree_view, case(true), case(false))]
+/// Directories can only be expanded in place with --tree-view.
+fn tree_view_toggles(tmpdir: TempDir, port: u16, tree_view: bool) -> Result<(), Error> {
+    let mut command = Command::cargo_bin("miniserve")?;
+    command.arg("-p").arg(port.to_string()).arg(tmpdir.path());
+    if tree_view {
+        command.arg("--tree-view");
+    }
+    let mut child = command.stdout(Stdio::null()).spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let toggles = parsed.find(Class("tree-toggle")).count();
+    if tree_view {
+        // All test directories plus the nested directory
+        assert_eq!(toggles, DIRECTORIES.len() + 1);
+    } else {
+        assert_eq!(toggles, 0);
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    mode,
+    expected_status,
+    case("redirect", reqwest::StatusCode::MOVED_PERMANENTLY),
+    case("accept", reqwest::StatusCode::OK),
+    case("strict", reqwest::StatusCode::NOT_FOUND)
+)]
+/// Directories without a trailing slash are handled according to --trailing-slash.
+fn trailing_slash_modes(
+    tmpdir: TempDir,
+    port: u16,
+    mode: &str,
+    expected_status: reqwest::StatusCode,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--trailing-slash")
+        .arg(mode)
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let resp = client
+        .get(format!("http://localhost:{}/very/deeply", port).as_str())
+        .send()?;
+    assert_eq!(resp.status(), expected_status);
+
+    if expected_status == reqwest::StatusCode::OK {
+        // The parent link has to work without the trailing slash, too.
+        let parsed = Document::from_read(resp)?;
+        let back_link =
+            get_link_from_text(&parsed, "Parent directory").expect("Back link not found.");
+        assert_eq!(back_link, "/very/");
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(sort, case("name"), case("natural"))]
+/// Sorting by name takes numbers into account.
+fn sorting_by_name_is_natural(tmpdir: TempDir, port: u16, sort: &str) -> Result<(), Error> {
+    for name in &["file10.txt", "file2.txt", "file1.txt"] {
+        std::fs::write(tmpdir.path().join("dira").join(name), "Test Hello Yes")?;
+    }
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/dira/?sort={}&order=asc", port, sort).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Class("file"))
+        .map(|node| node.text())
+        .filter(|name| name.starts_with("file"))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["file1.txt", "file2.txt", "file10.txt"]);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Entries can be sorted by their extension.
+fn sorting_by_extension(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    for name in &["b.txt", "a.zip", "c.md", "d.txt"] {
+        std::fs::write(tmpdir.path().join("dirb").join(name), "Test Hello Yes")?;
+    }
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/dirb/?sort=extension&order=asc", port).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Class("file"))
+        .map(|node| node.text())
+        .filter(|name| ["b.txt", "a.zip", "c.md", "d.txt"].contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["c.md", "b.txt", "d.txt", "a.zip"]);
+
+    // The sorting can be chosen from the table header
+    assert!(get_link_from_text(&parsed, "Extension").is_some());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// The last chosen sorting is remembered in a cookie.
+fn sorting_is_remembered(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    for name in &["b.txt", "a.zip", "c.md"] {
+        std::fs::write(tmpdir.path().join("dirb").join(name), "Test Hello Yes")?;
+    }
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = reqwest::blocking::Client::new();
+    let resp = client
+        .get(format!("http://localhost:{}/?sort=extension&order=desc", port).as_str())
+        .send()?
+        .error_for_status()?;
+    let cookies = resp
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok()?.split(';').next().map(String::from))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cookies,
+        vec!["miniserve-sort=extension", "miniserve-order=desc"]
+    );
+
+    let body = client
+        .get(format!("http://localhost:{}/dirb/", port).as_str())
+        .header("Cookie", cookies.join("; "))
+        .send()?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Class("file"))
+        .map(|node| node.text())
+        .filter(|name| ["b.txt", "a.zip", "c.md"].contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a.zip", "b.txt", "c.md"]);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// The sorting used when none was chosen can be configured.
+fn default_sort_is_used(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    for name in &["b.txt", "a.zip", "c.md"] {
+        std::fs::write(tmpdir.path().join("dirb").join(name), "Test Hello Yes")?;
+    }
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--default-sort")
+        .arg("extension")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dirb/", port).as_str())?
+        .error_for_status()?;
+    assert!(body.headers().get("set-cookie").is_none());
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Class("file"))
+        .map(|node| node.text())
+        .filter(|name| ["b.txt", "a.zip", "c.md"].contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["c.md", "b.txt", "a.zip"]);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(entry_type, case("file"), case("dir"))]
+/// Listings can be restricted to one type of entries.
+fn listing_is_filtered_by_type(tmpdir: TempDir, port: u16, entry_type: &str) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body =
+        reqwest::blocking::get(format!("http://localhost:{}/?type={}", port, entry_type).as_str())?
+            .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let files = parsed.find(Class("file")).count();
+    let directories = parsed.find(Class("directory")).count();
+
+    if entry_type == "file" {
+        assert_eq!(files, FILES.len());
+        assert_eq!(directories, 0);
+    } else {
+        assert_eq!(files, 0);
+        // The deeply nested file adds another directory
+        assert_eq!(directories, DIRECTORIES.len() + 1);
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Listings can be restricted to entries modified after a point in time.
+fn listing_is_filtered_by_modification_date(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    sleep(Duration::from_secs(1));
+    let since = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    sleep(Duration::from_secs(1));
+    std::fs::write(tmpdir.path().join("new.txt"), "Test Hello Yes")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/?modified-since={}", port, since).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Class("file").or(Class("directory")))
+        .map(|node| node.text())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["new.txt"]);
+
+    let status = reqwest::blocking::get(
+        format!("http://localhost:{}/?modified-since=yesterday", port).as_str(),
+    )?
+    .status();
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Filtering by modification date also lists changes below the directory, by relative path.
+fn listing_by_modification_date_includes_nested_changes(
+    tmpdir: TempDir,
+    port: u16,
+) -> Result<(), Error> {
+    sleep(Duration::from_secs(1));
+    let since = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    sleep(Duration::from_secs(1));
+    let nested_file = DEEPLY_NESTED_FILE.replace("test.rs", "new.txt");
+    std::fs::write(tmpdir.path().join(&nested_file), "Test Hello Yes")?;
+    std::fs::write(tmpdir.path().join(".hidden_dir1/new.txt"), "Test Hello Yes")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(
+        format!("http://localhost:{}/?modified-since={}", port, since).as_str(),
+    )?
+    .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let files = parsed
+        .find(Name("a").and(Class("file")))
+        .map(|node| {
+            (
+                node.text(),
+                node.attr("href").unwrap_or_default().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        vec![(nested_file.clone(), format!("/{}", nested_file))]
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Pagination links keep the modification date filter and the view.
+fn pagination_keeps_modification_date_and_view(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    sleep(Duration::from_secs(1));
+    let since = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    sleep(Duration::from_secs(1));
+    std::fs::write(tmpdir.path().join("new1.txt"), "Test Hello Yes")?;
+    std::fs::write(tmpdir.path().join("new2.txt"), "Test Hello Yes")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--page-size")
+        .arg("1")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = Url::parse(&format!("http://localhost:{}/", port))?;
+    let mut url = base_url.clone();
+    url.query_pairs_mut()
+        .append_pair("modified-since", &since)
+        .append_pair("view", "gallery");
+    let mut names = Vec::new();
+    loop {
+        let body = reqwest::blocking::get(url.clone())?.error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        names.extend(
+            parsed
+                .find(Class("file").or(Class("directory")))
+                .map(|node| node.text()),
+        );
+
+        match get_link_from_text(&parsed, "Next page") {
+            Some(next_link) => {
+                assert!(next_link.contains("view=gallery"));
+                url = base_url.join(&next_link)?;
+                let kept_since = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "modified-since")
+                    .map(|(_, value)| value.into_owned());
+                assert_eq!(kept_since.as_deref(), Some(since.as_str()));
+            }
+            None => break,
+        }
+    }
+    names.sort();
+    assert_eq!(names, vec!["new1.txt", "new2.txt"]);
+
+    child.kill()?;
+
+    Ok(())
+}
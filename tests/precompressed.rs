@@ -0,0 +1,90 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    accept_encoding,
+    expected,
+    case("gzip, deflate, br", Some("br")),
+    case("gzip", Some("gzip")),
+    case("br;q=0, gzip;q=0.5", Some("gzip")),
+    case("identity", None)
+)]
+fn serves_precompressed_siblings(
+    tmpdir: TempDir,
+    port: u16,
+    accept_encoding: &str,
+    expected: Option<&str>,
+) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("app.js"), "plain")?;
+    std::fs::write(tmpdir.path().join("app.js.br"), "brotli")?;
+    std::fs::write(tmpdir.path().join("app.js.gz"), "gzip")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--serve-precompressed")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/app.js", port).as_str())
+        .header(ACCEPT_ENCODING, accept_encoding)
+        .send()?
+        .error_for_status()?;
+    assert_eq!(
+        res.headers()
+            .get(CONTENT_ENCODING)
+            .map(|encoding| encoding.to_str().unwrap()),
+        expected
+    );
+    assert!(res.headers()[CONTENT_TYPE].to_str()?.contains("javascript"));
+    let body = res.text()?;
+    match expected {
+        Some("br") => assert_eq!(body, "brotli"),
+        Some(_) => assert_eq!(body, "gzip"),
+        None => assert_eq!(body, "plain"),
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn precompressed_siblings_are_ignored_by_default(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("app.js"), "plain")?;
+    std::fs::write(tmpdir.path().join("app.js.gz"), "gzip")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/app.js", port).as_str())
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()?
+        .error_for_status()?;
+    assert!(res.headers().get(CONTENT_ENCODING).is_none());
+    assert_eq!(res.text()?, "plain");
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Attr;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    trusted_proxy,
+    trusted,
+    case("127.0.0.1,::1", true),
+    case("10.0.0.0/8", false)
+)]
+/// Absolute links use the scheme and host forwarded by trusted proxies only.
+fn forwarded_headers_of_trusted_proxies_are_used(
+    tmpdir: TempDir,
+    port: u16,
+    trusted_proxy: &str,
+    trusted: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--trusted-proxy")
+        .arg(trusted_proxy)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = Client::new()
+        .get(format!("http://localhost:{}/dira/", port).as_str())
+        .header("X-Forwarded-For", "203.0.113.7")
+        .header("X-Forwarded-Proto", "https")
+        .header("X-Forwarded-Host", "files.example.com")
+        .send()?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let image = parsed
+        .find(Attr("property", "og:image"))
+        .next()
+        .and_then(|meta| meta.attr("content").map(str::to_string))
+        .unwrap();
+
+    let expected = if trusted {
+        "https://files.example.com/".to_string()
+    } else {
+        format!("http://localhost:{}/", port)
+    };
+    assert!(image.starts_with(&expected));
+
+    child.kill()?;
+
+    Ok(())
+}
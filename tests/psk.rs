@@ -0,0 +1,55 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, FILES};
+use rstest::rstest;
+use secrecy::Secret;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(from_env, case(false), case(true))]
+fn downloads_are_encrypted_with_psk(
+    tmpdir: TempDir,
+    port: u16,
+    from_env: bool,
+) -> Result<(), Error> {
+    let mut command = Command::cargo_bin("miniserve")?;
+    command.arg(tmpdir.path()).arg("-p").arg(port.to_string());
+    if from_env {
+        command.env("MINISERVE_PSK", "correct horse battery staple");
+    } else {
+        command.arg("--psk").arg("correct horse battery staple");
+    }
+    let mut child = command.stdout(Stdio::null()).spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Listings are still readable
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+
+    let resp = reqwest::blocking::get(format!("http://localhost:{}/{}", port, FILES[0]).as_str())?
+        .error_for_status()?;
+    assert_eq!(resp.headers()["Content-Type"], "application/octet-stream");
+    let encrypted = resp.bytes()?;
+    assert!(!encrypted.windows(14).any(|w| w == b"Test Hello Yes"));
+
+    let decryptor = match age::Decryptor::new(&encrypted[..])? {
+        age::Decryptor::Passphrase(d) => d,
+        _ => panic!("File was not encrypted with a passphrase"),
+    };
+    let mut decrypted = String::new();
+    decryptor
+        .decrypt(
+            &Secret::new("correct horse battery staple".to_string()),
+            None,
+        )?
+        .read_to_string(&mut decrypted)?;
+    assert_eq!(decrypted, "Test Hello Yes");
+
+    child.kill()?;
+
+    Ok(())
+}
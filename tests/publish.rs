@@ -0,0 +1,71 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
+use fixtures::{port, Error};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+/// The staging directory replaces the served one, which is kept as previous version.
+fn publishes_staging_directory(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    tmpdir.child("site/old.txt").write_str("old")?;
+    tmpdir.child("staging/new.txt").write_str("new")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.child("site").path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--publish-from")
+        .arg(tmpdir.child("staging").path())
+        .arg("--publish-token")
+        .arg("s3cret")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let base_url = format!("http://localhost:{}", port);
+    let status = |file: &str| -> Result<StatusCode, Error> {
+        Ok(reqwest::blocking::get(format!("{}/{}", base_url, file).as_str())?.status())
+    };
+    assert_eq!(status("old.txt")?, StatusCode::OK);
+    assert_eq!(status("new.txt")?, StatusCode::NOT_FOUND);
+
+    let client = Client::new();
+    let publish_url = format!("{}/__miniserve/publish", base_url);
+    let publish = |token: Option<&str>| -> Result<StatusCode, Error> {
+        let mut req = client.post(publish_url.as_str());
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        Ok(req.send()?.status())
+    };
+
+    // Only requests with the token may publish
+    assert_eq!(publish(None)?, StatusCode::UNAUTHORIZED);
+    assert_eq!(publish(Some("wrong"))?, StatusCode::UNAUTHORIZED);
+    assert_eq!(status("old.txt")?, StatusCode::OK);
+
+    assert_eq!(publish(Some("s3cret"))?, StatusCode::OK);
+
+    assert_eq!(status("old.txt")?, StatusCode::NOT_FOUND);
+    assert_eq!(status("new.txt")?, StatusCode::OK);
+    assert!(tmpdir.child("site.previous/old.txt").path().exists());
+    assert!(!tmpdir.child("staging").path().exists());
+
+    // Nothing left to publish
+    assert_eq!(publish(Some("s3cret"))?, StatusCode::CONFLICT);
+    assert_eq!(status("new.txt")?, StatusCode::OK);
+
+    child.kill()?;
+
+    Ok(())
+}
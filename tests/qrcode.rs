@@ -6,7 +6,7 @@ use fixtures::{port, tmpdir, Error};
 use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
-use select::predicate::Attr;
+use select::predicate::{Attr, Class, Name};
 use std::iter::repeat_with;
 use std::process::{Command, Stdio};
 use std::thread::sleep;
@@ -87,3 +87,35 @@ fn get_svg_qrcode(tmpdir: TempDir, port: u16) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[rstest]
+fn get_svg_qrcode_of_file(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-q")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let qr_link = "/__miniserve/qr?path=%2Ftest%2Etxt";
+    assert!(parsed
+        .find(Class("file-qrcode"))
+        .filter_map(|span| span.find(Name("img")).next())
+        .any(|img| img.attr("data-src") == Some(qr_link)));
+
+    let resp = reqwest::blocking::get(format!("http://localhost:{}{}", port, qr_link).as_str())?;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["Content-Type"], "image/svg+xml");
+    assert!(resp.text()?.starts_with("<?xml"));
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use pretty_assertions::assert_eq;
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+/// Generated routes have the length and alphabet asked for, and are kept across restarts.
+fn random_route_is_saved_and_reused(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let route_file = tmpdir.path().join("route");
+
+    let start = || {
+        Command::cargo_bin("miniserve")?
+            .arg(tmpdir.path())
+            .arg("-p")
+            .arg(port.to_string())
+            .arg("--random-route-file")
+            .arg(&route_file)
+            .arg("--random-route-length")
+            .arg("24")
+            .arg("--random-route-alphabet")
+            .arg("xyz")
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(Error::from)
+    };
+
+    let mut child = start()?;
+    sleep(Duration::from_secs(1));
+
+    let route = std::fs::read_to_string(&route_file)?.trim().to_string();
+    assert_eq!(route.len(), 24);
+    assert!(route.chars().all(|c| "xyz".contains(c)));
+
+    let status = |path: &str| -> Result<StatusCode, Error> {
+        Ok(reqwest::blocking::get(format!("http://localhost:{}{}", port, path).as_str())?.status())
+    };
+    assert_eq!(status(&format!("/{}/", route))?, StatusCode::OK);
+    assert_eq!(status("/")?, StatusCode::NOT_FOUND);
+
+    child.kill()?;
+    child.wait()?;
+
+    let mut child = start()?;
+    sleep(Duration::from_secs(1));
+
+    assert_eq!(std::fs::read_to_string(&route_file)?.trim(), route);
+    assert_eq!(status(&format!("/{}/", route))?, StatusCode::OK);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// A route written to the file beforehand is used as it is.
+fn random_route_file_can_be_given(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let route_file = tmpdir.path().join("route");
+    std::fs::write(&route_file, "my-share\n")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--random-route-file")
+        .arg(&route_file)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    reqwest::blocking::get(format!("http://localhost:{}/my-share/", port).as_str())?
+        .error_for_status()?;
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[rstest]
+/// The browser is opened at the served URL, including the random route.
+fn open_shows_random_route(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let route_file = tmpdir.path().join("route");
+    std::fs::write(&route_file, "opened\n")?;
+
+    // Stands in for the browser, which is taken from $BROWSER
+    let opened_file = tmpdir.path().join("opened");
+    let browser = tmpdir.path().join("browser");
+    std::fs::write(
+        &browser,
+        format!("#!/bin/sh\necho \"$1\" > '{}'\n", opened_file.display()),
+    )?;
+    std::fs::set_permissions(&browser, std::fs::Permissions::from_mode(0o755))?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--random-route-file")
+        .arg(&route_file)
+        .arg("--open")
+        .env("BROWSER", &browser)
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    assert_eq!(
+        std::fs::read_to_string(&opened_file)?.trim(),
+        format!("http://127.0.0.1:{}/opened", port)
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
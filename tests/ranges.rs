@@ -0,0 +1,96 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_RANGE, RANGE};
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn serves_several_ranges_as_multipart(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/test.txt", port).as_str())
+        .header(RANGE, "bytes=0-3,5-9")
+        .send()?;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    let content_type = res.headers()[CONTENT_TYPE].to_str()?.to_string();
+    let boundary = content_type
+        .strip_prefix("multipart/byteranges; boundary=")
+        .unwrap();
+
+    let body = res.text()?;
+    assert_eq!(
+        body,
+        format!(
+            "--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-3/14\r\n\r\nTest\r\n\
+             --{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 5-9/14\r\n\r\nHello\r\n\
+             --{b}--\r\n",
+            b = boundary
+        )
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    if_range,
+    expected_status,
+    expected_body,
+    case(None, StatusCode::PARTIAL_CONTENT, "Hello"),
+    case(Some("\"outdated\""), StatusCode::OK, "Test Hello Yes"),
+    case(
+        Some("Thu, 01 Jan 1970 00:00:00 GMT"),
+        StatusCode::OK,
+        "Test Hello Yes"
+    )
+)]
+fn if_range_serves_whole_file_if_changed(
+    tmpdir: TempDir,
+    port: u16,
+    if_range: Option<&str>,
+    expected_status: StatusCode,
+    expected_body: &str,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let url = format!("http://localhost:{}/test.txt", port);
+    let etag = reqwest::blocking::get(url.as_str())?.headers()[ETAG].clone();
+
+    let res = Client::new()
+        .get(url.as_str())
+        .header(RANGE, "bytes=5-9")
+        .header(
+            IF_RANGE,
+            if_range.map_or(etag, |value| value.parse().unwrap()),
+        )
+        .send()?;
+    assert_eq!(res.status(), expected_status);
+    assert_eq!(res.text()?, expected_body);
+
+    child.kill()?;
+
+    Ok(())
+}
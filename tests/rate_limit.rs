@@ -0,0 +1,43 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+#[rstest(option, case("--rate-limit"), case("--rate-limit-total"))]
+fn rate_limit_slows_down_downloads(tmpdir: TempDir, port: u16, option: &str) -> Result<(), Error> {
+    let content = vec![b'x'; 48 * 1024];
+    std::fs::write(tmpdir.path().join("big.bin"), &content)?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(option)
+        .arg("16KiB/s")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let start = Instant::now();
+    let body = reqwest::blocking::get(format!("http://localhost:{}/big.bin", port).as_str())?
+        .error_for_status()?
+        .bytes()?;
+    assert_eq!(body.len(), content.len());
+    // The first 16 KiB are sent right away, the rest takes a second each
+    assert!(start.elapsed() >= Duration::from_millis(1900));
+
+    // Listings aren't limited
+    let start = Instant::now();
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    child.kill()?;
+
+    Ok(())
+}
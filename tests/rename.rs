@@ -0,0 +1,145 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Class};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn rename_is_disabled_by_default(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Class("rename")).next().is_none());
+
+    let status = Client::new()
+        .post(format!("http://localhost:{}/__miniserve/rename?path=/", port).as_str())
+        .form(&[("from", "test.txt"), ("to", "renamed.txt")])
+        .send()?
+        .status();
+    assert_ne!(status, StatusCode::SEE_OTHER);
+    assert!(tmpdir.path().join("test.txt").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn renames_entries(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-rename")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let action = parsed
+        .find(Attr("id", "rename-entry"))
+        .next()
+        .and_then(|form| form.attr("action"))
+        .map(String::from)
+        .unwrap();
+    assert!(parsed
+        .find(Class("rename"))
+        .any(|button| button.attr("data-name") == Some("test.txt")));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let rename = |from: &str, to: &str| -> Result<StatusCode, Error> {
+        Ok(client
+            .post(format!("http://localhost:{}{}", port, action).as_str())
+            .form(&[("from", from), ("to", to)])
+            .send()?
+            .status())
+    };
+
+    assert_eq!(rename("test.txt", "renamed.txt")?, StatusCode::SEE_OTHER);
+    assert!(!tmpdir.path().join("dira/test.txt").exists());
+    assert!(tmpdir.path().join("dira/renamed.txt").exists());
+
+    // Existing entries are never replaced
+    assert_eq!(rename("renamed.txt", "test.html")?, StatusCode::CONFLICT);
+    assert!(tmpdir.path().join("dira/renamed.txt").exists());
+
+    // Entries can't be moved elsewhere or hidden
+    assert_eq!(
+        rename("renamed.txt", "../moved.txt")?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(rename("renamed.txt", ".hidden")?, StatusCode::BAD_REQUEST);
+    // Nor be turned into annotations of the listing
+    assert_eq!(
+        rename("renamed.txt", "HEADER.html")?,
+        StatusCode::BAD_REQUEST
+    );
+    assert!(!tmpdir.path().join("dira/HEADER.html").exists());
+    assert_eq!(rename("../test.txt", "moved.txt")?, StatusCode::BAD_REQUEST);
+    assert!(tmpdir.path().join("dira/renamed.txt").exists());
+    assert!(tmpdir.path().join("test.txt").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Hidden or ignored entries, or entries of hidden directories, can't be made visible by
+/// renaming them.
+fn rejects_renaming_unlisted_entries(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join(".miniserveignore"), "secret\n")?;
+    std::fs::write(tmpdir.path().join("secret"), "Top secret")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-rename")
+        .arg("--ignore-files")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    for (path, from) in &[
+        ("/", "secret"),
+        ("/", ".hidden_file1"),
+        ("/.hidden_dir1", "test.txt"),
+    ] {
+        let status = client
+            .post(format!("http://localhost:{}/__miniserve/rename?path={}", port, path).as_str())
+            .form(&[("from", *from), ("to", "visible")])
+            .send()?
+            .status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    assert!(tmpdir.path().join("secret").exists());
+    assert!(tmpdir.path().join(".hidden_dir1/test.txt").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use rstest::rstest;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest(
+    option,
+    header,
+    expected,
+    case("--security-headers", "x-content-type-options", Some("nosniff")),
+    case("--security-headers", "referrer-policy", Some("no-referrer")),
+    case("--security-headers", "x-frame-options", Some("SAMEORIGIN")),
+    case("--security-headers=frame-options=DENY", "x-frame-options", Some("DENY")),
+    case("--security-headers=referrer-policy=off", "referrer-policy", None),
+    // HSTS is left out over plain HTTP
+    case("--security-headers", "strict-transport-security", None)
+)]
+fn sends_security_headers(
+    tmpdir: TempDir,
+    port: u16,
+    option: &str,
+    header: &str,
+    expected: Option<&str>,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(option)
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    for path in &["", "test.txt", "missing.txt"] {
+        let res = Client::new()
+            .get(format!("http://localhost:{}/{}", port, path).as_str())
+            .send()?;
+        let value = res
+            .headers()
+            .get(header)
+            .map(|value| value.to_str().unwrap());
+        assert_eq!(value, expected);
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn security_headers_keep_custom_headers(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--security-headers")
+        .arg("--header")
+        .arg("Referrer-Policy: same-origin")
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let res = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?;
+    assert_eq!(res.headers()["referrer-policy"], "same-origin");
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -8,6 +8,8 @@ use regex::Regex;
 use rstest::rstest;
 use select::document::Document;
 use select::node::Node;
+use select::predicate::{Attr, Class, Name};
+use std::io::{Read, Write};
 use std::path::Path;
 use std::process::{Command, Stdio};
 use std::thread::sleep;
@@ -280,3 +282,594 @@ fn serves_requests_custom_index_notice(tmpdir: TempDir, port: u16) -> Result<(),
 
     Ok(())
 }
+
+#[rstest]
+fn serves_requests_logging_slow_operations(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--slow-op-threshold=0")
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+
+    child.kill()?;
+    let output = child.wait_with_output().expect("Failed to read stderr");
+    let all_text = String::from_utf8(output.stderr)?;
+
+    // Every operation takes longer than no time at all
+    assert!(all_text.contains("Slow filesystem operation: reading directory"));
+    assert!(all_text.contains("Slow filesystem operation: reading metadata of the entries of"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[rstest]
+fn serves_requests_with_case_collisions(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("File.txt"), "upper")?;
+    std::fs::write(tmpdir.path().join("file.TXT"), "lower")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let download_name = |name: &str| {
+        parsed
+            .find(Class("file"))
+            .find(|link| link.text() == name)
+            .map(|link| link.attr("download").map(str::to_string))
+    };
+
+    // Only the second of the colliding files is saved under another name
+    assert_eq!(download_name("File.txt"), Some(None));
+    assert_eq!(
+        download_name("file.TXT"),
+        Some(Some("file (2).TXT".to_string()))
+    );
+    assert_eq!(download_name("test.txt"), Some(None));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_file_type_icons(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    for &entry in FILES.into_iter().chain(DIRECTORIES) {
+        let node = parsed
+            .find(|x: &Node| x.name().unwrap_or_default() == "a" && x.text() == entry)
+            .next()
+            .unwrap();
+        assert!(node
+            .find(|x: &Node| x.name().unwrap_or_default() == "svg")
+            .next()
+            .is_some());
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_dir_sizes(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    // Hidden files don't count towards the size
+    std::fs::write(tmpdir.path().join("very/deeply/.secret"), "Not counted")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--show-dir-sizes")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Sizes are computed in the background, so the first listing may not contain them yet
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let row = parsed
+        .find(Name("tr"))
+        .find(|row| {
+            row.find(Class("directory"))
+                .any(|link| link.text().trim() == "very/")
+        })
+        .expect("directory missing from listing");
+    let size = row.find(Class("size-cell")).next().unwrap().text();
+    assert_eq!(size.trim(), "34 B");
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_totals(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let totals = parsed.find(Class("totals")).next().unwrap().text();
+    assert_eq!(
+        totals,
+        format!(
+            "{} files, {} directories, {} B total",
+            FILES.len(),
+            // The deeply nested file adds another directory
+            DIRECTORIES.len() + 1,
+            FILES.len() * "Test Hello Yes".len()
+        )
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_copy_link_buttons(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    // Every file, but no directory, can have its link copied
+    for row in parsed.find(Name("tr")) {
+        let is_file = row.find(Class("file")).next().is_some();
+        let has_button = row.find(Class("copy-link")).next().is_some();
+        assert_eq!(is_file, has_button);
+    }
+    assert_eq!(parsed.find(Class("copy-link")).count(), FILES.len());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_filter_input(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let text = body.text()?;
+    let parsed = Document::from(text.as_str());
+
+    let filter = parsed.find(Attr("id", "filter")).next().unwrap();
+    assert_eq!(filter.attr("oninput"), None);
+    assert!(text.contains("function filterEntries"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_link_previews(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let meta = |property: &str| {
+        parsed
+            .find(Attr("property", property))
+            .next()
+            .and_then(|meta| meta.attr("content").map(str::to_string))
+    };
+
+    assert!(meta("og:title").unwrap().ends_with("/dira"));
+    assert!(meta("og:description")
+        .unwrap()
+        .starts_with(&format!("{} files, 0 directories", FILES.len())));
+    assert!(meta("og:image")
+        .unwrap()
+        .starts_with(&format!("http://localhost:{}/", port)));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_custom_time_format(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--time-format=%Y-%m-%dT%H:%M:%S%:z")
+        .arg("--utc")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let re = Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\+00:00$").unwrap();
+    let dates = parsed
+        .find(Class("date-cell"))
+        .filter_map(|cell| cell.find(Name("span")).next())
+        .map(|date| date.text().trim().to_string())
+        .collect::<Vec<_>>();
+    assert!(!dates.is_empty());
+    assert!(dates.iter().all(|date| re.is_match(date)));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(
+    suggest,
+    path,
+    expected,
+    case(false, "/dira/tset.txt", None),
+    case(true, "/dira/tset.txt", Some("/dira/test.txt")),
+    case(true, "/.hidden_dir1/tset.txt", None)
+)]
+fn serves_404_with_suggestions(
+    tmpdir: TempDir,
+    port: u16,
+    suggest: bool,
+    path: &str,
+    expected: Option<&str>,
+) -> Result<(), Error> {
+    let mut command = Command::cargo_bin("miniserve")?;
+    command.arg("-p").arg(port.to_string());
+    if suggest {
+        command.arg("--suggest-on-404");
+    }
+    let mut child = command.arg(tmpdir.path()).stdout(Stdio::null()).spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let resp = reqwest::blocking::get(format!("http://localhost:{}{}", port, path).as_str())?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let parsed = Document::from_read(resp)?;
+
+    let suggestion = parsed
+        .find(Class("suggestion"))
+        .next()
+        .and_then(|suggestion| suggestion.find(Name("a")).next())
+        .and_then(|link| link.attr("href").map(str::to_string));
+    assert_eq!(suggestion.as_deref(), expected);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_cached_listings_until_directory_changes(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--listing-cache=10")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let list_links = || -> Result<Vec<String>, Error> {
+        let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+            .error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        Ok(parsed
+            .find(Class("file"))
+            .filter_map(|link| link.attr("href").map(str::to_string))
+            .collect())
+    };
+
+    let links = list_links()?;
+    assert_eq!(links, list_links()?);
+    assert!(!links.contains(&"/dira/new.txt".to_string()));
+
+    std::fs::write(tmpdir.path().join("dira").join("new.txt"), "new")?;
+    assert!(list_links()?.contains(&"/dira/new.txt".to_string()));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_annotations(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let dir = tmpdir.path().join("dira");
+    std::fs::write(
+        dir.join("HEADER.html"),
+        "<p id=\"header\">Scans of 2021</p>",
+    )?;
+    std::fs::write(dir.join("FOOTER.html"), "<p id=\"footer\">Ask Bob</p>")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let header = parsed.find(Attr("id", "header")).next().unwrap();
+    assert_eq!(header.text(), "Scans of 2021");
+    let footer = parsed.find(Attr("id", "footer")).next().unwrap();
+    assert_eq!(footer.text(), "Ask Bob");
+    assert!(parsed.find(Class("file")).all(|link| {
+        let name = link.text();
+        !name.contains("HEADER.html") && !name.contains("FOOTER.html")
+    }));
+
+    // The root directory has no annotations
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Class("annotation")).next().is_none());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[rstest(show_permissions, case(false), case(true))]
+fn serves_requests_with_permissions(
+    tmpdir: TempDir,
+    port: u16,
+    show_permissions: bool,
+) -> Result<(), Error> {
+    let mut command = Command::cargo_bin("miniserve")?;
+    command.arg("-p").arg(port.to_string());
+    if show_permissions {
+        command.arg("--show-permissions");
+    }
+    let mut child = command.arg(tmpdir.path()).stdout(Stdio::null()).spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    assert_eq!(
+        parsed.find(Class("permissions")).next().is_some(),
+        show_permissions
+    );
+    let modes = parsed
+        .find(Class("permissions-cell"))
+        .filter_map(|cell| cell.find(Class("mode")).next())
+        .map(|mode| mode.text())
+        .collect::<Vec<_>>();
+    if show_permissions {
+        let re = Regex::new(r"^[-d][-r][-w][-x][-r][-w][-x][-r][-w][-x]$").unwrap();
+        assert_eq!(modes.len(), FILES.len() + DIRECTORIES.len() + 1);
+        assert!(modes.iter().all(|mode| re.is_match(mode)));
+        assert!(modes.iter().any(|mode| mode.starts_with('d')));
+    } else {
+        assert!(modes.is_empty());
+    }
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(gallery, case(false), case(true))]
+fn serves_requests_with_gallery_view(
+    tmpdir: TempDir,
+    port: u16,
+    gallery: bool,
+) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("photo.jpg"), "not really a photo")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let query = if gallery { "?view=gallery" } else { "" };
+    let body = reqwest::blocking::get(format!("http://localhost:{}/{}", port, query).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let gallery_links = parsed
+        .find(Class("gallery-item"))
+        .filter_map(|item| item.attr("href"))
+        .collect::<Vec<_>>();
+    let table_links = parsed
+        .find(Class("file"))
+        .filter_map(|link| link.attr("href"))
+        .collect::<Vec<_>>();
+    let toggle = parsed
+        .find(Class("view-toggle"))
+        .next()
+        .and_then(|link| link.attr("href"));
+
+    if gallery {
+        assert_eq!(gallery_links, vec!["/photo.jpg"]);
+        assert!(!table_links.contains(&"/photo.jpg"));
+        assert!(parsed.find(Attr("id", "lightbox")).next().is_some());
+        assert_eq!(toggle, Some("?"));
+    } else {
+        assert!(gallery_links.is_empty());
+        assert!(table_links.contains(&"/photo.jpg"));
+        assert_eq!(toggle, Some("?view=gallery"));
+    }
+    assert!(table_links.contains(&"/test.txt"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_legacy_http_clients(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--legacy-http")
+        .arg("--enable-tar")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // An HTTP/1.0 request without Host header for a body of unknown length
+    let mut stream = std::net::TcpStream::connect(("localhost", port))?;
+    stream.write_all(b"GET /?download=tar HTTP/1.0\r\n\r\n")?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_at(response.find("\r\n\r\n").unwrap());
+
+    assert!(head.lines().next().unwrap().ends_with("200 OK"));
+    assert!(!head.to_lowercase().contains("transfer-encoding"));
+    assert!(body.contains("test.txt"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_index_per_dir(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::write(tmpdir.path().join("dira").join("index.html"), "static site")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--index-per-dir")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dira/", port).as_str())?
+        .error_for_status()?;
+    assert_eq!(body.text()?, "static site");
+
+    // Directories without an index.html are still listed
+    let body = reqwest::blocking::get(format!("http://localhost:{}/dirb/", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Name("table")).next().is_some());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// The Markdown of `--header-text` is rendered above the listing, along with HTML in it.
+fn serves_requests_with_header_text(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--title")
+        .arg("Team X file share")
+        .arg("--header-text")
+        .arg("Contact **ops** about <span id=\"contact\">this share</span>")
+        .arg(tmpdir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert!(title.contains("Team X file share"));
+    let header_text = parsed.find(Class("header-text")).next().unwrap();
+    assert_eq!(
+        header_text.find(Name("strong")).next().unwrap().text(),
+        "ops"
+    );
+    assert_eq!(
+        header_text
+            .find(Attr("id", "contact"))
+            .next()
+            .unwrap()
+            .text(),
+        "this share"
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
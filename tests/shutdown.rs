@@ -0,0 +1,110 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, FILES};
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use rstest::rstest;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Waits up to `timeout` for `child` to exit, returning whether it exited successfully
+fn exited_successfully(child: &mut Child, timeout: Duration) -> Result<Option<bool>, Error> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status.success()));
+        }
+        sleep(Duration::from_millis(100));
+    }
+    Ok(None)
+}
+
+#[rstest]
+/// miniserve exits once the given number of files are downloaded completely, partial downloads
+/// and listings don't count.
+fn shutdown_after_downloads(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--shutdown-after-downloads")
+        .arg("2")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let get = |path: &str, range: Option<&str>| -> Result<(), Error> {
+        let mut req = Client::new().get(&format!("http://localhost:{}{}", port, path));
+        if let Some(range) = range {
+            req = req.header(RANGE, range);
+        }
+        req.send()?.error_for_status()?.text()?;
+        Ok(())
+    };
+    let file = format!("/{}", FILES[0]);
+
+    get("/", None)?;
+    get(&file, Some("bytes=0-1"))?;
+    get(&file, None)?;
+    assert_eq!(
+        exited_successfully(&mut child, Duration::from_secs(1))?,
+        None
+    );
+
+    get(&file, None)?;
+    assert_eq!(
+        exited_successfully(&mut child, Duration::from_secs(10))?,
+        Some(true)
+    );
+
+    Ok(())
+}
+
+#[rstest]
+/// miniserve exits once the given duration has passed.
+fn shutdown_after_duration(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--shutdown-after")
+        .arg("2s")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?.error_for_status()?;
+
+    assert_eq!(
+        exited_successfully(&mut child, Duration::from_secs(10))?,
+        Some(true)
+    );
+
+    Ok(())
+}
+
+#[rstest(
+    option,
+    value,
+    case("--shutdown-after", "0"),
+    case("--shutdown-after", "10x"),
+    case("--shutdown-after", "1.5h"),
+    case("--shutdown-after-downloads", "0"),
+    case("--shutdown-after-downloads", "-1")
+)]
+/// Shutting down needs a positive duration or number of downloads.
+fn shutdown_options_invalid(tmpdir: TempDir, option: &str, value: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(option)
+        .arg(value)
+        .arg(tmpdir.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, DIRECTORIES, FILES};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Name;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+/// Custom templates replace the listing and error pages.
+fn custom_templates_are_used(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let template_dir = TempDir::new()?;
+    std::fs::write(
+        template_dir.path().join("listing.html"),
+        "<ul>{% for entry in entries %}<li>{{ entry.name }}</li>{% endfor %}</ul>",
+    )?;
+    std::fs::write(
+        template_dir.path().join("error.html"),
+        "<h1>{{ status }} {{ reason }}</h1>",
+    )?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--template")
+        .arg(template_dir.path())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let names = parsed
+        .find(Name("li"))
+        .map(|x| x.text())
+        .collect::<Vec<_>>();
+    for file in FILES {
+        assert!(names.contains(&file.to_string()));
+    }
+    for directory in DIRECTORIES {
+        assert!(names.contains(&directory.trim_end_matches('/').to_string()));
+    }
+    assert!(parsed.find(Name("table")).next().is_none());
+
+    let resp = reqwest::blocking::get(format!("http://localhost:{}/missing/", port).as_str())?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let parsed = Document::from_read(resp)?;
+    assert_eq!(
+        parsed.find(Name("h1")).next().unwrap().text(),
+        "404 Not Found"
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Invalid templates are reported at startup.
+fn invalid_templates_are_rejected(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let template_dir = TempDir::new()?;
+    std::fs::write(
+        template_dir.path().join("listing.html"),
+        "{% for entry in entries %}",
+    )?;
+
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--template")
+        .arg(template_dir.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
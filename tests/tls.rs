@@ -0,0 +1,159 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error, FILES};
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, StatusCode};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Text;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Self-signed certificate for localhost and its key
+fn tls_files() -> (&'static Path, &'static Path) {
+    (
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/cert.pem")),
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/key.pem")),
+    )
+}
+
+#[rstest]
+fn serves_requests_over_tls(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let (cert, key) = tls_files();
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--tls-cert")
+        .arg(cert)
+        .arg("--tls-key")
+        .arg(key)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder()
+        .add_root_certificate(Certificate::from_pem(&std::fs::read(cert)?)?)
+        .build()?;
+    let body = client
+        .get(format!("https://localhost:{}", port).as_str())
+        .send()?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    for &file in FILES {
+        assert!(parsed.find(Text).any(|x| x.text() == file));
+    }
+
+    // Plain HTTP isn't served anymore
+    assert!(reqwest::blocking::get(format!("http://localhost:{}", port).as_str()).is_err());
+
+    child.kill()?;
+    let output = child.wait_with_output().expect("Failed to read stdout");
+    let all_text = String::from_utf8(output.stdout)?;
+    assert!(all_text.contains(&format!("https://127.0.0.1:{}", port)));
+
+    Ok(())
+}
+
+#[rstest]
+fn refuses_to_start_with_an_invalid_key(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let (cert, _) = tls_files();
+    let output = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--tls-cert")
+        .arg(cert)
+        .arg("--tls-key")
+        .arg(cert)
+        .output()?;
+
+    let all_text = String::from_utf8(output.stderr)?;
+    assert!(all_text.contains("Failed to set up TLS with"));
+
+    Ok(())
+}
+
+#[rstest]
+fn serves_requests_with_a_self_signed_certificate(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--tls-self-signed")
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // The certificate isn't signed by any CA, so only its fingerprint vouches for it
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    client
+        .get(format!("https://localhost:{}", port).as_str())
+        .send()?
+        .error_for_status()?;
+
+    child.kill()?;
+    let output = child.wait_with_output().expect("Failed to read stdout");
+    let all_text = String::from_utf8(output.stdout)?;
+    assert!(all_text.contains(&format!("https://127.0.0.1:{}", port)));
+    let fingerprint = Regex::new(r"fingerprint \S*([0-9A-F]{2}:){31}[0-9A-F]{2}")?;
+    assert!(fingerprint.is_match(&all_text));
+
+    Ok(())
+}
+
+#[rstest]
+fn redirects_plain_http_to_https(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let http_port = fixtures::port();
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--tls-self-signed")
+        .arg("--redirect-http-port")
+        .arg(http_port.to_string())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let res = client
+        .get(format!("http://localhost:{}/dira/?sort=size", http_port).as_str())
+        .send()?;
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(
+        res.headers()["location"],
+        format!("https://localhost:{}/dira/?sort=size", port).as_str()
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn refuses_to_redirect_without_tls(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let output = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--redirect-http-port")
+        .arg(fixtures::port().to_string())
+        .output()?;
+
+    let all_text = String::from_utf8(output.stderr)?;
+    assert!(all_text.contains("requires serving HTTPS"));
+
+    Ok(())
+}
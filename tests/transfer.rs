@@ -0,0 +1,191 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, Error};
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Attr;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[rstest]
+fn moves_and_copies_selected_entries(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::create_dir(tmpdir.path().join("target"))?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-move")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Entries can be selected even though archives are disabled
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Attr("name", "entry"))
+        .any(|input| input.attr("value") == Some("test.txt")));
+    assert!(parsed
+        .find(Attr("name", "operation"))
+        .any(|button| button.attr("value") == Some("move")));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let transfer = |path: &str, form: &[(&str, &str)]| -> Result<StatusCode, Error> {
+        Ok(client
+            .post(
+                format!(
+                    "http://localhost:{}/__miniserve/transfer?path={}",
+                    port, path
+                )
+                .as_str(),
+            )
+            .form(form)
+            .send()?
+            .status())
+    };
+
+    let status = transfer(
+        "/",
+        &[
+            ("operation", "copy"),
+            ("destination", "/target"),
+            ("entry", "test.txt"),
+            ("entry", "dira"),
+        ],
+    )?;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert!(tmpdir.path().join("test.txt").exists());
+    assert!(tmpdir.path().join("target/test.txt").exists());
+    assert!(tmpdir.path().join("target/dira/test.txt").exists());
+
+    let status = transfer(
+        "/dira",
+        &[
+            ("operation", "move"),
+            ("destination", "/target"),
+            ("entry", "test.html"),
+        ],
+    )?;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert!(!tmpdir.path().join("dira/test.html").exists());
+    assert!(tmpdir.path().join("target/test.html").exists());
+
+    // Existing entries are never replaced, the other entries are still moved
+    let status = transfer(
+        "/dira",
+        &[
+            ("operation", "move"),
+            ("destination", "/target"),
+            ("entry", "test.txt"),
+            ("entry", "test.mkv"),
+        ],
+    )?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(tmpdir.path().join("dira/test.txt").exists());
+    assert!(!tmpdir.path().join("dira/test.mkv").exists());
+
+    // The outcome of each entry is reported
+    let resp = client
+        .post(format!("http://localhost:{}/__miniserve/transfer?path=/dira", port).as_str())
+        .header("Accept", "application/json")
+        .form(&[
+            ("operation", "copy"),
+            ("destination", "/target"),
+            ("entry", "test.txt"),
+            ("entry", "😀.data"),
+        ])
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body = resp.text()?;
+    assert!(body.contains(r#"{"name":"test.txt","done":false,"error":"#));
+    assert!(body.contains(r#"{"name":"😀.data","done":true,"error":null}"#));
+
+    // Entries can't leave the served directory or be moved into themselves
+    let status = transfer(
+        "/",
+        &[
+            ("operation", "move"),
+            ("destination", "/.."),
+            ("entry", "test.txt"),
+        ],
+    )?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = transfer(
+        "/",
+        &[
+            ("operation", "move"),
+            ("destination", "/dira"),
+            ("entry", "dira"),
+        ],
+    )?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(tmpdir.path().join("dira").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+/// Entries of hidden or ignored directories can't be made visible by moving or copying them.
+fn rejects_transfers_of_unlisted_entries(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    std::fs::create_dir(tmpdir.path().join("target"))?;
+    std::fs::write(tmpdir.path().join(".miniserveignore"), "secret\nprivate/\n")?;
+    std::fs::write(tmpdir.path().join("secret"), "Top secret")?;
+    std::fs::create_dir(tmpdir.path().join("private"))?;
+    std::fs::write(tmpdir.path().join("private/notes"), "Private notes")?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--allow-move")
+        .arg("--ignore-files")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let copy = |path: &str, entry: &str, destination: &str| -> Result<StatusCode, Error> {
+        Ok(client
+            .post(
+                format!(
+                    "http://localhost:{}/__miniserve/transfer?path={}",
+                    port, path
+                )
+                .as_str(),
+            )
+            .form(&[
+                ("operation", "copy"),
+                ("destination", destination),
+                ("entry", entry),
+            ])
+            .send()?
+            .status())
+    };
+
+    for (path, entry, destination) in &[
+        (".hidden_dir1", "test.txt", "/target"),
+        ("/", "secret", "/target"),
+        ("/private", "notes", "/target"),
+        ("/", "test.txt", "/private"),
+    ] {
+        assert_eq!(copy(path, entry, destination)?, StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(std::fs::read_dir(tmpdir.path().join("target"))?.count(), 0);
+    assert!(!tmpdir.path().join("private/test.txt").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
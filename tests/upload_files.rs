@@ -3,13 +3,15 @@ mod fixtures;
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
 use fixtures::{port, tmpdir, Error};
+use fs2::FileExt;
 use reqwest::blocking::{multipart, Client};
+use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
 use select::predicate::{Attr, Text};
 use std::process::{Command, Stdio};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[rstest]
 fn uploading_files_works(tmpdir: TempDir, port: u16) -> Result<(), Error> {
@@ -108,3 +110,333 @@ fn uploading_files_is_prevented(tmpdir: TempDir, port: u16) -> Result<(), Error>
 
     Ok(())
 }
+
+#[rstest(
+    file_name,
+    case("HEADER.html"),
+    case("FOOTER.html"),
+    case("header.html")
+)]
+/// Uploads can't plant HTML which would be shown unescaped around the listing.
+fn uploading_annotation_files_is_rejected(
+    tmpdir: TempDir,
+    port: u16,
+    file_name: &str,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let form = multipart::Form::new();
+    let part = multipart::Part::text(
+        "<meta http-equiv=\"refresh\" content=\"0; url=https://evil.example\">",
+    )
+    .file_name(file_name)
+    .mime_str("text/html")?;
+    let form = form.part("file_to_upload", part);
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("http://localhost:{}{}", port, "/upload?path=/").as_str())
+        .multipart(form)
+        .send()?;
+    assert!(!resp.status().is_success());
+    assert!(!tmpdir.path().join(file_name).exists());
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?
+        .text()?;
+    assert!(!body.contains("evil.example"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn uploading_to_a_locked_file_conflicts(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let test_file_name = "test.txt";
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .arg("-o")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // Pretend another upload is currently writing to the file
+    let locked_file = std::fs::File::open(tmpdir.path().join(test_file_name))?;
+    locked_file.lock_exclusive()?;
+
+    let form = multipart::Form::new();
+    let part = multipart::Part::text("this should not be uploaded")
+        .file_name(test_file_name)
+        .mime_str("text/plain")?;
+    let form = form.part("file_to_upload", part);
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("http://localhost:{}{}", port, "/upload?path=/").as_str())
+        .multipart(form)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // The file wasn't touched
+    locked_file.unlock()?;
+    assert_eq!(
+        std::fs::read_to_string(tmpdir.path().join(test_file_name))?,
+        "Test Hello Yes"
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest(json, case(false), case(true))]
+/// A file which fails to upload doesn't keep the other files of the request from being uploaded.
+fn uploading_several_files_reports_each(
+    tmpdir: TempDir,
+    port: u16,
+    json: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    // test.txt already exists and may not be overwritten
+    let form = ["test.txt", "new.txt"]
+        .iter()
+        .fold(multipart::Form::new(), |form, &name| {
+            let part = multipart::Part::text(format!("uploaded {}", name))
+                .file_name(name)
+                .mime_str("text/plain")
+                .unwrap();
+            form.part("file_to_upload", part)
+        });
+
+    let client = Client::new();
+    let mut req = client
+        .post(format!("http://localhost:{}{}", port, "/upload?path=/").as_str())
+        .multipart(form);
+    if json {
+        req = req.header("Accept", "application/json");
+    }
+    let resp = req.send()?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let body = resp.text()?;
+    if json {
+        assert!(body.contains(r#"{"name":"test.txt","uploaded":false,"size":null,"error":"#));
+        assert!(body.contains(r#"{"name":"new.txt","uploaded":true,"size":16,"error":null}"#));
+    } else {
+        let parsed = Document::from_read(body.as_bytes())?;
+        assert!(parsed
+            .find(Text)
+            .any(|x| x.text() == "1 of 2 files could not be uploaded"));
+        assert!(parsed.find(Text).any(|x| x.text() == "new.txt: uploaded"));
+    }
+
+    assert_eq!(
+        std::fs::read_to_string(tmpdir.path().join("test.txt"))?,
+        "Test Hello Yes"
+    );
+    assert_eq!(
+        std::fs::read_to_string(tmpdir.path().join("new.txt"))?,
+        "uploaded new.txt"
+    );
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn creating_empty_files_works(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let test_file_name = "notes.txt";
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let body = reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let create_action = parsed
+        .find(Attr("id", "create-file"))
+        .next()
+        .expect("Couldn't find element with id=create-file")
+        .attr("action")
+        .expect("Create file form doesn't have action attribute")
+        .to_string();
+    let url = format!("http://localhost:{}{}", port, create_action);
+
+    let client = Client::new();
+    client
+        .post(url.as_str())
+        .form(&[("name", test_file_name)])
+        .send()?
+        .error_for_status()?;
+    let created = tmpdir.path().join(test_file_name);
+    assert!(created.is_file());
+    assert_eq!(std::fs::metadata(&created)?.len(), 0);
+
+    // Existing files are never overwritten
+    let res = client
+        .post(url.as_str())
+        .form(&[("name", test_file_name)])
+        .send()?;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Names must not leave the directory
+    let res = client
+        .post(url.as_str())
+        .form(&[("name", "../escaped.txt")])
+        .send()?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(!tmpdir.path().join("..").join("escaped.txt").exists());
+
+    // Nor create annotations of the listing
+    let res = client
+        .post(url.as_str())
+        .form(&[("name", "FOOTER.html")])
+        .send()?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(!tmpdir.path().join("FOOTER.html").exists());
+
+    child.kill()?;
+
+    Ok(())
+}
+
+#[rstest]
+fn uploading_files_larger_than_the_buffer_works(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let test_file_name = "large.txt";
+    let content = "0123456789".repeat(10_000);
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .arg("--client-body-buffer-size")
+        .arg("1KiB")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let form = multipart::Form::new().part(
+        "file_to_upload",
+        multipart::Part::text(content.clone()).file_name(test_file_name),
+    );
+    Client::new()
+        .post(format!("http://localhost:{}/upload?path=/", port).as_str())
+        .multipart(form)
+        .send()?
+        .error_for_status()?;
+
+    let uploaded = std::fs::read_to_string(tmpdir.path().join(test_file_name))?;
+    assert_eq!(uploaded, content);
+
+    child.kill()?;
+
+    Ok(())
+}
+
+/// Reader yielding `chunks` chunks of 1 MiB with a pause before each, like a slow client
+struct SlowReader {
+    chunks: usize,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl std::io::Read for SlowReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.offset == self.chunk.len() {
+            if self.chunks == 0 {
+                return Ok(0);
+            }
+            self.chunks -= 1;
+            self.offset = 0;
+            sleep(Duration::from_millis(100));
+        }
+        let read = buf.len().min(self.chunk.len() - self.offset);
+        buf[..read].copy_from_slice(&self.chunk[self.offset..self.offset + read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+#[rstest]
+/// Other requests are answered promptly while a large file is uploaded slowly.
+fn slow_uploads_dont_block_other_requests(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let chunks = 30;
+    let chunk = vec![0x5a; 1024 * 1024];
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-u")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    sleep(Duration::from_secs(1));
+
+    let reader = SlowReader {
+        chunks,
+        offset: chunk.len(),
+        chunk,
+    };
+    let form = multipart::Form::new().part(
+        "file_to_upload",
+        multipart::Part::reader(reader).file_name("slow.bin"),
+    );
+    let upload = std::thread::spawn(move || {
+        Client::new()
+            .post(format!("http://localhost:{}/upload?path=/", port).as_str())
+            .multipart(form)
+            .send()
+            .map(|res| res.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    for _ in 0..5 {
+        let start = Instant::now();
+        reqwest::blocking::get(format!("http://localhost:{}", port).as_str())?
+            .error_for_status()?;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        sleep(Duration::from_millis(200));
+    }
+
+    assert!(upload.join().unwrap()?.is_success());
+    let uploaded = std::fs::metadata(tmpdir.path().join("slow.bin"))?;
+    assert_eq!(uploaded.len(), (chunks * 1024 * 1024) as u64);
+
+    child.kill()?;
+
+    Ok(())
+}